@@ -0,0 +1,77 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Periodically writes the scene to a recovery file while it has edits that haven't been saved,
+/// so that they can be restored after a crash.
+///
+/// Saving the scene to a file or to the app's storage (which eframe does periodically and on exit)
+/// removes the recovery file, so one that exists is always newer than both.
+#[derive(Debug)]
+pub struct Autosave {
+    /// Where the scene is auto-saved to, `None` when there is nowhere to put it or writing to it
+    /// failed.
+    path: Option<PathBuf>,
+    last_save: Instant,
+    /// Set when the scene is edited, cleared once it has been saved either way.
+    pub dirty: bool,
+}
+
+impl Autosave {
+    pub const INTERVAL: Duration = Duration::from_secs(60);
+
+    pub fn new(path: Option<PathBuf>, time: Instant) -> Self {
+        Self {
+            path,
+            last_save: time,
+            dirty: false,
+        }
+    }
+
+    /// Reads the recovery file left behind by an earlier run, if there is one.
+    pub fn recovery(&self) -> Option<String> {
+        std::fs::read_to_string(self.path.as_ref()?).ok()
+    }
+
+    /// Writes `state` to the recovery file if the scene is dirty and it has been
+    /// [`Autosave::INTERVAL`] since the last save. A failed write turns auto-saving off, so that
+    /// the error is only reported once.
+    pub fn update(&mut self, time: Instant, state: impl FnOnce() -> String) -> Result<(), String> {
+        if !self.dirty || time - self.last_save < Self::INTERVAL {
+            return Ok(());
+        }
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.last_save = time;
+        self.dirty = false;
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, state()));
+        if let Err(e) = result {
+            let error = format!(
+                "Error when auto-saving to '{}', auto-saving is off until the app is restarted: {e}",
+                path.to_string_lossy()
+            );
+            self.path = None;
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Marks the scene as saved to a file, removing the recovery file.
+    pub fn saved(&mut self) {
+        self.dirty = false;
+        self.discard();
+    }
+
+    /// Removes the recovery file.
+    pub fn discard(&self) {
+        if let Some(path) = &self.path {
+            // there is usually no file to remove
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::ui_vector4;
+use cgmath::{InnerSpace, Zero};
 use eframe::egui;
 use math::{Rotor, Transform};
+use rendering::{Projection, RenderMode};
 use serde::{Deserialize, Serialize};
 use std::f32::consts::TAU;
 
@@ -10,8 +13,169 @@ pub struct Camera {
     pub main_rotation: Rotor,
     pub xy_rotation: f32,
 
+    pub render_mode: RenderMode,
+    pub projection: Projection,
+    /// The vertical field of view in radians.
+    pub fov: f32,
+    pub orthographic_height: f32,
+    /// The distances from the camera that objects are drawn between.
+    pub near: f32,
+    pub far: f32,
+    /// The radius of the lens, 0 keeps everything in focus.
+    pub aperture: f32,
+    /// How far in front of the camera things are in focus.
+    pub focus_distance: f32,
+
     pub move_speed: f32,
+    /// How quickly the camera's velocity approaches the direction being moved in, the fraction of
+    /// the difference left after one second is `exp(-acceleration)`.
+    pub acceleration: f32,
+    /// How quickly the camera slows down once no movement key is held, in the same units as
+    /// `acceleration`.
+    pub damping: f32,
     pub rotation_speed: f32,
+    pub mode: CameraMode,
+
+    #[serde(skip)]
+    velocity: cgmath::Vector4<f32>,
+    #[serde(skip)]
+    transition: Option<Transition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// The movement keys fly the camera around.
+    FreeFly,
+    /// The camera stays `distance` away from `pivot` looking at it, so turning the camera swings it
+    /// around the pivot.
+    Orbit {
+        pivot: cgmath::Vector4<f32>,
+        distance: f32,
+    },
+}
+
+/// The keys that move and turn the camera. While ctrl is held the turn keys turn through w
+/// instead, in the xw and zw planes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub forward: egui::Key,
+    pub backward: egui::Key,
+    pub up: egui::Key,
+    pub down: egui::Key,
+    pub right: egui::Key,
+    pub left: egui::Key,
+    pub ana: egui::Key,
+    pub kata: egui::Key,
+    pub turn_right: egui::Key,
+    pub turn_left: egui::Key,
+    pub turn_up: egui::Key,
+    pub turn_down: egui::Key,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            forward: egui::Key::W,
+            backward: egui::Key::S,
+            up: egui::Key::E,
+            down: egui::Key::Q,
+            right: egui::Key::D,
+            left: egui::Key::A,
+            ana: egui::Key::R,
+            kata: egui::Key::F,
+            turn_right: egui::Key::ArrowRight,
+            turn_left: egui::Key::ArrowLeft,
+            turn_up: egui::Key::ArrowUp,
+            turn_down: egui::Key::ArrowDown,
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Lists every binding, clicking one waits for the next key to be pressed and binds it, or
+    /// leaves the binding as it was if that key is escape.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let id = ui.id().with("Rebinding");
+        let mut rebinding = ui.data(|data| data.get_temp::<usize>(id));
+        let pressed = ui.input(|i| {
+            i.events.iter().find_map(|event| match *event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    repeat: false,
+                    ..
+                } => Some(key),
+                _ => None,
+            })
+        });
+
+        egui::Grid::new("Key Bindings").show(ui, |ui| {
+            for (index, (label, key)) in [
+                ("Forward:", &mut self.forward),
+                ("Backward:", &mut self.backward),
+                ("Up:", &mut self.up),
+                ("Down:", &mut self.down),
+                ("Right:", &mut self.right),
+                ("Left:", &mut self.left),
+                ("Ana (+w):", &mut self.ana),
+                ("Kata (-w):", &mut self.kata),
+                ("Turn Right:", &mut self.turn_right),
+                ("Turn Left:", &mut self.turn_left),
+                ("Turn Up:", &mut self.turn_up),
+                ("Turn Down:", &mut self.turn_down),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if rebinding == Some(index)
+                    && let Some(pressed) = pressed
+                {
+                    if pressed != egui::Key::Escape {
+                        *key = pressed;
+                    }
+                    rebinding = None;
+                }
+
+                ui.label(label);
+                let text = if rebinding == Some(index) {
+                    "Press a key..."
+                } else {
+                    key.name()
+                };
+                if ui.button(text).clicked() {
+                    rebinding = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset to Defaults").clicked() {
+            *self = Self::default();
+            rebinding = None;
+        }
+
+        ui.data_mut(|data| match rebinding {
+            Some(index) => data.insert_temp(id, index),
+            None => data.remove::<usize>(id),
+        });
+    }
+}
+
+/// A saved camera position and orientation that the camera can be moved back to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub position: cgmath::Vector4<f32>,
+    pub main_rotation: Rotor,
+    pub xy_rotation: f32,
+}
+
+#[derive(Debug)]
+struct Transition {
+    from: CameraBookmark,
+    to: CameraBookmark,
+    /// How far through the transition the camera is, from 0 to 1.
+    progress: f32,
 }
 
 impl Default for Camera {
@@ -26,14 +190,38 @@ impl Default for Camera {
 }
 
 impl Camera {
+    /// The fraction of `rotation_speed` the camera turns by for every pixel the mouse is dragged.
+    const MOUSE_SENSITIVITY: f32 = 0.002;
+    /// The largest `acceleration` and `damping`, which are fast enough that the camera reaches its
+    /// velocity within a frame.
+    pub const MAX_ACCELERATION: f32 = 1000.0;
+    /// Below this speed a camera that is slowing down stops, so that it doesn't keep creeping
+    /// forever and restarting the accumulation.
+    const STOP_SPEED: f32 = 1e-3;
+
     pub fn new(position: cgmath::Vector4<f32>) -> Self {
         Self {
             position,
             main_rotation: Rotor::identity(),
             xy_rotation: 0.0,
 
+            render_mode: RenderMode::Project,
+            projection: Projection::Perspective,
+            fov: TAU * 0.25,
+            orthographic_height: 4.0,
+            near: 0.0,
+            far: 1000.0,
+            aperture: 0.0,
+            focus_distance: 5.0,
+
             move_speed: 2.0,
+            acceleration: Self::MAX_ACCELERATION,
+            damping: Self::MAX_ACCELERATION,
             rotation_speed: 0.5,
+            mode: CameraMode::FreeFly,
+
+            velocity: cgmath::Vector4::zero(),
+            transition: None,
         }
     }
 
@@ -45,7 +233,16 @@ impl Camera {
         Transform::translation(self.position).then(Transform::from_rotor(self.rotation()))
     }
 
-    pub fn update(&mut self, ts: f32, i: &egui::InputState) {
+    /// Replaces the camera's orientation with `realign` applied to its full rotation, with the xy
+    /// rotation folded into `main_rotation` first so that it works from any orientation.
+    pub fn realign(&mut self, realign: impl FnOnce(Rotor) -> Rotor) {
+        self.transition = None;
+        self.main_rotation = realign(self.rotation()).normalize();
+        self.xy_rotation = 0.0;
+        self.orbit();
+    }
+
+    pub fn update(&mut self, ts: f32, i: &egui::InputState, key_bindings: &KeyBindings) {
         let mut move_speed = self.move_speed;
         let rotation_speed = self.rotation_speed * TAU;
 
@@ -53,80 +250,450 @@ impl Camera {
             move_speed *= 2.0;
         }
 
-        {
-            let forward = self.main_rotation.x();
-            let up = self.main_rotation.y();
-            let right = self.main_rotation.z();
-            let ana = self.main_rotation.w();
-
-            if i.key_down(egui::Key::W) {
-                self.position += forward * move_speed * ts;
-            }
-            if i.key_down(egui::Key::S) {
-                self.position -= forward * move_speed * ts;
-            }
-            if i.key_down(egui::Key::E) {
-                self.position += up * move_speed * ts;
-            }
-            if i.key_down(egui::Key::Q) {
-                self.position -= up * move_speed * ts;
-            }
-            if i.key_down(egui::Key::D) {
-                self.position += right * move_speed * ts;
-            }
-            if i.key_down(egui::Key::A) {
-                self.position -= right * move_speed * ts;
-            }
-            if i.key_down(egui::Key::R) {
-                self.position += ana * move_speed * ts;
-            }
-            if i.key_down(egui::Key::F) {
-                self.position -= ana * move_speed * ts;
+        let mut direction = cgmath::Vector4::zero();
+        if self.mode == CameraMode::FreeFly {
+            for (key, axis) in [
+                (key_bindings.forward, self.main_rotation.x()),
+                (key_bindings.backward, -self.main_rotation.x()),
+                (key_bindings.up, self.main_rotation.y()),
+                (key_bindings.down, -self.main_rotation.y()),
+                (key_bindings.right, self.main_rotation.z()),
+                (key_bindings.left, -self.main_rotation.z()),
+                (key_bindings.ana, self.main_rotation.w()),
+                (key_bindings.kata, -self.main_rotation.w()),
+            ] {
+                if i.key_down(key) {
+                    direction += axis;
+                }
             }
         }
 
+        // the velocity eases exponentially towards the target, which doesn't depend on the framerate
+        let rate = if direction == cgmath::Vector4::zero() {
+            self.damping
+        } else {
+            self.acceleration
+        };
+        self.velocity += (direction * move_speed - self.velocity) * (1.0 - (-rate * ts).exp());
+        if direction == cgmath::Vector4::zero() && self.velocity.magnitude() < Self::STOP_SPEED {
+            self.velocity = cgmath::Vector4::zero();
+        }
+        if self.mode == CameraMode::FreeFly {
+            self.position += self.velocity * ts;
+        } else {
+            self.velocity = cgmath::Vector4::zero();
+        }
+
         if i.modifiers.ctrl {
-            if i.key_down(egui::Key::ArrowRight) {
+            if i.key_down(key_bindings.turn_right) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_xw(rotation_speed * ts));
             }
-            if i.key_down(egui::Key::ArrowLeft) {
+            if i.key_down(key_bindings.turn_left) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_xw(-rotation_speed * ts));
             }
 
-            if i.key_down(egui::Key::ArrowUp) {
+            if i.key_down(key_bindings.turn_up) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_zw(rotation_speed * ts));
             }
-            if i.key_down(egui::Key::ArrowDown) {
+            if i.key_down(key_bindings.turn_down) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_zw(-rotation_speed * ts));
             }
         } else {
-            if i.key_down(egui::Key::ArrowRight) {
+            if i.key_down(key_bindings.turn_right) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_xz(rotation_speed * ts));
             }
-            if i.key_down(egui::Key::ArrowLeft) {
+            if i.key_down(key_bindings.turn_left) {
                 self.main_rotation = self
                     .main_rotation
                     .then(Rotor::rotate_xz(-rotation_speed * ts));
             }
 
-            if i.key_down(egui::Key::ArrowUp) {
+            if i.key_down(key_bindings.turn_up) {
                 self.xy_rotation += rotation_speed * ts;
             }
-            if i.key_down(egui::Key::ArrowDown) {
+            if i.key_down(key_bindings.turn_down) {
                 self.xy_rotation -= rotation_speed * ts;
             }
         }
 
         self.xy_rotation = self.xy_rotation.clamp(-TAU * 0.25, TAU * 0.25);
+        // rounding errors from composing a rotation every frame would otherwise slowly skew the
+        // camera's axes
+        self.main_rotation = self.main_rotation.normalize();
+        self.orbit();
+    }
+
+    /// Moves an orbiting camera towards or away from its pivot, scrolling up moves it closer.
+    pub fn zoom(&mut self, scroll: f32) {
+        if let CameraMode::Orbit { distance, .. } = &mut self.mode {
+            *distance = (*distance * (-scroll * Self::ZOOM_SENSITIVITY).exp()).max(1e-3);
+        }
+        self.orbit();
+    }
+
+    /// Puts an orbiting camera back at its distance from the pivot, behind it along the view
+    /// direction.
+    fn orbit(&mut self) {
+        if let CameraMode::Orbit { pivot, distance } = self.mode {
+            self.position = pivot - self.rotation().x() * distance;
+        }
+    }
+
+    /// Edits the components of `main_rotation` directly. An edit that would leave the rotation
+    /// with no magnitude is undone, since there would be no orientation left to normalize.
+    pub fn rotor_ui(&mut self, ui: &mut egui::Ui) {
+        let previous = self.main_rotation;
+        let Rotor {
+            s,
+            e1e2,
+            e1e3,
+            e1e4,
+            e2e3,
+            e2e4,
+            e3e4,
+            e1e2e3e4,
+        } = &mut self.main_rotation;
+        egui::Grid::new("Camera Rotor").show(ui, |ui| {
+            for (label, component) in [
+                ("S:", s),
+                ("XY:", e1e2),
+                ("XZ:", e1e3),
+                ("XW:", e1e4),
+                ("YZ:", e2e3),
+                ("YW:", e2e4),
+                ("ZW:", e3e4),
+                ("XYZW:", e1e2e3e4),
+            ] {
+                ui.label(label);
+                ui.add(egui::DragValue::new(component).speed(0.01));
+                ui.end_row();
+            }
+        });
+        let magnitude = self.main_rotation.magnitude();
+        if !magnitude.is_finite() || magnitude < 1e-6 {
+            self.main_rotation = previous;
+        }
+        if ui
+            .button("Normalize")
+            .on_hover_text("Turning the camera normalizes the rotor as well")
+            .clicked()
+        {
+            self.main_rotation = self.main_rotation.normalize();
+        }
+        self.orbit();
+    }
+
+    pub fn mode_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Mode:");
+            let orbiting = matches!(self.mode, CameraMode::Orbit { .. });
+            egui::ComboBox::new("Camera Mode", "")
+                .selected_text(if orbiting { "Orbit" } else { "Free Fly" })
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(!orbiting, "Free Fly").clicked() {
+                        self.mode = CameraMode::FreeFly;
+                    }
+                    if ui.selectable_label(orbiting, "Orbit").clicked() && !orbiting {
+                        self.mode = CameraMode::Orbit {
+                            pivot: self.position
+                                + self.rotation().x() * Self::DEFAULT_ORBIT_DISTANCE,
+                            distance: Self::DEFAULT_ORBIT_DISTANCE,
+                        };
+                    }
+                });
+        });
+        if let CameraMode::Orbit { pivot, distance } = &mut self.mode {
+            ui.horizontal(|ui| {
+                ui.label("Pivot:");
+                ui_vector4(ui, pivot);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Distance:");
+                ui.add(egui::DragValue::new(distance).speed(0.1));
+                *distance = distance.max(1e-3);
+            })
+            .response
+            .on_hover_text("Scroll over a view to change the distance");
+            self.orbit();
+        }
+    }
+
+    /// How much the orbit distance changes for every point scrolled, as a power of e.
+    const ZOOM_SENSITIVITY: f32 = 0.002;
+    /// How far in front of the camera the pivot is put when switching to orbiting.
+    const DEFAULT_ORBIT_DISTANCE: f32 = 5.0;
+
+    /// How much room is left around an object framed by [`Camera::focus`].
+    const FOCUS_MARGIN: f32 = 1.5;
+
+    /// How long moving to a bookmark takes in seconds.
+    const TRANSITION_DURATION: f32 = 0.5;
+
+    pub fn bookmark(&self, name: String) -> CameraBookmark {
+        CameraBookmark {
+            name,
+            position: self.position,
+            main_rotation: self.main_rotation,
+            xy_rotation: self.xy_rotation,
+        }
+    }
+
+    /// Starts smoothly moving the camera to `bookmark`, see [`Camera::update_transition`].
+    pub fn go_to_bookmark(&mut self, bookmark: &CameraBookmark) {
+        self.transition = Some(Transition {
+            from: self.bookmark(String::new()),
+            to: bookmark.clone(),
+            progress: 0.0,
+        });
+    }
+
+    /// Advances the camera towards the bookmark it is moving to, if there is one.
+    pub fn update_transition(&mut self, ts: f32) {
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+        transition.progress = (transition.progress + ts / Self::TRANSITION_DURATION).min(1.0);
+
+        let t = transition.progress;
+        // smoothstep, so the camera eases in and out
+        let t = t * t * (3.0 - 2.0 * t);
+        let Transition { from, to, .. } = transition;
+        self.position = from.position + (to.position - from.position) * t;
+        self.main_rotation = from.main_rotation.slerp(to.main_rotation, t);
+        self.xy_rotation = from.xy_rotation + (to.xy_rotation - from.xy_rotation) * t;
+
+        if transition.progress >= 1.0 {
+            self.main_rotation = to.main_rotation;
+            self.transition = None;
+        }
+
+        // an orbiting camera keeps its distance, so its pivot moves along with it
+        let forward = self.rotation().x();
+        if let CameraMode::Orbit { pivot, distance } = &mut self.mode {
+            *pivot = self.position + forward * *distance;
+        }
+    }
+
+    /// Turns the camera to look straight at `target` and moves it back along its new view direction
+    /// until a hypersphere of `radius` around `target` fits in the view.
+    ///
+    /// Looking straight at the target puts it in the 3d slice that every view shows, in slice mode
+    /// the slice is moved to the target's w coordinate instead.
+    pub fn focus(&mut self, target: cgmath::Vector4<f32>, radius: f32) {
+        self.transition = None;
+
+        let mut direction = target - self.position;
+        if let RenderMode::Slice { w } = &mut self.render_mode {
+            *w = target.w;
+            direction.w = 0.0;
+        }
+        if direction.magnitude2() > 0.0 {
+            self.main_rotation =
+                Rotor::from_two_directions(self.rotation().x(), direction).then(self.main_rotation);
+        }
+
+        let radius = radius.max(1e-3) * Self::FOCUS_MARGIN;
+        let distance = match self.projection {
+            Projection::Perspective | Projection::Stereographic { .. } => {
+                radius / (self.fov * 0.5).sin()
+            }
+            Projection::Orthographic => {
+                self.orthographic_height = radius * 2.0;
+                radius * 2.0
+            }
+        };
+        self.position = target - self.rotation().x() * distance;
+        if let CameraMode::Orbit {
+            pivot,
+            distance: orbit_distance,
+        } = &mut self.mode
+        {
+            *pivot = target;
+            *orbit_distance = distance;
+        }
+    }
+
+    /// Sets the focus distance to how far `target` is in front of the camera.
+    pub fn focus_on(&mut self, target: cgmath::Vector4<f32>) {
+        let mut direction = target - self.position;
+        if let RenderMode::Slice { .. } = self.render_mode {
+            direction.w = 0.0;
+        }
+        self.focus_distance = direction.dot(self.rotation().x()).max(0.0);
+    }
+
+    /// The point `distance` along the view direction from the camera. In slice mode it is kept in
+    /// the slice, so that it is always somewhere the main view can show.
+    pub fn point_ahead(&self, distance: f32) -> cgmath::Vector4<f32> {
+        let mut forward = self.rotation().x();
+        if let RenderMode::Slice { w } = self.render_mode {
+            forward.w = 0.0;
+            // looking straight along w there is no direction within the slice to go in
+            let mut point = if forward.magnitude2() > 1e-12 {
+                self.position + forward.normalize_to(distance)
+            } else {
+                self.position
+            };
+            point.w = w;
+            return point;
+        }
+        self.position + forward * distance
+    }
+
+    /// Turns the camera to look straight at `target` without moving it, rolled so that its up
+    /// direction is as close to world +y as it can be. Nothing happens when `target` is at the
+    /// camera's position, since there is no direction to look in.
+    ///
+    /// An orbiting camera starts orbiting around `target`, and in slice mode the slice is moved to
+    /// the target's w coordinate like in [`Camera::focus`].
+    pub fn look_at(&mut self, target: cgmath::Vector4<f32>) {
+        let mut direction = target - self.position;
+        if let RenderMode::Slice { .. } = self.render_mode {
+            direction.w = 0.0;
+        }
+        if direction.magnitude2() < 1e-12 {
+            return;
+        }
+        self.transition = None;
+        if let RenderMode::Slice { w } = &mut self.render_mode {
+            *w = target.w;
+        }
+
+        let forward = direction.normalize();
+        let mut rotation =
+            Rotor::from_two_directions(self.rotation().x(), forward).then(self.rotation());
+        // rolling around the view direction only turns the up direction within the directions
+        // perpendicular to it, so the closest it can get to +y is the part of +y perpendicular to
+        // the view direction, which there is none of when looking straight up or down
+        let up = cgmath::Vector4::unit_y() - forward * forward.y;
+        if up.magnitude2() > 1e-6 {
+            if rotation.y().dot(up) < 0.0 {
+                // the shortest rotation between opposite directions could be in any plane, a
+                // quarter turn towards the right first keeps it away from the view direction
+                rotation = Rotor::from_two_directions(rotation.y(), rotation.z()).then(rotation);
+            }
+            rotation = Rotor::from_two_directions(rotation.y(), up).then(rotation);
+        }
+        self.main_rotation = rotation.normalize();
+        self.xy_rotation = 0.0;
+
+        if let CameraMode::Orbit { pivot, distance } = &mut self.mode {
+            *pivot = target;
+            *distance = direction.magnitude();
+        }
+    }
+
+    /// Turns the camera by a mouse drag of `delta` pixels the same way the arrow keys do, holding
+    /// ctrl rotates through the w axis instead.
+    pub fn mouse_look(&mut self, delta: egui::Vec2, ctrl: bool) {
+        let rotation = self.rotation_speed * TAU * Self::MOUSE_SENSITIVITY;
+        if ctrl {
+            self.main_rotation = self
+                .main_rotation
+                .then(Rotor::rotate_xw(delta.x * rotation))
+                .then(Rotor::rotate_zw(-delta.y * rotation));
+        } else {
+            self.main_rotation = self
+                .main_rotation
+                .then(Rotor::rotate_xz(delta.x * rotation));
+            self.xy_rotation -= delta.y * rotation;
+        }
+
+        self.xy_rotation = self.xy_rotation.clamp(-TAU * 0.25, TAU * 0.25);
+        self.main_rotation = self.main_rotation.normalize();
+        self.orbit();
+    }
+}
+
+pub fn bookmarks_ui(ui: &mut egui::Ui, camera: &mut Camera, bookmarks: &mut Vec<CameraBookmark>) {
+    if ui.button("Save Bookmark").clicked() {
+        bookmarks.push(camera.bookmark(format!("Bookmark {}", bookmarks.len() + 1)));
+    }
+
+    let mut to_delete = None;
+    for (index, bookmark) in bookmarks.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            if ui.button("Go").clicked() {
+                camera.go_to_bookmark(bookmark);
+            }
+            ui.text_edit_singleline(&mut bookmark.name);
+            if ui.button("Delete").clicked() {
+                to_delete = Some(index);
+            }
+        });
+    }
+    if let Some(index) = to_delete {
+        bookmarks.remove(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn assert_orthonormal(rotor: Rotor) {
+        let axes = [rotor.x(), rotor.y(), rotor.z(), rotor.w()];
+        for (i, a) in axes.into_iter().enumerate() {
+            for (j, b) in axes.into_iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert_abs_diff_eq!(a.dot(b), expected, epsilon = 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn turning_keeps_the_axes_orthonormal() {
+        let key_bindings = KeyBindings::default();
+        let mut camera = Camera::default();
+        let mut input = egui::InputState::default();
+        input.keys_down.insert(key_bindings.turn_right);
+        input.keys_down.insert(key_bindings.turn_up);
+        // a minute of turning at a thousand frames per second, half of it through w
+        for frame in 0..60_000 {
+            input.modifiers.ctrl = frame % 2 == 0;
+            camera.update(0.001, &input, &key_bindings);
+        }
+        assert_orthonormal(camera.main_rotation);
+        assert_orthonormal(camera.rotation());
+    }
+
+    /// A camera looking up at an angle while turned through w, so none of its axes line up with
+    /// the world's.
+    fn tilted_camera() -> Camera {
+        Camera {
+            main_rotation: Rotor::rotate_xz(0.7).then(Rotor::rotate_yw(0.3)),
+            xy_rotation: 0.4,
+            ..Camera::default()
+        }
+    }
+
+    #[test]
+    fn realigning_from_a_tilted_camera() {
+        let mut camera = tilted_camera();
+        let before = camera.rotation();
+        // "Rotate to WYZ" looks along the old ana direction, keeping up and right
+        camera.realign(|rotation| rotation.then(Rotor::rotate_xw(0.25 * TAU)));
+        let after = camera.rotation();
+        assert_eq!(camera.xy_rotation, 0.0);
+        assert_abs_diff_eq!(after.x(), before.w(), epsilon = 1e-5);
+        assert_abs_diff_eq!(after.y(), before.y(), epsilon = 1e-5);
+        assert_abs_diff_eq!(after.z(), before.z(), epsilon = 1e-5);
+        assert_abs_diff_eq!(after.w(), -before.x(), epsilon = 1e-5);
+
+        let mut camera = tilted_camera();
+        camera.realign(|_| Rotor::identity());
+        assert_eq!(camera.xy_rotation, 0.0);
+        assert_abs_diff_eq!(camera.rotation(), Rotor::identity(), epsilon = 1e-6);
     }
 }
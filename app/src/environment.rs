@@ -0,0 +1,183 @@
+use crate::ui_vector4;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    pub sky_zenith: cgmath::Vector3<f32>,
+    pub sky_horizon: cgmath::Vector3<f32>,
+    pub sky_direction: cgmath::Vector4<f32>,
+    /// An equirectangular image shown as the sky in place of the gradient, see
+    /// [`rendering::EnvironmentMap`] for how it is wrapped around the 4d sky.
+    pub environment_map: Option<PathBuf>,
+    /// The direction towards the sun, which lights the whole scene evenly.
+    pub sun_direction: cgmath::Vector4<f32>,
+    pub sun_color: cgmath::Vector3<f32>,
+    pub sun_intensity: f32,
+    pub fog_color: cgmath::Vector3<f32>,
+    /// How far from the camera the fog starts.
+    pub fog_start: f32,
+    /// How quickly surfaces past `fog_start` fade into the fog, 0 turns it off.
+    pub fog_density: f32,
+    /// How quickly surfaces fade to a dim gray the further they are from the camera in w, in
+    /// projection mode. 0 turns it off.
+    pub w_falloff: f32,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            sky_zenith: cgmath::Vector3 {
+                x: 0.4,
+                y: 0.5,
+                z: 0.8,
+            },
+            sky_horizon: cgmath::Vector3 {
+                x: 0.2,
+                y: 0.2,
+                z: 0.3,
+            },
+            sky_direction: cgmath::Vector4 {
+                x: 0.0,
+                y: 1.0,
+                z: 0.0,
+                w: 0.0,
+            },
+            environment_map: None,
+            sun_direction: cgmath::Vector4 {
+                x: -0.1,
+                y: 1.0,
+                z: 0.3,
+                w: 0.1,
+            },
+            sun_color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            sun_intensity: 1.0,
+            fog_color: cgmath::Vector3 {
+                x: 0.2,
+                y: 0.2,
+                z: 0.3,
+            },
+            fog_start: 0.0,
+            fog_density: 0.0,
+            w_falloff: 0.0,
+        }
+    }
+}
+
+/// Loads an image to use as an environment map, as linear RGBA. Images with 8 bit channels are
+/// stored as sRGB and are converted, HDR images are already linear. Images larger than
+/// `max_size` in either direction are scaled down to fit.
+pub fn load_environment_map(
+    path: &Path,
+    max_size: u32,
+) -> Result<(u32, u32, Vec<[f32; 4]>), String> {
+    let mut map = image::open(path).map_err(|e| {
+        format!(
+            "Error when loading environment map '{}': {e}",
+            path.to_string_lossy()
+        )
+    })?;
+    if map.width() > max_size || map.height() > max_size {
+        map = map.resize(max_size, max_size, image::imageops::FilterType::Triangle);
+    }
+    let srgb = !matches!(
+        map,
+        image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+    );
+    let map = map.into_rgba32f();
+    let pixels = map
+        .pixels()
+        .map(|&image::Rgba([r, g, b, a])| {
+            if srgb {
+                [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a]
+            } else {
+                [r, g, b, a]
+            }
+        })
+        .collect();
+    Ok((map.width(), map.height(), pixels))
+}
+
+fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Returns whether the button to pick an environment map was clicked.
+pub fn environment_ui(ui: &mut egui::Ui, environment: &mut Environment) -> bool {
+    let mut pick_environment_map = false;
+    ui.horizontal(|ui| {
+        ui.label("Sky Zenith:");
+        ui.color_edit_button_rgb(environment.sky_zenith.as_mut());
+    });
+    ui.horizontal(|ui| {
+        ui.label("Sky Horizon:");
+        ui.color_edit_button_rgb(environment.sky_horizon.as_mut());
+    });
+    ui.horizontal(|ui| {
+        ui.label("Sky Direction:");
+        ui_vector4(ui, &mut environment.sky_direction);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Environment Map:");
+        ui.label(environment.environment_map.as_ref().map_or("None".into(), |path| {
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+        }));
+        pick_environment_map = ui.button("Load").clicked();
+        if ui
+            .add_enabled(environment.environment_map.is_some(), egui::Button::new("Clear"))
+            .clicked()
+        {
+            environment.environment_map = None;
+        }
+    })
+    .response
+    .on_hover_text("An equirectangular PNG or HDR image shown as the sky instead of the gradient, and seen in reflections");
+    ui.horizontal(|ui| {
+        ui.label("Sun Direction:");
+        ui_vector4(ui, &mut environment.sun_direction);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Sun Color:");
+        ui.color_edit_button_rgb(environment.sun_color.as_mut());
+        ui.add(egui::DragValue::new(&mut environment.sun_intensity).speed(0.1));
+        environment.sun_intensity = environment.sun_intensity.max(0.0);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Fog Color:");
+        ui.color_edit_button_rgb(environment.fog_color.as_mut());
+    });
+    ui.horizontal(|ui| {
+        ui.label("Fog Start:");
+        ui.add(egui::DragValue::new(&mut environment.fog_start).speed(0.1));
+        environment.fog_start = environment.fog_start.max(0.0);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Fog Density:");
+        ui.add(
+            egui::DragValue::new(&mut environment.fog_density)
+                .speed(0.01)
+                .range(0.0..=f32::INFINITY),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("W Falloff:");
+        ui.add(egui::Slider::new(&mut environment.w_falloff, 0.0..=2.0));
+    })
+    .response
+    .on_hover_text(
+        "Dims and desaturates surfaces the further they are from the camera in w, in projection mode",
+    );
+    pick_environment_map
+}
@@ -0,0 +1,174 @@
+use crate::{camera::Camera, objects::Objects};
+use cgmath::{InnerSpace, SquareMatrix};
+use eframe::egui;
+use rendering::{Projection, RenderMode, ViewAxes};
+
+/// How long an arrow pointing across the view is, in points.
+const ARROW_LENGTH: f32 = 80.0;
+/// Arrows shorter than this point almost straight into the view, so they are hidden since dragging
+/// them would send the object flying.
+const MIN_ARROW_LENGTH: f32 = 10.0;
+/// How close the pointer has to be to an arrow to grab it, in points.
+const GRAB_DISTANCE: f32 = 8.0;
+/// The same colors as the axes drawn by the overlay in ray_tracing.wgsl.
+const AXIS_COLORS: [egui::Color32; 4] = [
+    egui::Color32::from_rgb(255, 51, 51),
+    egui::Color32::from_rgb(51, 255, 51),
+    egui::Color32::from_rgb(51, 102, 255),
+    egui::Color32::from_rgb(255, 230, 51),
+];
+
+/// Maps points in the world to where they are drawn in a view, the inverse of `camera_ray` in
+/// ray_tracing.wgsl.
+struct ViewProjection<'a> {
+    camera: &'a Camera,
+    view_axes: ViewAxes,
+    rect: egui::Rect,
+}
+
+impl ViewProjection<'_> {
+    /// Returns `None` for points behind a perspective camera, and for the stereographic projection
+    /// which has no simple inverse.
+    fn project(&self, point: cgmath::Vector4<f32>) -> Option<egui::Pos2> {
+        let transform = self.camera.transform();
+        let ViewAxes { forward, up, right } = self.view_axes;
+        let mut offset = point - transform.position();
+        let mut axes = [forward, up, right].map(|axis| axis.direction(transform));
+        if let RenderMode::Slice { .. } = self.camera.render_mode {
+            // the rays stay inside the slice, so only the parts of the offset and axes in it matter
+            offset.w = 0.0;
+            for axis in &mut axes {
+                axis.w = 0.0;
+            }
+        }
+
+        // the axes are only orthonormal outside of slice mode, so the offset is written in terms of
+        // them by solving the normal equations
+        let dot = |a: usize, b: usize| axes[a].dot(axes[b]);
+        let gram = cgmath::Matrix3::new(
+            dot(0, 0),
+            dot(0, 1),
+            dot(0, 2),
+            dot(1, 0),
+            dot(1, 1),
+            dot(1, 2),
+            dot(2, 0),
+            dot(2, 1),
+            dot(2, 2),
+        );
+        let coordinates = cgmath::Vector3::from(axes.map(|axis| offset.dot(axis)));
+        let [depth, up, right]: [f32; 3] = (gram.invert()? * coordinates).into();
+
+        let half_height = match self.camera.projection {
+            Projection::Perspective => {
+                if depth <= 1e-6 {
+                    return None;
+                }
+                depth * (self.camera.fov * 0.5).tan()
+            }
+            Projection::Orthographic => self.camera.orthographic_height * 0.5,
+            Projection::Stereographic { .. } => return None,
+        };
+        let aspect = self.rect.width() / self.rect.height();
+        let uv = egui::vec2(right / (half_height * aspect), up / half_height);
+        Some(self.rect.center() + egui::vec2(uv.x, -uv.y) * self.rect.size() * 0.5)
+    }
+}
+
+/// Draws arrows over a view for moving the selected object along the world axes, and moves it
+/// while one of them is dragged with the primary button. Axes that point into the view are hidden,
+/// so the w arrow only shows up in the views that show w.
+///
+/// Returns whether the pointer is on an arrow, in which case a click shouldn't pick an object.
+pub fn gizmo(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    camera: &Camera,
+    view_axes: ViewAxes,
+    objects: &mut Objects,
+) -> bool {
+    let Some(id) = objects.selection.id else {
+        return false;
+    };
+    let Some((position, _)) = objects.focus_target(id) else {
+        return false;
+    };
+    let projection = ViewProjection {
+        camera,
+        view_axes,
+        rect: response.rect,
+    };
+    let Some(origin) = projection.project(position) else {
+        return false;
+    };
+
+    // the arrows have the same length on screen however far away the object is
+    let up = view_axes.up.direction(camera.transform());
+    let Some(points_per_unit) = projection
+        .project(position + up)
+        .map(|point| (point - origin).length())
+        .filter(|&points_per_unit| points_per_unit > 0.0)
+    else {
+        return false;
+    };
+    let world_length = ARROW_LENGTH / points_per_unit;
+    let arrows = [
+        cgmath::Vector4::unit_x(),
+        cgmath::Vector4::unit_y(),
+        cgmath::Vector4::unit_z(),
+        cgmath::Vector4::unit_w(),
+    ]
+    .map(|axis| {
+        Some((
+            axis,
+            projection.project(position + axis * world_length)? - origin,
+        ))
+        .filter(|(_, arrow)| arrow.length() >= MIN_ARROW_LENGTH)
+    });
+
+    let dragged_id = response.id.with("Gizmo Axis");
+    let mut dragged = ui.ctx().data(|data| data.get_temp::<usize>(dragged_id));
+    let hovered = response.hover_pos().and_then(|pointer| {
+        arrows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, arrow)| {
+                let (_, arrow) = (*arrow)?;
+                let along = ((pointer - origin).dot(arrow) / arrow.length_sq()).clamp(0.0, 1.0);
+                let distance = (pointer - (origin + arrow * along)).length();
+                (distance <= GRAB_DISTANCE).then_some((index, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    });
+
+    if response.drag_started_by(egui::PointerButton::Primary) {
+        dragged = hovered;
+    }
+    if let Some(index) = dragged
+        && let Some((axis, arrow)) = arrows[index]
+        && response.dragged_by(egui::PointerButton::Primary)
+    {
+        let along = response.drag_delta().dot(arrow) / arrow.length_sq();
+        objects.translate(id, axis * along * world_length);
+    }
+    if !response.dragged_by(egui::PointerButton::Primary) {
+        dragged = None;
+    }
+    ui.ctx().data_mut(|data| match dragged {
+        Some(index) => data.insert_temp(dragged_id, index),
+        None => data.remove::<usize>(dragged_id),
+    });
+
+    let painter = ui.painter_at(response.rect);
+    let highlighted = dragged.or(hovered);
+    for (index, arrow) in arrows.iter().enumerate() {
+        if let Some((_, arrow)) = *arrow {
+            let width = if highlighted == Some(index) { 4.0 } else { 2.0 };
+            painter.arrow(origin, arrow, egui::Stroke::new(width, AXIS_COLORS[index]));
+        }
+    }
+    painter.circle_filled(origin, 3.0, egui::Color32::WHITE);
+
+    highlighted.is_some()
+}
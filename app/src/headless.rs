@@ -0,0 +1,218 @@
+//! Renders a saved scene straight to an image without opening a window, for batch rendering, or
+//! times how long rendering it takes.
+
+use crate::{
+    Scene, UISettings, device_descriptor, render_data, upload_environment_map, upload_scene,
+};
+use eframe::wgpu;
+use rendering::{
+    RenderData, RenderState, RenderTarget, ViewAxes, ViewMask, pixels_to_rgba8,
+    supports_workgroup_size,
+};
+use std::{path::PathBuf, time::Instant};
+
+pub const USAGE: &str =
+    "Usage: app --render <scene> (--out <image> | --benchmark <frames>) [--size <width>x<height>]";
+
+pub struct Options {
+    pub scene: PathBuf,
+    pub output: Output,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub enum Output {
+    Image(PathBuf),
+    /// Renders the scene this many times with each tile size, see [`benchmark`].
+    Benchmark {
+        frames: u32,
+    },
+}
+
+impl Options {
+    /// Parses the command line arguments after the program name, `None` means there were none and
+    /// the window should be opened as usual.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut scene = None;
+        let mut out = None;
+        let mut frames = None;
+        let mut size = (1280, 720);
+        let mut any = false;
+        while let Some(arg) = args.next() {
+            any = true;
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for '{arg}'"))
+            };
+            match arg.as_str() {
+                "--render" => scene = Some(PathBuf::from(value()?)),
+                "--out" => out = Some(PathBuf::from(value()?)),
+                "--benchmark" => {
+                    let value = value()?;
+                    frames = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&frames| frames > 0)
+                            .ok_or_else(|| format!("Invalid number of frames '{value}'"))?,
+                    );
+                }
+                "--size" => {
+                    let value = value()?;
+                    size = value
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .filter(|&(width, height)| width > 0 && height > 0)
+                        .ok_or_else(|| {
+                            format!("Invalid size '{value}', expected <width>x<height>")
+                        })?;
+                }
+                _ => return Err(format!("Unknown argument '{arg}'")),
+            }
+        }
+        if !any {
+            return Ok(None);
+        }
+
+        let output = match (out, frames) {
+            (Some(out), None) => Output::Image(out),
+            (None, Some(frames)) => Output::Benchmark { frames },
+            (None, None) => return Err("Missing '--out <image>' or '--benchmark <frames>'".into()),
+            (Some(_), Some(_)) => {
+                return Err("Only one of '--out' and '--benchmark' can be given".into());
+            }
+        };
+        let (width, height) = size;
+        Ok(Some(Self {
+            scene: scene.ok_or("Missing '--render <scene>'")?,
+            output,
+            width,
+            height,
+        }))
+    }
+}
+
+/// Renders the scene's camera view the same way the XYZ view shows it, with the default render
+/// settings, and saves it as an image or benchmarks it.
+pub fn render(options: &Options) -> Result<(), String> {
+    let scene = std::fs::read_to_string(&options.scene)
+        .map_err(|e| {
+            format!(
+                "Error when loading scene '{}': {e}",
+                options.scene.to_string_lossy()
+            )
+        })
+        .and_then(|s| {
+            Scene::deserialize_migrated(&s).map_err(|e| {
+                format!(
+                    "Error when deserialising scene '{}': {e}",
+                    options.scene.to_string_lossy()
+                )
+            })
+        })?;
+    let ui_settings = UISettings::default();
+
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .map_err(|e| format!("Error when finding a GPU: {e}"))?;
+    let (device, queue) = pollster::block_on(adapter.request_device(&device_descriptor(&adapter)))
+        .map_err(|e| format!("Error when creating the GPU device: {e}"))?;
+
+    // the target format is only used to paint into a window, which never happens here
+    let mut render_state = RenderState::new(&adapter, &device, wgpu::TextureFormat::Rgba8Unorm);
+    upload_scene(
+        &mut render_state,
+        &device,
+        &queue,
+        &scene,
+        &ui_settings,
+        None,
+    );
+    upload_environment_map(
+        &mut render_state,
+        &device,
+        &queue,
+        scene.environment.environment_map.as_deref(),
+    )?;
+    render_state.update_selection(&queue, None);
+
+    let render_target = RenderTarget::new(&device, options.width, options.height);
+    let render_data = render_data(
+        &render_target,
+        &scene.camera,
+        ViewAxes::XYZ,
+        ViewMask::XYZ,
+        false,
+        0,
+    );
+    let out = match &options.output {
+        Output::Image(out) => out,
+        &Output::Benchmark { frames } => {
+            let info = adapter.get_info();
+            println!(
+                "{} ({:?}, {:?}), {}x{} pixels, picks {size}x{size} tiles",
+                info.name,
+                info.device_type,
+                info.backend,
+                options.width,
+                options.height,
+                size = render_state.workgroup_size(),
+            );
+            return benchmark(&device, &queue, &mut render_state, &render_data, frames);
+        }
+    };
+    render_state.render(&device, &queue, &render_data);
+    let pixels = render_target.read_pixels(&device, &queue);
+    image::save_buffer(
+        out,
+        &pixels_to_rgba8(&pixels, ui_settings.tone_mapping),
+        options.width,
+        options.height,
+        image::ColorType::Rgba8,
+    )
+    .map_err(|e| format!("Error when saving image '{}': {e}", out.to_string_lossy()))
+}
+
+/// Renders `render_data` `frames` times with each tile size the device supports and prints how
+/// long a frame took on average, so the size [`RenderState`] picks for a device can be checked.
+///
+/// Each frame is waited for before the next one is submitted, the overhead of that is the same for
+/// every size. One untimed frame is rendered first, since the first use of a pipeline can be slow.
+fn benchmark(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    render_state: &mut RenderState,
+    render_data: &RenderData,
+    frames: u32,
+) -> Result<(), String> {
+    const WORKGROUP_SIZES: [u32; 4] = [4, 8, 16, 32];
+
+    let render = |render_state: &RenderState| {
+        render_state.render(device, queue, render_data);
+        device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|e| format!("Error when waiting for the GPU: {e}"))
+    };
+    for size in WORKGROUP_SIZES {
+        let tile = format!("{size}x{size}");
+        if !supports_workgroup_size(device, size) {
+            println!("{tile:>5}: not supported");
+            continue;
+        }
+        render_state.set_workgroup_size(device, size);
+        render(render_state)?;
+        let start = Instant::now();
+        for _ in 0..frames {
+            render(render_state)?;
+        }
+        let frame_time = start.elapsed() / frames;
+        println!(
+            "{tile:>5}: {:.3} ms per frame",
+            frame_time.as_secs_f64() * 1000.0
+        );
+    }
+    Ok(())
+}
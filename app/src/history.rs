@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+/// A bounded undo/redo stack of serialized snapshots.
+///
+/// Snapshots are recorded after a change has been made, so the current state is always the last
+/// one recorded and undoing steps back to the one before it.
+#[derive(Debug)]
+pub struct History {
+    current: String,
+    undo: VecDeque<String>,
+    redo: Vec<String>,
+}
+
+impl History {
+    pub const MAX_DEPTH: usize = 100;
+
+    pub fn new(state: String) -> Self {
+        Self {
+            current: state,
+            undo: VecDeque::new(),
+            redo: vec![],
+        }
+    }
+
+    /// Makes `state` the current state, pushing the old one onto the undo stack if it changed.
+    /// Recording a change discards everything that could have been redone. Returns whether it
+    /// changed.
+    pub fn record(&mut self, state: String) -> bool {
+        if state == self.current {
+            return false;
+        }
+        self.undo
+            .push_back(std::mem::replace(&mut self.current, state));
+        if self.undo.len() > Self::MAX_DEPTH {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+        true
+    }
+
+    /// Steps back to the previously recorded state and returns it.
+    pub fn undo(&mut self) -> Option<&str> {
+        let previous = self.undo.pop_back()?;
+        self.redo
+            .push(std::mem::replace(&mut self.current, previous));
+        Some(&self.current)
+    }
+
+    /// Steps forward to the state that was last undone and returns it.
+    pub fn redo(&mut self) -> Option<&str> {
+        let next = self.redo.pop()?;
+        self.undo
+            .push_back(std::mem::replace(&mut self.current, next));
+        Some(&self.current)
+    }
+
+    pub fn undo_depth(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_depth(&self) -> usize {
+        self.redo.len()
+    }
+}
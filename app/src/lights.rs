@@ -0,0 +1,91 @@
+use crate::ui_vector4;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Light {
+    pub name: String,
+    pub position: cgmath::Vector4<f32>,
+    pub color: cgmath::Vector3<f32>,
+    pub intensity: f32,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            name: "Default Light".into(),
+            position: cgmath::Vector4 {
+                x: -2.0,
+                y: 4.0,
+                z: 2.0,
+                w: 0.0,
+            },
+            color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            intensity: 50.0,
+        }
+    }
+}
+
+pub fn gpu_lights(lights: &[Light]) -> impl ExactSizeIterator<Item = rendering::Light> {
+    lights.iter().map(
+        |&Light {
+             name: _,
+             position,
+             color,
+             intensity,
+         }| rendering::Light {
+            position,
+            color,
+            intensity,
+        },
+    )
+}
+
+pub fn lights_ui(ui: &mut egui::Ui, lights: &mut Vec<Light>) {
+    if ui.button("New Light").clicked() {
+        lights.push(Light::default());
+    }
+
+    let mut to_insert = vec![];
+    let mut to_delete = vec![];
+    for (index, light) in lights.iter_mut().enumerate() {
+        egui::CollapsingHeader::new(&light.name)
+            .id_salt(index)
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut light.name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Position:");
+                    ui_vector4(ui, &mut light.position);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Color:");
+                    ui.color_edit_button_rgb(light.color.as_mut());
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Intensity:");
+                    ui.add(egui::DragValue::new(&mut light.intensity).speed(0.1));
+                    light.intensity = light.intensity.max(0.0);
+                });
+                if ui.button("Clone").clicked() {
+                    let mut new_light = light.clone();
+                    new_light.name += " Clone";
+                    to_insert.push(new_light);
+                }
+                if ui.button("Delete").clicked() {
+                    to_delete.push(index);
+                }
+            });
+    }
+    for index in to_delete.into_iter().rev() {
+        lights.remove(index);
+    }
+    lights.extend(to_insert);
+}
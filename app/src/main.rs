@@ -1,26 +1,98 @@
+pub mod autosave;
 pub mod camera;
+pub mod environment;
+pub mod gizmo;
+pub mod headless;
+pub mod history;
+pub mod lights;
+pub mod migration;
 pub mod objects;
+pub mod slice_export;
+pub mod timeline;
 
 use crate::{
-    camera::Camera,
-    objects::{Group, Hyperplane, Hypersphere, Objects},
+    autosave::Autosave,
+    camera::{Camera, CameraBookmark, KeyBindings},
+    environment::Environment,
+    history::History,
+    lights::Light,
+    objects::{Group, Hyperplane, Hypersphere, Material, Objects, Selection},
+    timeline::Timeline,
 };
 use eframe::{egui, wgpu};
 use egui_file_dialog::FileDialog;
 use math::Rotor;
-use rendering::{RenderData, RenderState, RenderTarget, ViewAxes, register_rendering_state};
+use rendering::{
+    CameraAxis, EnvironmentMap, Projection, RenderData, RenderMode, RenderState, RenderTarget,
+    ToneMapping, ViewAxes, ViewMask, pixels_to_rgba8, register_rendering_state,
+};
 use serde::{Deserialize, Serialize};
-use slotmap::SlotMap;
-use std::{f32::consts::TAU, sync::Arc, time::Instant};
+use std::{
+    f32::consts::TAU,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 struct UISettings {
     info_window_open: bool,
     camera_window_open: bool,
+    lights_window_open: bool,
+    environment_window_open: bool,
+    timeline_window_open: bool,
+    statistics_window_open: bool,
     xwz_window_open: bool,
     xyw_window_open: bool,
+    /// The axes shown by the window that starts out as the XWZ view.
+    xwz_view_axes: ViewAxes,
+    /// The axes shown by the window that starts out as the XYW view.
+    xyw_view_axes: ViewAxes,
+    /// The fraction of each view's size that is ray traced, the image is stretched to fill the view.
+    xyz_render_scale: f32,
+    xwz_render_scale: f32,
+    xyw_render_scale: f32,
+    /// Blends between pixels when a view is rendered at a lower resolution than it is shown at.
+    bilinear_filtering: bool,
     objects_view: ObjectsView,
+    new_object_placement: NewObjectPlacement,
+    key_bindings: KeyBindings,
+    headlamp_enabled: bool,
+    headlamp_color: cgmath::Vector3<f32>,
+    headlamp_intensity: f32,
+    shadow_bias: f32,
+    shadow_samples: u32,
+    max_bounces: u32,
+    samples_per_pixel: u32,
+    torus_max_steps: u32,
+    accumulate: bool,
+    tone_mapping: ToneMapping,
+    show_grid: bool,
+    /// When set, frames are paced so that the app draws at most this many per second.
+    max_fps: Option<u32>,
+    /// Stops redrawing while nothing in the scene or settings changes, egui still redraws the UI
+    /// when there is input.
+    only_repaint_on_change: bool,
+}
+
+impl UISettings {
+    /// Puts the windows and the views' axes back to how they start out, leaving the render
+    /// settings alone.
+    fn reset_layout(&mut self) {
+        let default = Self::default();
+        self.info_window_open = default.info_window_open;
+        self.camera_window_open = default.camera_window_open;
+        self.lights_window_open = default.lights_window_open;
+        self.environment_window_open = default.environment_window_open;
+        self.timeline_window_open = default.timeline_window_open;
+        self.statistics_window_open = default.statistics_window_open;
+        self.xwz_window_open = default.xwz_window_open;
+        self.xyw_window_open = default.xyw_window_open;
+        self.xwz_view_axes = default.xwz_view_axes;
+        self.xyw_view_axes = default.xyw_view_axes;
+        self.objects_view = default.objects_view;
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -29,14 +101,56 @@ enum ObjectsView {
     Grouped,
 }
 
+/// Where the new object buttons put objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum NewObjectPlacement {
+    AtOrigin,
+    /// [`NewObjectPlacement::DISTANCE`] in front of the camera, where it shows up in the main
+    /// view.
+    InFrontOfCamera,
+}
+
+impl NewObjectPlacement {
+    const DISTANCE: f32 = 5.0;
+}
+
 impl Default for UISettings {
     fn default() -> Self {
         Self {
             info_window_open: true,
             camera_window_open: true,
+            lights_window_open: true,
+            environment_window_open: true,
+            timeline_window_open: false,
+            statistics_window_open: false,
             xwz_window_open: true,
             xyw_window_open: true,
+            xwz_view_axes: ViewAxes::XWZ,
+            xyw_view_axes: ViewAxes::XYW,
+            xyz_render_scale: 1.0,
+            xwz_render_scale: 1.0,
+            xyw_render_scale: 1.0,
+            bilinear_filtering: false,
             objects_view: ObjectsView::Grouped,
+            new_object_placement: NewObjectPlacement::InFrontOfCamera,
+            key_bindings: KeyBindings::default(),
+            headlamp_enabled: false,
+            headlamp_color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            headlamp_intensity: 0.3,
+            shadow_bias: 0.001,
+            shadow_samples: 1,
+            max_bounces: 2,
+            samples_per_pixel: 1,
+            torus_max_steps: 64,
+            accumulate: false,
+            tone_mapping: ToneMapping::Aces { exposure: 1.0 },
+            show_grid: false,
+            max_fps: None,
+            only_repaint_on_change: false,
         }
     }
 }
@@ -44,8 +158,13 @@ impl Default for UISettings {
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default)]
 struct Scene {
+    /// The format version the scene was saved with, see [`migration::migrate`].
+    version: u32,
     camera: Camera,
     objects: Objects,
+    lights: Vec<Light>,
+    environment: Environment,
+    bookmarks: Vec<CameraBookmark>,
 }
 
 impl Default for Scene {
@@ -57,18 +176,18 @@ impl Default for Scene {
             w: 0.0,
         });
 
-        let mut objects = Objects {
-            groups: SlotMap::with_key(),
-            hyperspheres: SlotMap::with_key(),
-            hyperplanes: SlotMap::with_key(),
-        };
+        let mut objects = Objects::default();
 
         objects.groups.insert(Group {
             name: "Test Group".into(),
+            visible: true,
+            parent: None,
             transform: objects::Transform::default(),
         });
         objects.hyperspheres.insert(Hypersphere {
             name: "Red".into(),
+            visible: true,
+            views: ViewMask::ALL,
             group: None,
             transform: objects::Transform {
                 position: cgmath::Vector4 {
@@ -79,15 +198,27 @@ impl Default for Scene {
                 },
                 ..Default::default()
             },
-            color: cgmath::Vector3 {
+            scale: cgmath::Vector4 {
                 x: 1.0,
-                y: 0.0,
-                z: 0.0,
+                y: 1.0,
+                z: 1.0,
+                w: 1.0,
             },
             radius: 1.0,
+            material: Material {
+                color: cgmath::Vector3 {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                ..Default::default()
+            },
+            keyframes: vec![],
         });
         objects.hyperplanes.insert(Hyperplane {
             name: "Ground".into(),
+            visible: true,
+            views: ViewMask::ALL,
             group: None,
             transform: objects::Transform {
                 position: cgmath::Vector4 {
@@ -98,22 +229,138 @@ impl Default for Scene {
                 },
                 ..Default::default()
             },
+            scale: cgmath::Vector4 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+                w: 1.0,
+            },
             width: 5.0,
             height: 5.0,
             depth: 5.0,
-            color: cgmath::Vector3 {
-                x: 0.2,
-                y: 0.8,
-                z: 0.3,
+            infinite: false,
+            double_sided: true,
+            material: Material {
+                color: cgmath::Vector3 {
+                    x: 0.2,
+                    y: 0.8,
+                    z: 0.3,
+                },
+                ..Default::default()
             },
+            keyframes: vec![],
         });
 
-        Self { camera, objects }
+        Self {
+            version: migration::CURRENT_VERSION,
+            camera,
+            objects,
+            lights: vec![Light::default()],
+            environment: Environment::default(),
+            bookmarks: vec![],
+        }
+    }
+}
+
+impl Scene {
+    /// Deserializes a scene saved by this or any older version.
+    fn deserialize_migrated(s: &str) -> Result<Self, String> {
+        let state = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        serde_json::from_value(migration::migrate(state)?).map_err(|e| e.to_string())
+    }
+
+    /// The parts of the scene that undo and redo apply to, moving the camera is not an edit.
+    fn history_state(&self) -> String {
+        serde_json::to_string(&(
+            &self.objects,
+            &self.lights,
+            &self.environment,
+            &self.bookmarks,
+        ))
+        .unwrap()
+    }
+
+    fn restore_history_state(&mut self, state: &str) {
+        (self.objects, self.lights, self.environment, self.bookmarks) =
+            serde_json::from_str(state).unwrap();
+    }
+
+    /// The 3d cross section of the visible objects at `w` as an OBJ mesh, see
+    /// [`slice_export::slice_obj`].
+    fn export_slice_obj(&self, w: f32) -> String {
+        slice_export::slice_obj(
+            self.objects.gpu_hyperspheres(None),
+            self.objects.gpu_hyperplanes(None),
+            self.objects.gpu_tesseracts(None),
+            w,
+        )
+    }
+}
+
+struct SceneStatistics {
+    last_update: Instant,
+    hyperspheres_count: usize,
+    hyperplanes_count: usize,
+    tesseracts_count: usize,
+    tori_count: usize,
+    spherinders_count: usize,
+    csg_pairs_count: usize,
+    groups_count: usize,
+    max_group_depth: usize,
+    gpu_buffer_bytes: u64,
+    bounds: Option<(cgmath::Vector4<f32>, cgmath::Vector4<f32>)>,
+    centroid: Option<cgmath::Vector4<f32>>,
+}
+
+impl SceneStatistics {
+    const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+    fn new(objects: &Objects, render_state: &RenderState, time: Instant) -> Self {
+        Self {
+            last_update: time,
+            hyperspheres_count: objects.hyperspheres.len(),
+            hyperplanes_count: objects.hyperplanes.len(),
+            tesseracts_count: objects.tesseracts.len(),
+            tori_count: objects.tori.len(),
+            spherinders_count: objects.spherinders.len(),
+            csg_pairs_count: objects.csg_pairs.len(),
+            groups_count: objects.groups.len(),
+            max_group_depth: objects.max_group_depth(),
+            gpu_buffer_bytes: render_state.gpu_buffer_bytes(),
+            bounds: objects.world_bounds(),
+            centroid: objects.centroid(),
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        ui.label(format!("Hyperspheres: {}", self.hyperspheres_count));
+        ui.label(format!("Hyperplanes: {}", self.hyperplanes_count));
+        ui.label(format!("Tesseracts: {}", self.tesseracts_count));
+        ui.label(format!("Tori: {}", self.tori_count));
+        ui.label(format!("Spherinders: {}", self.spherinders_count));
+        ui.label(format!("CSG Pairs: {}", self.csg_pairs_count));
+        ui.label(format!("Groups: {}", self.groups_count));
+        ui.label(format!("Group Nesting Depth: {}", self.max_group_depth));
+        ui.label(format!(
+            "GPU Buffers: {:.3} KiB",
+            self.gpu_buffer_bytes as f64 / 1024.0
+        ));
+        if let Some((min, max)) = self.bounds {
+            ui.label(format!("Bounds Min: {}", format_vector4(min)));
+            ui.label(format!("Bounds Max: {}", format_vector4(max)));
+            ui.label(format!("Extents: {}", format_vector4(max - min)));
+        } else {
+            ui.label("Bounds: Empty");
+        }
+        if let Some(centroid) = self.centroid {
+            ui.label(format!("Centroid: {}", format_vector4(centroid)));
+        }
     }
 }
 
 struct App {
     last_time: Option<Instant>,
+    scene_statistics: Option<SceneStatistics>,
 
     xyz_render_target: RenderTarget,
     xwz_render_target: RenderTarget,
@@ -121,15 +368,52 @@ struct App {
 
     ui_settings: UISettings,
     scene: Scene,
+    /// The serialized camera and settings from the previous frame, the accumulated samples are
+    /// reset whenever this or `last_scene_state` changes and it decides when to repaint with
+    /// `only_repaint_on_change`.
+    last_render_state: String,
+    /// The last [`Scene::history_state`] compared against, only updated while `edit_pending`.
+    last_scene_state: String,
+    /// Set by input that could have edited the scene and cleared once the edit has been recorded,
+    /// serializing the whole scene to look for changes is too slow to do every frame.
+    edit_pending: bool,
+    /// Snapshots of the edits made to the scene, everything but the camera is recorded.
+    history: History,
+    autosave: Autosave,
+    /// A scene auto-saved by an earlier run that differs from the one that was loaded, offered to
+    /// be restored until it is restored or discarded.
+    recovery: Option<String>,
+    timeline: Timeline,
+    /// Only the objects with names containing this are listed in the objects panel.
+    objects_filter: String,
+
+    go_to_position: cgmath::Vector4<f32>,
+    /// Where the camera turns to look after going to `go_to_position`, if anywhere.
+    go_to_target: Option<cgmath::Vector4<f32>>,
+    /// The options for the next press of the Generate button in the objects panel.
+    random_hyperspheres: usize,
+    random_hyperplanes: usize,
+    random_seed: u64,
+    /// A click in one of the views, the object under it is selected once the objects for the
+    /// frame have been uploaded.
+    pick_request: Option<(RenderTarget, ViewAxes, ViewMask, (u32, u32))>,
 
     file_dialog: FileDialog,
     file_interaction: FileInteraction,
+    /// A failed file interaction, shown until it is dismissed.
+    error: Option<String>,
+    /// The environment map that was last uploaded, it is loaded again whenever the scene's
+    /// changes.
+    environment_map: Option<PathBuf>,
 }
 
 enum FileInteraction {
     None,
     Save,
     Load,
+    SaveImage,
+    ExportSlice,
+    LoadEnvironmentMap,
 }
 
 impl App {
@@ -138,8 +422,26 @@ impl App {
 
         register_rendering_state(cc);
 
+        let scene: Scene = cc
+            .storage
+            .unwrap()
+            .get_string("scene")
+            .and_then(|str| Scene::deserialize_migrated(&str).ok())
+            .unwrap_or_default();
+        let history = History::new(scene.history_state());
+        let autosave = Autosave::new(
+            eframe::storage_dir(Self::NAME).map(|dir| dir.join("recovery.scene")),
+            Instant::now(),
+        );
+        // the camera isn't an edit, so a recovery that only moved it isn't worth asking about
+        let recovery = autosave.recovery().filter(|recovery| {
+            !Scene::deserialize_migrated(recovery)
+                .is_ok_and(|recovery| recovery.history_state() == scene.history_state())
+        });
+
         Self {
             last_time: None,
+            scene_statistics: None,
 
             xyz_render_target: RenderTarget::new(device, 1, 1),
             xwz_render_target: RenderTarget::new(device, 1, 1),
@@ -151,19 +453,69 @@ impl App {
                 .get_string("ui_settings")
                 .and_then(|str| serde_json::from_str(&str).ok())
                 .unwrap_or_default(),
-            scene: cc
-                .storage
-                .unwrap()
-                .get_string("scene")
-                .and_then(|str| serde_json::from_str(&str).ok())
-                .unwrap_or_default(),
+            scene,
+            last_render_state: String::new(),
+            last_scene_state: String::new(),
+            edit_pending: false,
+            history,
+            autosave,
+            recovery,
+            timeline: Timeline::default(),
+            objects_filter: String::new(),
+
+            go_to_position: cgmath::Vector4 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                w: 0.0,
+            },
+            go_to_target: None,
+            random_hyperspheres: 100,
+            random_hyperplanes: 0,
+            random_seed: 0,
+            pick_request: None,
 
             file_dialog: FileDialog::new()
                 .add_file_filter_extensions("Scene", vec!["scene"])
+                .add_file_filter_extensions("PNG", vec!["png"])
+                .add_file_filter_extensions("OBJ", vec!["obj"])
+                .add_file_filter_extensions("Environment Map", vec!["png", "hdr"])
                 .default_file_filter("Scene")
                 .add_save_extension("Scene", "scene")
+                .add_save_extension("PNG", "png")
+                .add_save_extension("OBJ", "obj")
                 .default_save_extension("Scene"),
             file_interaction: FileInteraction::None,
+            error: None,
+            environment_map: None,
+        }
+    }
+}
+
+impl App {
+    const NAME: &str = "4d Rendering";
+
+    /// Replaces the whole scene, like when loading one. The undo history is started over from it
+    /// so that undoing can't bring back the old scene.
+    fn replace_scene(&mut self, scene: Scene) {
+        self.history = History::new(scene.history_state());
+        self.scene = scene;
+        self.edit_pending = true;
+    }
+
+    fn undo(&mut self) {
+        if let Some(state) = self.history.undo() {
+            self.scene.restore_history_state(state);
+            self.autosave.dirty = true;
+            self.edit_pending = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(state) = self.history.redo() {
+            self.scene.restore_history_state(state);
+            self.autosave.dirty = true;
+            self.edit_pending = true;
         }
     }
 }
@@ -181,6 +533,14 @@ impl eframe::App for App {
         let dt = (time - self.last_time.unwrap_or(time)).as_secs_f32();
         self.last_time = Some(time);
 
+        let input_may_edit = may_edit_scene(ctx);
+        self.edit_pending |= input_may_edit;
+        let scene_may_have_changed = self.edit_pending;
+        if input_may_edit {
+            // an edit made late in this frame is only looked for in the next one
+            ctx.request_repaint();
+        }
+
         egui::TopBottomPanel::top("Windows").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 if ui.button("Load").clicked() {
@@ -191,10 +551,34 @@ impl eframe::App for App {
                     self.file_interaction = FileInteraction::Save;
                     self.file_dialog.save_file();
                 }
+                if ui.button("Save Image").clicked() {
+                    self.file_interaction = FileInteraction::SaveImage;
+                    self.file_dialog.save_file();
+                }
+                if ui
+                    .add_enabled(
+                        matches!(self.scene.camera.render_mode, RenderMode::Slice { .. }),
+                        egui::Button::new("Export Slice"),
+                    )
+                    .on_hover_text("Saves the cross section shown in slice mode as an OBJ mesh")
+                    .on_disabled_hover_text("Only available while the camera is in slice mode")
+                    .clicked()
+                {
+                    self.file_interaction = FileInteraction::ExportSlice;
+                    self.file_dialog.save_file();
+                }
                 self.ui_settings.info_window_open |= ui.button("Info").clicked();
                 self.ui_settings.camera_window_open |= ui.button("Camera").clicked();
-                self.ui_settings.xwz_window_open |= ui.button("XWZ View").clicked();
-                self.ui_settings.xyw_window_open |= ui.button("XYW View").clicked();
+                self.ui_settings.lights_window_open |= ui.button("Lights").clicked();
+                self.ui_settings.environment_window_open |= ui.button("Environment").clicked();
+                self.ui_settings.timeline_window_open |= ui.button("Timeline").clicked();
+                self.ui_settings.statistics_window_open |= ui.button("Statistics").clicked();
+                self.ui_settings.xwz_window_open |= ui
+                    .button(format!("{} View", self.ui_settings.xwz_view_axes))
+                    .clicked();
+                self.ui_settings.xyw_window_open |= ui
+                    .button(format!("{} View", self.ui_settings.xyw_view_axes))
+                    .clicked();
             });
         });
 
@@ -220,9 +604,93 @@ impl eframe::App for App {
                             );
                         });
                 });
+                ui.horizontal(|ui| {
+                    ui.label("New Objects:");
+                    egui::ComboBox::new("New Objects", "")
+                        .selected_text(match self.ui_settings.new_object_placement {
+                            NewObjectPlacement::AtOrigin => "At Origin",
+                            NewObjectPlacement::InFrontOfCamera => "In Front of Camera",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.ui_settings.new_object_placement,
+                                NewObjectPlacement::AtOrigin,
+                                "At Origin",
+                            );
+                            ui.selectable_value(
+                                &mut self.ui_settings.new_object_placement,
+                                NewObjectPlacement::InFrontOfCamera,
+                                "In Front of Camera",
+                            );
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Filter:");
+                    ui.text_edit_singleline(&mut self.objects_filter);
+                    if ui.button("Clear").clicked() {
+                        self.objects_filter.clear();
+                    }
+                });
+                if ui
+                    .button("Paste")
+                    .on_hover_text("Adds the object on the clipboard")
+                    .clicked()
+                    && let Err(error) = arboard::Clipboard::new()
+                        .and_then(|mut clipboard| clipboard.get_text())
+                        .map_err(|e| format!("Error when reading the clipboard: {e}"))
+                        .and_then(|text| self.scene.objects.paste(&text))
+                {
+                    self.error = Some(error);
+                }
+                ui.collapsing("Generate Random", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Hyperspheres:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.random_hyperspheres)
+                                .range(0..=100_000),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Hyperplanes:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.random_hyperplanes).range(0..=100_000),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Seed:");
+                        ui.add(egui::DragValue::new(&mut self.random_seed));
+                    });
+                    if ui
+                        .button("Generate")
+                        .on_hover_text("Adds the objects in a new group, the same seed always gives the same objects")
+                        .clicked()
+                    {
+                        self.scene.objects.generate_random(
+                            self.random_hyperspheres,
+                            self.random_hyperplanes,
+                            self.random_seed,
+                        );
+                    }
+                });
+                self.scene.objects.isolation_ui(ui);
+                self.scene.objects.bulk_ui(ui);
+                let new_position = match self.ui_settings.new_object_placement {
+                    NewObjectPlacement::AtOrigin => cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    NewObjectPlacement::InFrontOfCamera => {
+                        self.scene.camera.point_ahead(NewObjectPlacement::DISTANCE)
+                    }
+                };
                 match self.ui_settings.objects_view {
-                    ObjectsView::Flat => self.scene.objects.flat_ui(ui),
-                    ObjectsView::Grouped => self.scene.objects.grouped_ui(ui),
+                    ObjectsView::Flat => {
+                        self.scene
+                            .objects
+                            .flat_ui(ui, &self.objects_filter, new_position)
+                    }
+                    ObjectsView::Grouped => {
+                        self.scene
+                            .objects
+                            .grouped_ui(ui, &self.objects_filter, new_position)
+                    }
                 }
             });
             ui.allocate_space(ui.available_size());
@@ -238,38 +706,297 @@ impl eframe::App for App {
                     }
                     let state = serde_json::to_string(&self.scene).unwrap();
                     if let Err(e) = std::fs::write(&path, state) {
-                        eprintln!("Error when writing scene '{}': {e}", path.to_string_lossy());
+                        self.error = Some(format!(
+                            "Error when writing scene '{}': {e}",
+                            path.to_string_lossy()
+                        ));
+                    } else {
+                        self.autosave.saved();
                     }
                 }
                 FileInteraction::Load => {
-                    if let Ok(s) = std::fs::read_to_string(&path).inspect_err(|e| {
-                        eprintln!("Error when loading scene '{}': {e}", path.to_string_lossy());
-                    }) && let Ok(state) = serde_json::from_str(&s).inspect_err(|e| {
-                        eprintln!(
-                            "Error when deserialising scene '{}': {e}",
+                    match std::fs::read_to_string(&path)
+                        .map_err(|e| {
+                            format!("Error when loading scene '{}': {e}", path.to_string_lossy())
+                        })
+                        .and_then(|s| {
+                            Scene::deserialize_migrated(&s).map_err(|e| {
+                                format!(
+                                    "Error when deserialising scene '{}': {e}",
+                                    path.to_string_lossy()
+                                )
+                            })
+                        }) {
+                        Ok(state) => {
+                            self.replace_scene(state);
+                            renderer
+                                .write()
+                                .callback_resources
+                                .get_mut::<RenderState>()
+                                .unwrap()
+                                .release_scene_resources(device, queue);
+                        }
+                        Err(error) => self.error = Some(error),
+                    }
+                }
+                FileInteraction::SaveImage => {
+                    if path.extension().is_none() {
+                        path.set_extension("png");
+                    }
+                    let (width, height) = self.xyz_render_target.size();
+                    let pixels = self.xyz_render_target.read_pixels(device, queue);
+                    if let Err(e) = image::save_buffer(
+                        &path,
+                        &pixels_to_rgba8(&pixels, self.ui_settings.tone_mapping),
+                        width,
+                        height,
+                        image::ColorType::Rgba8,
+                    ) {
+                        self.error = Some(format!(
+                            "Error when saving image '{}': {e}",
                             path.to_string_lossy()
-                        );
-                    }) {
-                        self.scene = state;
+                        ));
+                    }
+                }
+                FileInteraction::LoadEnvironmentMap => {
+                    self.scene.environment.environment_map = Some(path);
+                }
+                FileInteraction::ExportSlice => {
+                    if path.extension().is_none() {
+                        path.set_extension("obj");
+                    }
+                    // the camera could have left slice mode while the dialog was open
+                    if let RenderMode::Slice { w } = self.scene.camera.render_mode
+                        && let Err(e) = std::fs::write(&path, self.scene.export_slice_obj(w))
+                    {
+                        self.error = Some(format!(
+                            "Error when exporting slice '{}': {e}",
+                            path.to_string_lossy()
+                        ));
+                    }
+                }
+            }
+        }
+
+        if let Some(error) = &self.error {
+            let response = egui::Modal::new(egui::Id::new("Error")).show(ctx, |ui| {
+                ui.heading("Error");
+                ui.label(error);
+                ui.button("Dismiss").clicked()
+            });
+            if response.inner || response.should_close() {
+                self.error = None;
+            }
+        }
+
+        if let Some(recovery) = &self.recovery {
+            let mut restore = false;
+            let response = egui::Modal::new(egui::Id::new("Recovery")).show(ctx, |ui| {
+                ui.heading("Unsaved Changes");
+                ui.label("The scene was auto-saved with changes that were never saved to a file, restore it?");
+                ui.horizontal(|ui| {
+                    restore = ui.button("Restore").clicked();
+                    ui.button("Discard").clicked()
+                })
+                .inner
+            });
+            if restore {
+                match Scene::deserialize_migrated(recovery) {
+                    Ok(scene) => {
+                        self.replace_scene(scene);
+                        renderer
+                            .write()
+                            .callback_resources
+                            .get_mut::<RenderState>()
+                            .unwrap()
+                            .release_scene_resources(device, queue);
+                    }
+                    Err(e) => {
+                        self.error = Some(format!(
+                            "Error when deserialising the auto-saved scene: {e}"
+                        ));
                     }
                 }
+                self.recovery = None;
+            } else if response.inner || response.should_close() {
+                self.autosave.discard();
+                self.recovery = None;
             }
         }
 
         {
             let mut reset = false;
+            let mut reset_objects = false;
+            let mut reset_camera = false;
+            let mut reset_layout = false;
+            let mut undo = false;
+            let mut redo = false;
             egui::Window::new("Info")
                 .open(&mut self.ui_settings.info_window_open)
                 .scroll(true)
                 .show(ctx, |ui| {
                     ui.label(format!("FPS: {:.3}", 1.0 / dt));
                     ui.label(format!("Frame Time: {:.3}ms", 1000.0 * dt));
+                    ui.checkbox(&mut self.ui_settings.headlamp_enabled, "Headlamp");
+                    ui.add_enabled_ui(self.ui_settings.headlamp_enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Headlamp Intensity:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.ui_settings.headlamp_intensity)
+                                    .speed(0.01),
+                            );
+                            self.ui_settings.headlamp_intensity =
+                                self.ui_settings.headlamp_intensity.max(0.0);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Headlamp Color:");
+                            ui.color_edit_button_rgb(self.ui_settings.headlamp_color.as_mut());
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Shadow Bias:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.ui_settings.shadow_bias)
+                                .speed(0.0001)
+                                .max_decimals(5),
+                        );
+                        self.ui_settings.shadow_bias = self.ui_settings.shadow_bias.max(0.0);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Shadow Samples:");
+                        ui.add(egui::Slider::new(&mut self.ui_settings.shadow_samples, 1..=16))
+                            .on_hover_text(
+                                "Shadow rays traced towards each emissive hypersphere, more than 1 gives soft shadows which smooth out when accumulating samples",
+                            );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max Bounces:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.ui_settings.max_bounces).range(0..=16),
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Samples Per Pixel:");
+                        ui.add(egui::Slider::new(
+                            &mut self.ui_settings.samples_per_pixel,
+                            1..=8,
+                        ))
+                        .on_hover_text("Shoots an NxN grid of rays for every pixel");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Torus Max Steps:");
+                        ui.add(egui::Slider::new(
+                            &mut self.ui_settings.torus_max_steps,
+                            1..=256,
+                        ))
+                        .on_hover_text(
+                            "How many steps a ray can march towards a torus before it counts as a miss",
+                        );
+                    });
+                    ui.collapsing("Render Scale", |ui| {
+                        for (label, render_scale) in [
+                            ("XYZ View:", &mut self.ui_settings.xyz_render_scale),
+                            ("XWZ View:", &mut self.ui_settings.xwz_render_scale),
+                            ("XYW View:", &mut self.ui_settings.xyw_render_scale),
+                        ] {
+                            ui.horizontal(|ui| {
+                                ui.label(label);
+                                ui.add(egui::Slider::new(render_scale, 0.25..=1.0));
+                            });
+                        }
+                        ui.checkbox(&mut self.ui_settings.bilinear_filtering, "Bilinear Filtering")
+                            .on_hover_text("Smooths out the pixels of views rendered below full scale, if the GPU supports it");
+                    })
+                    .header_response
+                    .on_hover_text("Ray traces fewer pixels than the view shows, for a faster frame");
+                    ui.checkbox(&mut self.ui_settings.show_grid, "Show Grid")
+                        .on_hover_text(
+                            "Draws a grid on the y = 0 hyperplane and the x, y, z and w axes in red, green, blue and yellow",
+                        );
+                    ui.checkbox(&mut self.ui_settings.accumulate, "Accumulate Samples")
+                        .on_hover_text(
+                            "Averages the samples from every frame while the camera and scene stay still",
+                        );
+                    ui.horizontal(|ui| {
+                        let mut limited = self.ui_settings.max_fps.is_some();
+                        let mut max_fps = self.ui_settings.max_fps.unwrap_or(60);
+                        ui.checkbox(&mut limited, "Max FPS:");
+                        ui.add_enabled(limited, egui::Slider::new(&mut max_fps, 1..=240));
+                        self.ui_settings.max_fps = limited.then_some(max_fps);
+                    })
+                    .response
+                    .on_hover_text("Paces the frames to use less power");
+                    ui.checkbox(
+                        &mut self.ui_settings.only_repaint_on_change,
+                        "Only Repaint On Change",
+                    )
+                    .on_hover_text(
+                        "Stops rendering while the camera and scene stay still, unless samples are being accumulated",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Tone Mapping:");
+                        let aces = matches!(self.ui_settings.tone_mapping, ToneMapping::Aces { .. });
+                        egui::ComboBox::new("Tone Mapping", "")
+                            .selected_text(if aces { "ACES" } else { "None" })
+                            .show_ui(ui, |ui| {
+                                if ui.selectable_label(!aces, "None").clicked() {
+                                    self.ui_settings.tone_mapping = ToneMapping::None;
+                                }
+                                if ui.selectable_label(aces, "ACES").clicked() && !aces {
+                                    self.ui_settings.tone_mapping =
+                                        ToneMapping::Aces { exposure: 1.0 };
+                                }
+                            });
+                    });
+                    if let ToneMapping::Aces { exposure } = &mut self.ui_settings.tone_mapping {
+                        ui.horizontal(|ui| {
+                            ui.label("Exposure:");
+                            ui.add(egui::Slider::new(exposure, 0.01..=16.0).logarithmic(true));
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        undo |= ui
+                            .add_enabled(self.history.undo_depth() > 0, egui::Button::new("Undo"))
+                            .clicked();
+                        redo |= ui
+                            .add_enabled(self.history.redo_depth() > 0, egui::Button::new("Redo"))
+                            .clicked();
+                        ui.label(format!(
+                            "History: {}/{}",
+                            self.history.undo_depth(),
+                            History::MAX_DEPTH
+                        ));
+                    });
+                    ui.horizontal(|ui| {
+                        reset_objects |= ui.button("Reset Objects").clicked();
+                        reset_camera |= ui.button("Reset Camera").clicked();
+                        reset_layout |= ui
+                            .button("Reset UI Layout")
+                            .on_hover_text("Reopens the default windows and moves them back to where they started")
+                            .clicked();
+                    });
                     reset |= ui.button("RESET EVERYTHING").clicked();
                     ui.allocate_space(ui.available_size());
                 });
+            if reset_objects {
+                self.scene.objects = Scene::default().objects;
+            }
+            if reset_camera {
+                self.scene.camera = Scene::default().camera;
+            }
+            if reset_layout {
+                self.ui_settings.reset_layout();
+                ctx.memory_mut(|memory| memory.reset_areas());
+            }
             if reset {
                 self.ui_settings = Default::default();
-                self.scene = Default::default();
+                self.replace_scene(Default::default());
+            }
+            if undo {
+                self.undo();
+            }
+            if redo {
+                self.redo();
             }
         }
 
@@ -281,44 +1008,241 @@ impl eframe::App for App {
                     ui.label("Position:");
                     ui_vector4(ui, &mut self.scene.camera.position);
                 });
+                self.scene.camera.mode_ui(ui);
+                ui.horizontal(|ui| {
+                    ui.label("Render Mode:");
+                    let slicing = matches!(self.scene.camera.render_mode, RenderMode::Slice { .. });
+                    egui::ComboBox::new("Render Mode", "")
+                        .selected_text(if slicing { "Slice" } else { "Project" })
+                        .show_ui(ui, |ui| {
+                            if ui.selectable_label(!slicing, "Project").clicked() {
+                                self.scene.camera.render_mode = RenderMode::Project;
+                            }
+                            if ui.selectable_label(slicing, "Slice").clicked() && !slicing {
+                                self.scene.camera.render_mode = RenderMode::Slice {
+                                    w: self.scene.camera.position.w,
+                                };
+                            }
+                        });
+                });
+                if let RenderMode::Slice { w } = &mut self.scene.camera.render_mode {
+                    let (min, max) = self
+                        .scene_statistics
+                        .as_ref()
+                        .and_then(|scene_statistics| scene_statistics.bounds)
+                        .map_or((-5.0, 5.0), |(min, max)| (min.w, max.w));
+                    ui.horizontal(|ui| {
+                        ui.label("Slice W:");
+                        ui.add(
+                            egui::Slider::new(w, min..=max).clamping(egui::SliderClamping::Never),
+                        );
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Projection:");
+                    egui::ComboBox::new("Projection", "")
+                        .selected_text(match self.scene.camera.projection {
+                            Projection::Perspective => "Perspective",
+                            Projection::Orthographic => "Orthographic",
+                            Projection::Stereographic { .. } => "Stereographic",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.scene.camera.projection,
+                                Projection::Perspective,
+                                "Perspective",
+                            );
+                            ui.selectable_value(
+                                &mut self.scene.camera.projection,
+                                Projection::Orthographic,
+                                "Orthographic",
+                            );
+                            let stereographic = matches!(
+                                self.scene.camera.projection,
+                                Projection::Stereographic { .. }
+                            );
+                            if ui.selectable_label(stereographic, "Stereographic").clicked()
+                                && !stereographic
+                            {
+                                self.scene.camera.projection = Projection::Stereographic {
+                                    pole: -cgmath::Vector4::unit_x(),
+                                };
+                            }
+                        });
+                });
+                match &mut self.scene.camera.projection {
+                    Projection::Perspective => {
+                        ui.horizontal(|ui| {
+                            ui.label("FOV:");
+                            ui.drag_angle(&mut self.scene.camera.fov);
+                            self.scene.camera.fov =
+                                self.scene.camera.fov.clamp(TAU * 0.01, TAU * 0.49);
+                        });
+                    }
+                    Projection::Orthographic => {
+                        ui.horizontal(|ui| {
+                            ui.label("View Height:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.scene.camera.orthographic_height)
+                                    .speed(0.1),
+                            );
+                            self.scene.camera.orthographic_height =
+                                self.scene.camera.orthographic_height.max(0.01);
+                        });
+                    }
+                    Projection::Stereographic { pole } => {
+                        ui.horizontal(|ui| {
+                            ui.label("FOV:");
+                            ui.drag_angle(&mut self.scene.camera.fov);
+                            self.scene.camera.fov =
+                                self.scene.camera.fov.clamp(TAU * 0.01, TAU * 0.99);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Pole:");
+                            ui_vector4(ui, pole);
+                        })
+                        .response
+                        .on_hover_text(
+                            "The direction that the edges of the view go towards, relative to the camera, x is forward",
+                        );
+                    }
+                }
+                ui.horizontal(|ui| {
+                    let camera = &mut self.scene.camera;
+                    ui.label("Near:");
+                    ui.add(
+                        egui::DragValue::new(&mut camera.near)
+                            .speed(0.01)
+                            .range(0.0..=camera.far),
+                    );
+                    ui.label("Far:");
+                    ui.add(
+                        egui::DragValue::new(&mut camera.far)
+                            .speed(1.0)
+                            .range(camera.near..=f32::MAX),
+                    );
+                })
+                .response
+                .on_hover_text("Objects closer than near are cut away, and ones further than far are replaced by the sky");
+                ui.horizontal(|ui| {
+                    ui.label("Aperture:");
+                    ui.add(egui::Slider::new(&mut self.scene.camera.aperture, 0.0..=1.0));
+                })
+                .response
+                .on_hover_text("The radius of the lens, anything away from the focus distance is blurred more the larger it is, the blur smooths out when accumulating");
+                ui.horizontal(|ui| {
+                    ui.label("Focus Distance:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.scene.camera.focus_distance)
+                            .speed(0.1)
+                            .range(0.0..=f32::MAX),
+                    );
+                    if ui
+                        .add_enabled(
+                            self.scene.objects.selection.id.is_some(),
+                            egui::Button::new("Focus on Selected"),
+                        )
+                        .clicked()
+                        && let Some(id) = self.scene.objects.selection.id
+                        && let Some((position, _)) = self.scene.objects.focus_target(id)
+                    {
+                        self.scene.camera.focus_on(position);
+                    }
+                });
                 ui.horizontal(|ui| {
                     ui.label("Move Speed:");
                     ui.add(egui::DragValue::new(&mut self.scene.camera.move_speed).speed(0.1));
                 });
+                ui.horizontal(|ui| {
+                    ui.label("Acceleration:");
+                    ui.add(
+                        egui::Slider::new(
+                            &mut self.scene.camera.acceleration,
+                            0.1..=Camera::MAX_ACCELERATION,
+                        )
+                        .logarithmic(true),
+                    )
+                    .on_hover_text("How quickly the camera speeds up, the maximum starts moving instantly");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Damping:");
+                    ui.add(
+                        egui::Slider::new(
+                            &mut self.scene.camera.damping,
+                            0.1..=Camera::MAX_ACCELERATION,
+                        )
+                        .logarithmic(true),
+                    )
+                    .on_hover_text("How quickly the camera slows down, the maximum stops instantly");
+                });
                 ui.horizontal(|ui| {
                     ui.label("Rotation Speed:");
                     ui.add(egui::DragValue::new(&mut self.scene.camera.rotation_speed).speed(0.1));
                     self.scene.camera.rotation_speed = self.scene.camera.rotation_speed.max(0.0);
                 });
+                ui.collapsing("Go To", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Position:");
+                        ui_vector4(ui, &mut self.go_to_position);
+                    });
+                    ui.horizontal(|ui| {
+                        let mut look_at = self.go_to_target.is_some();
+                        ui.checkbox(&mut look_at, "Look At:");
+                        if look_at {
+                            let target = self
+                                .go_to_target
+                                .get_or_insert(cgmath::vec4(0.0, 0.0, 0.0, 0.0));
+                            ui_vector4(ui, target);
+                        } else {
+                            self.go_to_target = None;
+                        }
+                    });
+                    let valid = std::iter::once(self.go_to_position)
+                        .chain(self.go_to_target)
+                        .all(|cgmath::Vector4 { x, y, z, w }| {
+                            [x, y, z, w].into_iter().all(f32::is_finite)
+                        });
+                    if ui.add_enabled(valid, egui::Button::new("Go")).clicked() {
+                        self.scene.camera.position = self.go_to_position;
+                        if let Some(target) = self.go_to_target {
+                            self.scene.camera.look_at(target);
+                        }
+                    }
+                });
+                ui.collapsing("Bookmarks", |ui| {
+                    camera::bookmarks_ui(ui, &mut self.scene.camera, &mut self.scene.bookmarks);
+                });
                 ui.collapsing("Align", |ui| {
                     if ui.button("Reset XY Rotation").clicked() {
                         self.scene.camera.xy_rotation = 0.0;
                     }
                     if ui.button("Rotate to WYZ").clicked() {
-                        self.scene.camera.main_rotation = self
-                            .scene
+                        self.scene
                             .camera
-                            .main_rotation
-                            .then(Rotor::rotate_xw(0.25 * TAU));
+                            .realign(|rotation| rotation.then(Rotor::rotate_xw(0.25 * TAU)));
                     }
                     if ui.button("Rotate to XYW").clicked() {
-                        self.scene.camera.main_rotation = self
-                            .scene
+                        self.scene
                             .camera
-                            .main_rotation
-                            .then(Rotor::rotate_zw(0.25 * TAU));
+                            .realign(|rotation| rotation.then(Rotor::rotate_zw(0.25 * TAU)));
                     }
-                    ui.label("These align buttons assume that the current XY rotation is 0");
                     if ui.button("Align XYZ").clicked() {
-                        self.scene.camera.main_rotation = Rotor::identity();
+                        self.scene.camera.realign(|_| Rotor::identity());
                     }
                     if ui.button("Align WYZ").clicked() {
-                        self.scene.camera.main_rotation = Rotor::rotate_xw(0.25 * TAU);
+                        self.scene.camera.realign(|_| Rotor::rotate_xw(0.25 * TAU));
                     }
                     if ui.button("Align XYW").clicked() {
-                        self.scene.camera.main_rotation = Rotor::rotate_zw(0.25 * TAU);
+                        self.scene.camera.realign(|_| Rotor::rotate_zw(0.25 * TAU));
                     }
                 });
+                ui.collapsing("Key Bindings", |ui| {
+                    self.ui_settings.key_bindings.ui(ui);
+                });
+                ui.collapsing("Advanced", |ui| {
+                    ui.label("Main Rotation:");
+                    self.scene.camera.rotor_ui(ui);
+                });
                 ui.add_enabled_ui(false, |ui| {
                     let transform = self.scene.camera.transform();
                     ui.horizontal(|ui| {
@@ -345,62 +1269,339 @@ impl eframe::App for App {
                 ui.allocate_space(ui.available_size());
             });
 
+        egui::Window::new("Lights")
+            .open(&mut self.ui_settings.lights_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                lights::lights_ui(ui, &mut self.scene.lights);
+                ui.allocate_space(ui.available_size());
+            });
+
+        egui::Window::new("Environment")
+            .open(&mut self.ui_settings.environment_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if environment::environment_ui(ui, &mut self.scene.environment) {
+                    self.file_interaction = FileInteraction::LoadEnvironmentMap;
+                    self.file_dialog.pick_file();
+                }
+                ui.allocate_space(ui.available_size());
+            });
+
+        egui::Window::new("Statistics")
+            .open(&mut self.ui_settings.statistics_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if let Some(scene_statistics) = &self.scene_statistics {
+                    scene_statistics.ui(ui);
+                }
+                ui.separator();
+                ui.label("GPU Ray Tracing Time:");
+                for (name, render_target) in [
+                    ("XYZ View".to_string(), &self.xyz_render_target),
+                    (
+                        format!("{} View", self.ui_settings.xwz_view_axes),
+                        &self.xwz_render_target,
+                    ),
+                    (
+                        format!("{} View", self.ui_settings.xyw_view_axes),
+                        &self.xyw_render_target,
+                    ),
+                ] {
+                    ui.label(match render_target.gpu_time() {
+                        Some(time) => format!("{name}: {:.3}ms", time.as_secs_f64() * 1000.0),
+                        None => format!("{name}: Unavailable"),
+                    });
+                }
+            });
+
+        let animation_duration = self.scene.objects.animation_duration();
+        egui::Window::new("Timeline")
+            .open(&mut self.ui_settings.timeline_window_open)
+            .show(ctx, |ui| {
+                self.timeline.ui(ui, animation_duration);
+            });
+        self.timeline.update(dt, animation_duration);
+        let animation_time = self.timeline.animation_time();
+
         {
             let callback_resources = &mut renderer.write().callback_resources;
             let render_state: &mut RenderState = callback_resources.get_mut().unwrap();
 
-            render_state.update_hyperspheres(device, queue, self.scene.objects.gpu_hyperspheres());
-            render_state.update_hyperplanees(device, queue, self.scene.objects.gpu_hyperplanes());
+            if self.environment_map != self.scene.environment.environment_map {
+                self.environment_map = self.scene.environment.environment_map.clone();
+                if let Err(error) = upload_environment_map(
+                    render_state,
+                    device,
+                    queue,
+                    self.environment_map.as_deref(),
+                ) {
+                    self.error = Some(error);
+                }
+            }
+            upload_scene(
+                render_state,
+                device,
+                queue,
+                &self.scene,
+                &self.ui_settings,
+                animation_time,
+            );
+            if let Some((render_target, view_axes, view, pixel)) = self.pick_request.take() {
+                let render_data = render_data(
+                    &render_target,
+                    &self.scene.camera,
+                    view_axes,
+                    view,
+                    false,
+                    0,
+                );
+                self.scene.objects.selection = render_state
+                    .pick(device, queue, &render_data, pixel)
+                    .and_then(|id| self.scene.objects.object_from_gpu(id))
+                    .map_or_else(Selection::default, Selection::reveal);
+            }
+            render_state.update_selection(
+                queue,
+                self.scene
+                    .objects
+                    .selection
+                    .id
+                    .and_then(|id| self.scene.objects.gpu_object_id(id)),
+            );
+            if self
+                .scene_statistics
+                .as_ref()
+                .is_none_or(|scene_statistics| {
+                    time - scene_statistics.last_update >= SceneStatistics::UPDATE_INTERVAL
+                })
+            {
+                self.scene_statistics = Some(SceneStatistics::new(
+                    &self.scene.objects,
+                    render_state,
+                    time,
+                ));
+            }
         }
 
+        self.scene.camera.update_transition(dt);
         if !ctx.wants_keyboard_input() && !ctx.is_using_pointer() {
-            ctx.input(|i| self.scene.camera.update(dt, i));
+            ctx.input(|i| {
+                self.scene
+                    .camera
+                    .update(dt, i, &self.ui_settings.key_bindings)
+            });
+
+            // a drag or a text edit is only recorded once it is finished, so it undoes in one step
+            if self.edit_pending && !input_may_edit {
+                self.edit_pending = false;
+                if self.history.record(self.scene.history_state()) {
+                    self.autosave.dirty = true;
+                }
+            }
+
+            let redo = ctx.input_mut(|i| {
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                    egui::Key::Z,
+                ))
+            });
+            let undo = ctx.input_mut(|i| {
+                i.consume_shortcut(&egui::KeyboardShortcut::new(
+                    egui::Modifiers::COMMAND,
+                    egui::Key::Z,
+                ))
+            });
+            if redo {
+                self.redo();
+            } else if undo {
+                self.undo();
+            }
+
+            // F moves the camera along -w by default, so focusing uses the period key like other
+            // editors' "view selected"
+            if ctx.input(|i| i.key_pressed(egui::Key::Period)) {
+                self.scene.objects.selection.focus = true;
+            }
         }
 
-        egui::Window::new("XWZ View")
+        // the recovery file is left alone until it has been restored or discarded
+        if self.recovery.is_none()
+            && let Err(error) = self
+                .autosave
+                .update(time, || serde_json::to_string(&self.scene).unwrap())
+        {
+            self.error = Some(error);
+        }
+
+        if std::mem::take(&mut self.scene.objects.selection.focus)
+            && let Some(id) = self.scene.objects.selection.id
+            && let Some((position, radius)) = self.scene.objects.focus_target(id)
+        {
+            self.scene.camera.focus(position, radius);
+        }
+        if std::mem::take(&mut self.scene.objects.selection.look_at)
+            && let Some(id) = self.scene.objects.selection.id
+            && let Some((position, _)) = self.scene.objects.focus_target(id)
+        {
+            self.scene.camera.look_at(position);
+        }
+
+        let mut changed = false;
+        if self.ui_settings.accumulate || self.ui_settings.only_repaint_on_change {
+            if scene_may_have_changed || self.edit_pending {
+                let scene_state = self.scene.history_state();
+                if scene_state != self.last_scene_state {
+                    self.last_scene_state = scene_state;
+                    changed = true;
+                }
+            }
+            let render_state = serde_json::to_string(&(
+                &self.scene.camera,
+                &self.ui_settings,
+                self.timeline.animation_time(),
+                // the selection and isolation aren't saved with the scene, but they change the views
+                self.scene.objects.selection.id,
+                self.scene.objects.isolated,
+            ))
+            .unwrap();
+            if render_state != self.last_render_state {
+                self.last_render_state = render_state;
+                changed = true;
+            }
+        }
+        if self.ui_settings.accumulate && changed {
+            self.xyz_render_target.reset_accumulation();
+            self.xwz_render_target.reset_accumulation();
+            self.xyw_render_target.reset_accumulation();
+        }
+
+        let filter = if self.ui_settings.bilinear_filtering {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+        self.xyz_render_target.set_filter(device, filter);
+        self.xwz_render_target.set_filter(device, filter);
+        self.xyw_render_target.set_filter(device, filter);
+
+        egui::Window::new(format!("{} View", self.ui_settings.xwz_view_axes))
+            .id(egui::Id::new("XWZ View"))
             .frame(egui::Frame::window(&ctx.style()).inner_margin(egui::Margin::ZERO))
             .open(&mut self.ui_settings.xwz_window_open)
             .resizable(true)
             .show(ctx, |ui| {
-                ui_render_target(
+                view_axes_ui(ui, &mut self.ui_settings.xwz_view_axes);
+                let response = ui_render_target(
                     ui,
                     device,
                     &mut self.xwz_render_target,
                     &self.scene.camera,
-                    ViewAxes::XWZ,
-                    ui.available_size(),
+                    (self.ui_settings.xwz_view_axes, ViewMask::XWZ),
+                    self.ui_settings.accumulate,
+                    self.ui_settings.xwz_render_scale,
                 );
+                let on_gizmo = gizmo::gizmo(
+                    ui,
+                    &response,
+                    &self.scene.camera,
+                    self.ui_settings.xwz_view_axes,
+                    &mut self.scene.objects,
+                );
+                if let Some(pixel) =
+                    clicked_pixel(&response, &self.xwz_render_target).filter(|_| !on_gizmo)
+                {
+                    self.pick_request = Some((
+                        self.xwz_render_target.clone(),
+                        self.ui_settings.xwz_view_axes,
+                        ViewMask::XWZ,
+                        pixel,
+                    ));
+                }
+                mouse_controls(&response, &mut self.scene.camera);
             });
 
-        egui::Window::new("XYW View")
+        egui::Window::new(format!("{} View", self.ui_settings.xyw_view_axes))
+            .id(egui::Id::new("XYW View"))
             .frame(egui::Frame::window(&ctx.style()).inner_margin(egui::Margin::ZERO))
             .open(&mut self.ui_settings.xyw_window_open)
             .resizable(true)
             .show(ctx, |ui| {
-                ui_render_target(
+                view_axes_ui(ui, &mut self.ui_settings.xyw_view_axes);
+                let response = ui_render_target(
                     ui,
                     device,
                     &mut self.xyw_render_target,
                     &self.scene.camera,
-                    ViewAxes::XYW,
-                    ui.available_size(),
+                    (self.ui_settings.xyw_view_axes, ViewMask::XYW),
+                    self.ui_settings.accumulate,
+                    self.ui_settings.xyw_render_scale,
+                );
+                let on_gizmo = gizmo::gizmo(
+                    ui,
+                    &response,
+                    &self.scene.camera,
+                    self.ui_settings.xyw_view_axes,
+                    &mut self.scene.objects,
                 );
+                if let Some(pixel) =
+                    clicked_pixel(&response, &self.xyw_render_target).filter(|_| !on_gizmo)
+                {
+                    self.pick_request = Some((
+                        self.xyw_render_target.clone(),
+                        self.ui_settings.xyw_view_axes,
+                        ViewMask::XYW,
+                        pixel,
+                    ));
+                }
+                mouse_controls(&response, &mut self.scene.camera);
             });
 
         egui::CentralPanel::default()
             .frame(egui::Frame::NONE)
             .show(ctx, |ui| {
-                ui_render_target(
+                let response = ui_render_target(
                     ui,
                     device,
                     &mut self.xyz_render_target,
                     &self.scene.camera,
+                    (ViewAxes::XYZ, ViewMask::XYZ),
+                    self.ui_settings.accumulate,
+                    self.ui_settings.xyz_render_scale,
+                );
+                let on_gizmo = gizmo::gizmo(
+                    ui,
+                    &response,
+                    &self.scene.camera,
                     ViewAxes::XYZ,
-                    ui.available_size(),
+                    &mut self.scene.objects,
                 );
+                if let Some(pixel) =
+                    clicked_pixel(&response, &self.xyz_render_target).filter(|_| !on_gizmo)
+                {
+                    self.pick_request = Some((
+                        self.xyz_render_target.clone(),
+                        ViewAxes::XYZ,
+                        ViewMask::XYZ,
+                        pixel,
+                    ));
+                }
+                mouse_controls(&response, &mut self.scene.camera);
             });
 
-        ctx.request_repaint();
+        // accumulating keeps repainting to add more samples even though nothing changes
+        if !self.ui_settings.only_repaint_on_change || changed || self.ui_settings.accumulate {
+            match self.ui_settings.max_fps {
+                Some(max_fps) => ctx.request_repaint_after(
+                    Duration::from_secs_f64(1.0 / f64::from(max_fps))
+                        .saturating_sub(time.elapsed()),
+                ),
+                None => ctx.request_repaint(),
+            }
+        } else if self.autosave.dirty {
+            // otherwise an edit followed by no input would never be auto-saved
+            ctx.request_repaint_after(Autosave::INTERVAL);
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
@@ -409,12 +1610,31 @@ impl eframe::App for App {
             serde_json::to_string(&self.ui_settings).unwrap(),
         );
         storage.set_string("scene", serde_json::to_string(&self.scene).unwrap());
+        // the scene is restored from storage on the next start, so the recovery file is stale now
+        if self.recovery.is_none() {
+            self.autosave.saved();
+        }
     }
 }
 
 fn main() -> eframe::Result {
+    match headless::Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => {
+            if let Err(error) = headless::render(&options) {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(error) => {
+            eprintln!("{error}\n{}", headless::USAGE);
+            std::process::exit(2);
+        }
+    }
+
     eframe::run_native(
-        "4d Rendering",
+        App::NAME,
         eframe::NativeOptions {
             vsync: false,
             renderer: eframe::Renderer::Wgpu,
@@ -422,13 +1642,7 @@ fn main() -> eframe::Result {
                 present_mode: wgpu::PresentMode::AutoNoVsync,
                 wgpu_setup: eframe::egui_wgpu::WgpuSetup::CreateNew(
                     eframe::egui_wgpu::WgpuSetupCreateNew {
-                        device_descriptor: Arc::new(|adapter| wgpu::DeviceDescriptor {
-                            label: Some("Device"),
-                            required_features: wgpu::Features::PUSH_CONSTANTS,
-                            required_limits: adapter.limits(),
-                            memory_hints: wgpu::MemoryHints::Performance,
-                            trace: wgpu::Trace::Off,
-                        }),
+                        device_descriptor: Arc::new(device_descriptor),
                         ..Default::default()
                     },
                 ),
@@ -440,30 +1654,256 @@ fn main() -> eframe::Result {
     )
 }
 
+fn device_descriptor(adapter: &wgpu::Adapter) -> wgpu::DeviceDescriptor<'static> {
+    wgpu::DeviceDescriptor {
+        label: Some("Device"),
+        // the ray tracing passes are only timed, and the views only filtered, on devices that support
+        // it
+        required_features: wgpu::Features::PUSH_CONSTANTS
+            | (adapter.features()
+                & (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::FLOAT32_FILTERABLE)),
+        required_limits: adapter.limits(),
+        memory_hints: wgpu::MemoryHints::Performance,
+        trace: wgpu::Trace::Off,
+    }
+}
+
+/// Uploads everything about the scene and the render settings that the ray tracer needs, apart
+/// from the selection.
+fn upload_scene(
+    render_state: &mut RenderState,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    scene: &Scene,
+    ui_settings: &UISettings,
+    animation_time: Option<f32>,
+) {
+    render_state.update_hyperspheres(
+        device,
+        queue,
+        scene.objects.gpu_hyperspheres(animation_time),
+    );
+    render_state.update_hyperplanees(device, queue, scene.objects.gpu_hyperplanes(animation_time));
+    render_state.update_tesseracts(device, queue, scene.objects.gpu_tesseracts(animation_time));
+    render_state.update_tori(device, queue, scene.objects.gpu_tori(animation_time));
+    render_state.update_spherinders(device, queue, scene.objects.gpu_spherinders(animation_time));
+    render_state.update_csg_pairs(device, queue, scene.objects.gpu_csg_pairs(animation_time));
+    render_state.update_lights(device, queue, lights::gpu_lights(&scene.lights));
+    render_state.update_headlamp(
+        queue,
+        ui_settings.headlamp_color,
+        if ui_settings.headlamp_enabled {
+            ui_settings.headlamp_intensity
+        } else {
+            0.0
+        },
+    );
+    render_state.update_sky(
+        queue,
+        scene.environment.sky_zenith,
+        scene.environment.sky_horizon,
+        scene.environment.sky_direction,
+    );
+    render_state.update_sun(
+        queue,
+        scene.environment.sun_direction,
+        scene.environment.sun_color,
+        scene.environment.sun_intensity,
+    );
+    render_state.update_fog(
+        queue,
+        scene.environment.fog_color,
+        scene.environment.fog_start,
+        scene.environment.fog_density,
+    );
+    render_state.update_w_falloff(queue, scene.environment.w_falloff);
+    render_state.update_tone_mapping(queue, ui_settings.tone_mapping);
+    render_state.update_show_grid(queue, ui_settings.show_grid);
+    render_state.update_shadow_bias(queue, ui_settings.shadow_bias);
+    render_state.update_shadow_samples(queue, ui_settings.shadow_samples);
+    render_state.update_max_bounces(queue, ui_settings.max_bounces);
+    render_state.update_samples_per_pixel(queue, ui_settings.samples_per_pixel);
+    render_state.update_torus_max_steps(queue, ui_settings.torus_max_steps);
+}
+
+/// Loads the environment map at `path` into the render state, or removes it for `None`. It is
+/// removed as well when it can't be loaded.
+fn upload_environment_map(
+    render_state: &mut RenderState,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    path: Option<&Path>,
+) -> Result<(), String> {
+    let Some(path) = path else {
+        render_state.set_environment(device, queue, None);
+        return Ok(());
+    };
+    match environment::load_environment_map(path, device.limits().max_texture_dimension_2d) {
+        Ok((width, height, pixels)) => {
+            render_state.set_environment(
+                device,
+                queue,
+                Some(EnvironmentMap {
+                    width,
+                    height,
+                    pixels: &pixels,
+                }),
+            );
+            Ok(())
+        }
+        Err(error) => {
+            render_state.set_environment(device, queue, None);
+            Err(error)
+        }
+    }
+}
+
 fn ui_render_target(
     ui: &mut egui::Ui,
     device: &wgpu::Device,
     render_target: &mut RenderTarget,
     camera: &Camera,
-    view_axes: ViewAxes,
-    size: egui::Vec2,
+    (view_axes, view): (ViewAxes, ViewMask),
+    accumulate: bool,
+    render_scale: f32,
 ) -> egui::Response {
-    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::all());
+    let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::all());
 
-    render_target.maybe_resize(device, rect.width() as _, rect.height() as _);
+    let render_size = rect.size() * render_scale.clamp(0.25, 1.0);
+    render_target.maybe_resize(device, render_size.x as _, render_size.y as _);
+    let frame_index = render_target.next_frame_index(camera.transform());
     ui.painter()
         .add(eframe::egui_wgpu::Callback::new_paint_callback(
             rect,
-            RenderData {
-                render_target: render_target.clone(),
-                camera_transform: camera.transform(),
+            render_data(
+                render_target,
+                camera,
                 view_axes,
-            },
+                view,
+                accumulate,
+                frame_index,
+            ),
         ));
 
     response
 }
 
+fn render_data(
+    render_target: &RenderTarget,
+    camera: &Camera,
+    view_axes: ViewAxes,
+    view: ViewMask,
+    accumulate: bool,
+    frame_index: u32,
+) -> RenderData {
+    RenderData {
+        render_target: render_target.clone(),
+        camera_transform: camera.transform(),
+        view_axes,
+        render_mode: camera.render_mode,
+        projection: camera.projection,
+        fov: camera.fov,
+        orthographic_height: camera.orthographic_height,
+        near: camera.near,
+        far: camera.far,
+        view,
+        aperture: camera.aperture,
+        focus_distance: camera.focus_distance,
+        accumulate,
+        frame_index,
+    }
+}
+
+/// Whether this frame's input could have edited the scene, every edit starts with a click, a drag
+/// or a key pressed while a widget has focus.
+fn may_edit_scene(ctx: &egui::Context) -> bool {
+    let focused = ctx.memory(|memory| memory.focused().is_some());
+    ctx.input(|i| {
+        i.pointer.any_pressed()
+            || i.pointer.any_released()
+            || (i.pointer.any_down() && i.pointer.is_moving())
+            || (focused
+                && i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::Key { pressed: true, .. }
+                            | egui::Event::Text(_)
+                            | egui::Event::Paste(_)
+                            | egui::Event::Cut
+                            | egui::Event::Ime(_)
+                    )
+                }))
+    })
+}
+
+/// Turns the camera while a view is dragged with the right mouse button. The cursor is confined to
+/// the window for the length of the drag so that it can't end up over another window.
+///
+/// Scrolling over a view zooms an orbiting camera.
+fn mouse_controls(response: &egui::Response, camera: &mut Camera) {
+    if response.hovered() {
+        let scroll = response.ctx.input(|i| i.smooth_scroll_delta.y);
+        if scroll != 0.0 {
+            camera.zoom(scroll);
+        }
+    }
+    if response.drag_started_by(egui::PointerButton::Secondary) {
+        response
+            .ctx
+            .send_viewport_cmd(egui::ViewportCommand::CursorGrab(
+                egui::CursorGrab::Confined,
+            ));
+    }
+    if response.dragged_by(egui::PointerButton::Secondary) {
+        let ctrl = response.ctx.input(|i| i.modifiers.ctrl);
+        camera.mouse_look(response.drag_delta(), ctrl);
+    }
+    if response.drag_stopped_by(egui::PointerButton::Secondary) {
+        response
+            .ctx
+            .send_viewport_cmd(egui::ViewportCommand::CursorGrab(egui::CursorGrab::None));
+    }
+}
+
+/// Lets the camera axes shown by a view be chosen, picking an axis that is already used by the
+/// view swaps the two so that the three axes stay distinct.
+fn view_axes_ui(ui: &mut egui::Ui, view_axes: &mut ViewAxes) {
+    let ViewAxes { forward, up, right } = *view_axes;
+    let mut axes = [forward, up, right];
+    ui.horizontal(|ui| {
+        for (index, label) in ["Forward", "Up", "Right"].into_iter().enumerate() {
+            let mut axis = axes[index];
+            egui::ComboBox::new(label, label)
+                .selected_text(axis.to_string())
+                .show_ui(ui, |ui| {
+                    for option in CameraAxis::ALL {
+                        ui.selectable_value(&mut axis, option, option.to_string());
+                    }
+                });
+            if let Some(other) = axes.iter().position(|&other| other == axis) {
+                axes[other] = axes[index];
+            }
+            axes[index] = axis;
+        }
+    });
+    let [forward, up, right] = axes;
+    *view_axes = ViewAxes { forward, up, right };
+}
+
+/// Returns the pixel of `render_target` under a click, measured from its top left. The target is
+/// stretched to fill the view when it is rendered at a lower resolution.
+fn clicked_pixel(response: &egui::Response, render_target: &RenderTarget) -> Option<(u32, u32)> {
+    if !response.clicked() {
+        return None;
+    }
+    let (width, height) = render_target.size();
+    let position = (response.interact_pointer_pos()? - response.rect.min) / response.rect.size();
+    Some((
+        (position.x * width as f32).clamp(0.0, width as f32 - 1.0) as u32,
+        (position.y * height as f32).clamp(0.0, height as f32 - 1.0) as u32,
+    ))
+}
+
 fn ui_vector4(
     ui: &mut egui::Ui,
     cgmath::Vector4 { x, y, z, w }: &mut cgmath::Vector4<f32>,
@@ -473,3 +1913,7 @@ fn ui_vector4(
         | ui.add(egui::DragValue::new(z).speed(0.1).prefix("z:"))
         | ui.add(egui::DragValue::new(w).speed(0.1).prefix("w:"))
 }
+
+fn format_vector4(cgmath::Vector4 { x, y, z, w }: cgmath::Vector4<f32>) -> String {
+    format!("({x:.3}, {y:.3}, {z:.3}, {w:.3})")
+}
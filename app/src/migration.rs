@@ -0,0 +1,143 @@
+use serde_json::{Map, Value};
+
+/// The format version written to newly saved scenes. Bump it, and add a migration, whenever a
+/// change to the scene would stop older files from loading.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// `MIGRATIONS[n]` upgrades a scene from version `n` to version `n + 1`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); CURRENT_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+/// Upgrades a serialized scene saved by any older version to the current format, so it can be
+/// deserialized into the current structs.
+pub fn migrate(mut scene: Value) -> Result<Value, String> {
+    let fields = scene
+        .as_object_mut()
+        .ok_or("the scene is not a JSON object")?;
+
+    // scenes saved before versioning was added have no version field
+    let mut version = match fields.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("invalid scene version {version}"))?,
+    };
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "the scene has version {version}, but only versions up to {CURRENT_VERSION} are supported"
+        ));
+    }
+
+    while version < CURRENT_VERSION {
+        MIGRATIONS[version as usize](fields);
+        version += 1;
+    }
+    fields.insert("version".into(), version.into());
+
+    Ok(scene)
+}
+
+/// Every field added before versioning has a default, so unversioned scenes only need the
+/// version field itself.
+fn v0_to_v1(_scene: &mut Map<String, Value>) {}
+
+/// The surface fields of every object moved into a shared `material` field.
+fn v1_to_v2(scene: &mut Map<String, Value>) {
+    const MATERIAL_FIELDS: [&str; 8] = [
+        "color",
+        "reflectivity",
+        "emission_color",
+        "emission_intensity",
+        "opacity",
+        "ior",
+        "pattern",
+        "pattern_scale",
+    ];
+
+    let Some(objects) = scene.get_mut("objects").and_then(Value::as_object_mut) else {
+        return;
+    };
+    for kind in ["hyperspheres", "hyperplanes", "tesseracts", "tori"] {
+        let Some(slots) = objects.get_mut(kind).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        // slotmaps are saved as their slots, with a null value for the free ones
+        for object in slots
+            .iter_mut()
+            .filter_map(|slot| slot.get_mut("value").and_then(Value::as_object_mut))
+        {
+            let material = MATERIAL_FIELDS
+                .iter()
+                .filter_map(|&field| Some((field.to_owned(), object.remove(field)?)))
+                .collect::<Map<_, _>>();
+            object.insert("material".into(), material.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Scene, objects::Material};
+
+    /// A hypersphere with its own color and reflectivity, and a hyperplane that leaves them at
+    /// their defaults, saved before the material fields were grouped.
+    const V1_OBJECTS: &str = r#"{
+        "hyperspheres": [
+            { "value": null, "version": 0 },
+            {
+                "value": {
+                    "name": "Red",
+                    "radius": 2.0,
+                    "color": { "x": 1.0, "y": 0.0, "z": 0.0 },
+                    "reflectivity": 0.5
+                },
+                "version": 1
+            }
+        ],
+        "hyperplanes": [
+            { "value": null, "version": 0 },
+            { "value": { "name": "Ground", "width": 3.0 }, "version": 1 }
+        ]
+    }"#;
+
+    fn scene(version: Option<u32>) -> String {
+        let mut scene = Map::new();
+        if let Some(version) = version {
+            scene.insert("version".into(), version.into());
+        }
+        scene.insert("objects".into(), serde_json::from_str(V1_OBJECTS).unwrap());
+        Value::from(scene).to_string()
+    }
+
+    #[test]
+    fn flat_material_fields_load_into_the_material() {
+        for version in [None, Some(0), Some(1)] {
+            let scene = Scene::deserialize_migrated(&scene(version)).unwrap();
+            assert_eq!(scene.version, CURRENT_VERSION);
+
+            let (_, hypersphere) = scene.objects.hyperspheres.iter().next().unwrap();
+            assert_eq!(hypersphere.name, "Red");
+            assert_eq!(hypersphere.radius, 2.0);
+            assert_eq!(hypersphere.material.color, cgmath::vec3(1.0, 0.0, 0.0));
+            assert_eq!(hypersphere.material.reflectivity, 0.5);
+            assert_eq!(hypersphere.material.opacity, Material::default().opacity);
+
+            let (_, hyperplane) = scene.objects.hyperplanes.iter().next().unwrap();
+            assert_eq!(hyperplane.width, 3.0);
+            assert_eq!(hyperplane.material.color, Material::default().color);
+            assert_eq!(
+                hyperplane.material.reflectivity,
+                Material::default().reflectivity
+            );
+        }
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let newer = scene(Some(CURRENT_VERSION + 1));
+        assert!(migrate(serde_json::from_str(&newer).unwrap()).is_err());
+        assert!(Scene::deserialize_migrated(&newer).is_err());
+        assert!(Scene::deserialize_migrated(&scene(Some(CURRENT_VERSION))).is_ok());
+    }
+}
@@ -1,9 +1,25 @@
 use crate::ui_vector4;
+use cgmath::{Array, ElementWise, InnerSpace};
 use eframe::egui;
 use math::Rotor;
+use rendering::{
+    ViewMask,
+    objects::{CsgOp, Pattern},
+};
 use serde::{Deserialize, Serialize};
 use slotmap::{SlotMap, new_key_type};
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    f32::consts::TAU,
+};
+
+/// The smallest that the radii and sizes of objects can be set to, smaller ones either vanish or
+/// break the intersection math.
+const MIN_SIZE: f32 = 1e-3;
+
+/// How far the magnitude of a composed group transform can be from 1 before the transform UI
+/// warns about it.
+const MAX_TRANSFORM_DRIFT: f32 = 1e-4;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Transform {
@@ -36,15 +52,50 @@ impl Default for Transform {
 }
 
 impl Transform {
+    /// An unrotated transform that moves to `position`.
+    pub fn at(position: cgmath::Vector4<f32>) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
     pub fn transform(&self) -> math::Transform {
-        math::Transform::translation(self.position).then(math::Transform::from_rotor(
-            Rotor::rotate_xy(self.xy_rotation)
-                .then(Rotor::rotate_xz(self.xz_rotation))
-                .then(Rotor::rotate_xw(self.xw_rotation))
-                .then(Rotor::rotate_yz(self.yz_rotation))
-                .then(Rotor::rotate_yw(self.yw_rotation))
-                .then(Rotor::rotate_zw(self.zw_rotation)),
-        ))
+        math::Transform::translation(self.position).then(math::Transform::from_rotor(self.rotor()))
+    }
+
+    /// Mirrors the transform across the hyperplane through the origin that is perpendicular to
+    /// `axis`, which is 0 to 3 for x to w.
+    ///
+    /// A mirror can't be written as a rotation, so the result is the reflection applied on both
+    /// sides of the transform. The reflection's own effect on the object's shape is left out,
+    /// which makes no difference to shapes that are symmetric along their own axes. Reflecting
+    /// on both sides negates the axis' coordinate of the position. It also reverses each of the
+    /// six rotations whose plane contains the axis and leaves the others alone, since reflecting
+    /// each rotation in the chain in [`Transform::rotor`] reflects the whole chain. Every change
+    /// is a negation, so mirroring across the same axis twice gives back exactly the original.
+    pub fn mirrored(&self, axis: usize) -> Self {
+        let flip = |a: usize, b: usize| if a == axis || b == axis { -1.0 } else { 1.0 };
+        let mut position = self.position;
+        position[axis] = -position[axis];
+        Self {
+            position,
+            xy_rotation: self.xy_rotation * flip(0, 1),
+            xz_rotation: self.xz_rotation * flip(0, 2),
+            xw_rotation: self.xw_rotation * flip(0, 3),
+            yz_rotation: self.yz_rotation * flip(1, 2),
+            yw_rotation: self.yw_rotation * flip(1, 3),
+            zw_rotation: self.zw_rotation * flip(2, 3),
+        }
+    }
+
+    pub fn rotor(&self) -> Rotor {
+        Rotor::rotate_xy(self.xy_rotation)
+            .then(Rotor::rotate_xz(self.xz_rotation))
+            .then(Rotor::rotate_xw(self.xw_rotation))
+            .then(Rotor::rotate_yz(self.yz_rotation))
+            .then(Rotor::rotate_yw(self.yw_rotation))
+            .then(Rotor::rotate_zw(self.zw_rotation))
     }
 
     pub fn ui(&mut self, ui: &mut egui::Ui) {
@@ -79,10 +130,52 @@ impl Transform {
     }
 }
 
+/// The transform an object has at `time` seconds into the timeline.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    pub time: f32,
+    pub transform: Transform,
+}
+
+impl Keyframe {
+    /// Interpolates between the keyframes either side of `time`, holding the first and last
+    /// keyframes before and after them. The keyframes do not need to be sorted.
+    ///
+    /// Returns `None` if there are no keyframes.
+    pub fn sample(keyframes: &[Keyframe], time: f32) -> Option<math::Transform> {
+        let before = keyframes
+            .iter()
+            .filter(|keyframe| keyframe.time <= time)
+            .max_by(|a, b| a.time.total_cmp(&b.time));
+        let after = keyframes
+            .iter()
+            .filter(|keyframe| keyframe.time > time)
+            .min_by(|a, b| a.time.total_cmp(&b.time));
+        let (before, after) = match (before, after) {
+            (Some(before), Some(after)) => (before, after),
+            (Some(keyframe), None) | (None, Some(keyframe)) => {
+                return Some(keyframe.transform.transform());
+            }
+            (None, None) => return None,
+        };
+
+        let t = (time - before.time) / (after.time - before.time);
+        let position =
+            before.transform.position + (after.transform.position - before.transform.position) * t;
+        let rotor = before.transform.rotor().slerp(after.transform.rotor(), t);
+        Some(math::Transform::translation(position).then(math::Transform::from_rotor(rotor)))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Group {
     pub name: String,
+    /// Hides every object in the group and in the groups nested in it.
+    pub visible: bool,
+    /// The group this group is nested in, its transform is relative to the parent's.
+    pub parent: Option<GroupID>,
     pub transform: Transform,
 }
 
@@ -90,33 +183,103 @@ impl Default for Group {
     fn default() -> Self {
         Self {
             name: "Default Group".into(),
+            visible: true,
+            parent: None,
             transform: Transform::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    /// Linear RGB, which is also what egui's `color_edit_button_rgb` edits, so no conversion is
+    /// needed around the color pickers.
+    pub color: cgmath::Vector3<f32>,
+    pub reflectivity: f32,
+    pub emission_color: cgmath::Vector3<f32>,
+    pub emission_intensity: f32,
+    /// 1 is fully opaque, anything less lets the objects behind show through.
+    pub opacity: f32,
+    /// The index of refraction, only visible when the object isn't fully opaque.
+    pub ior: f32,
+    pub pattern: Pattern,
+    pub pattern_scale: f32,
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Material {
+    fn gpu(&self) -> rendering::objects::Material {
+        rendering::objects::Material {
+            color: self.color,
+            reflectivity: self.reflectivity,
+            emission: self.emission_color * self.emission_intensity,
+            opacity: self.opacity,
+            ior: self.ior,
+            pattern: self.pattern.into(),
+            pattern_scale: self.pattern_scale,
+            specular: self.specular,
+            shininess: self.shininess,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            reflectivity: 0.0,
+            emission_color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            emission_intensity: 0.0,
+            opacity: 1.0,
+            ior: 1.5,
+            pattern: Pattern::Solid,
+            pattern_scale: 1.0,
+            specular: 0.0,
+            shininess: 32.0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Hypersphere {
     pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
     pub group: Option<GroupID>,
     pub transform: Transform,
+    /// Stretches the object along its local axes, applied before its transform.
+    pub scale: cgmath::Vector4<f32>,
     pub radius: f32,
-    pub color: cgmath::Vector3<f32>,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
 }
 
 impl Default for Hypersphere {
     fn default() -> Self {
         Self {
             name: "Default Hypersphere".into(),
+            visible: true,
+            views: ViewMask::ALL,
             group: None,
             transform: Transform::default(),
+            scale: cgmath::Vector4::from_value(1.0),
             radius: 1.0,
-            color: cgmath::Vector3 {
-                x: 1.0,
-                y: 1.0,
-                z: 1.0,
-            },
+            material: Material::default(),
+            keyframes: vec![],
         }
     }
 }
@@ -125,28 +288,250 @@ impl Default for Hypersphere {
 #[serde(default)]
 pub struct Hyperplane {
     pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
     pub group: Option<GroupID>,
     pub transform: Transform,
+    /// Stretches the object along its local axes, applied before its transform.
+    pub scale: cgmath::Vector4<f32>,
     pub width: f32,
     pub height: f32,
     pub depth: f32,
-    pub color: cgmath::Vector3<f32>,
+    pub infinite: bool,
+    /// Whether the back of the hyperplane, the side opposite its local +y axis, is visible too.
+    pub double_sided: bool,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
 }
 
 impl Default for Hyperplane {
     fn default() -> Self {
         Self {
             name: "Default Hyperplane".into(),
+            visible: true,
+            views: ViewMask::ALL,
             group: None,
             transform: Transform::default(),
+            scale: cgmath::Vector4::from_value(1.0),
             width: 1.0,
             height: 1.0,
             depth: 1.0,
-            color: cgmath::Vector3 {
+            infinite: false,
+            double_sided: true,
+            material: Material::default(),
+            keyframes: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tesseract {
+    pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
+    pub group: Option<GroupID>,
+    pub transform: Transform,
+    /// Stretches the object along its local axes, applied before its transform.
+    pub scale: cgmath::Vector4<f32>,
+    pub size: cgmath::Vector4<f32>,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Default for Tesseract {
+    fn default() -> Self {
+        Self {
+            name: "Default Tesseract".into(),
+            visible: true,
+            views: ViewMask::ALL,
+            group: None,
+            transform: Transform::default(),
+            scale: cgmath::Vector4::from_value(1.0),
+            size: cgmath::Vector4 {
                 x: 1.0,
                 y: 1.0,
                 z: 1.0,
+                w: 1.0,
+            },
+            material: Material::default(),
+            keyframes: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Torus {
+    pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
+    pub group: Option<GroupID>,
+    pub transform: Transform,
+    /// The radius of the circles in the xy and zw planes that the torus is built around.
+    pub major_radius: f32,
+    /// How far the surface is from the clifford torus made by those circles.
+    pub minor_radius: f32,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Torus {
+    /// The radius of a hypersphere around the center of the torus that contains it.
+    fn bounding_radius(&self) -> f32 {
+        std::f32::consts::SQRT_2 * self.major_radius.abs() + self.minor_radius.abs()
+    }
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self {
+            name: "Default Torus".into(),
+            visible: true,
+            views: ViewMask::ALL,
+            group: None,
+            transform: Transform::default(),
+            major_radius: 1.0,
+            minor_radius: 0.25,
+            material: Material::default(),
+            keyframes: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spherinder {
+    pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
+    pub group: Option<GroupID>,
+    pub transform: Transform,
+    /// The radius of the 3-ball in the xzw hyperplane that is extruded.
+    pub radius: f32,
+    /// How far the ball is extruded along local y, half of it on either side of the center.
+    pub length: f32,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Spherinder {
+    /// The radius of a hypersphere around the center of the spherinder that contains it.
+    fn bounding_radius(&self) -> f32 {
+        self.radius.hypot(self.length * 0.5)
+    }
+}
+
+impl Default for Spherinder {
+    fn default() -> Self {
+        Self {
+            name: "Default Spherinder".into(),
+            visible: true,
+            views: ViewMask::ALL,
+            group: None,
+            transform: Transform::default(),
+            radius: 0.5,
+            length: 2.0,
+            material: Material::default(),
+            keyframes: vec![],
+        }
+    }
+}
+
+/// One of the two hyperspheres in a [`CsgPair`], placed relative to the pair's transform.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsgSphere {
+    pub transform: Transform,
+    /// Stretches the hypersphere along its local axes, applied before its transform.
+    pub scale: cgmath::Vector4<f32>,
+    pub radius: f32,
+}
+
+impl CsgSphere {
+    /// The radius of a hypersphere around the center of the pair that contains this hypersphere.
+    fn bounding_radius(&self) -> f32 {
+        let scale = self.scale.map(f32::abs);
+        self.transform.position.magnitude()
+            + self.radius.abs() * scale.x.max(scale.y).max(scale.z).max(scale.w)
+    }
+
+    fn gpu(&self, pair_transform: math::Transform) -> rendering::objects::Hypersphere {
+        rendering::objects::Hypersphere {
+            transform: pair_transform.then(self.transform.transform()),
+            scale: self.scale,
+            material: rendering::objects::Material::default(),
+            radius: self.radius,
+            views: ViewMask::ALL.0,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+impl Default for CsgSphere {
+    fn default() -> Self {
+        Self {
+            transform: Transform::default(),
+            scale: cgmath::Vector4::from_value(1.0),
+            radius: 1.0,
+        }
+    }
+}
+
+/// Two hyperspheres combined into one shape by a boolean operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsgPair {
+    pub name: String,
+    pub visible: bool,
+    /// Which of the views the object is drawn in, it can still be edited when it isn't drawn.
+    pub views: ViewMask,
+    pub group: Option<GroupID>,
+    pub transform: Transform,
+    pub op: CsgOp,
+    pub a: CsgSphere,
+    pub b: CsgSphere,
+    pub material: Material,
+    /// Replaces `transform` while the timeline is playing, if there are any.
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CsgPair {
+    /// The radius of a hypersphere around the center of the pair that contains both of its
+    /// hyperspheres.
+    fn bounding_radius(&self) -> f32 {
+        self.a.bounding_radius().max(self.b.bounding_radius())
+    }
+}
+
+impl Default for CsgPair {
+    fn default() -> Self {
+        Self {
+            name: "Default CSG Pair".into(),
+            visible: true,
+            views: ViewMask::ALL,
+            group: None,
+            transform: Transform::default(),
+            op: CsgOp::Difference,
+            a: CsgSphere::default(),
+            b: CsgSphere {
+                transform: Transform {
+                    position: cgmath::Vector4::new(0.0, 0.0, 0.75, 0.0),
+                    ..Default::default()
+                },
+                radius: 0.75,
+                ..Default::default()
             },
+            material: Material::default(),
+            keyframes: vec![],
         }
     }
 }
@@ -155,6 +540,158 @@ new_key_type! {
     pub struct GroupID;
     pub struct HypersphereID;
     pub struct HyperplaneID;
+    pub struct TesseractID;
+    pub struct TorusID;
+    pub struct SpherinderID;
+    pub struct CsgPairID;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum ObjectID {
+    Hypersphere(HypersphereID),
+    Hyperplane(HyperplaneID),
+    Tesseract(TesseractID),
+    Torus(TorusID),
+    Spherinder(SpherinderID),
+    CsgPair(CsgPairID),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    /// The object that was clicked last, which is the one that is highlighted in the views and
+    /// that focusing and range selection start from.
+    pub id: Option<ObjectID>,
+    /// The other objects selected along with `id` by ctrl or shift clicking them in the list,
+    /// which the bulk actions apply to as well.
+    pub others: BTreeSet<ObjectID>,
+    /// Set when the selection changes from outside of the objects panel, so that the next time it
+    /// is drawn it opens and scrolls to the selected object.
+    pub reveal: bool,
+    /// Set to have the camera frame the selected object, cleared once it has.
+    pub focus: bool,
+    /// Set to have the camera turn to face the selected object without moving, cleared once it
+    /// has.
+    pub look_at: bool,
+    /// Set to the axis to duplicate the selected objects mirrored across, cleared once they have
+    /// been.
+    pub mirror: Option<usize>,
+}
+
+impl Selection {
+    pub fn reveal(id: ObjectID) -> Self {
+        Self {
+            id: Some(id),
+            others: BTreeSet::new(),
+            reveal: true,
+            focus: false,
+            look_at: false,
+            mirror: None,
+        }
+    }
+
+    pub fn contains(&self, id: ObjectID) -> bool {
+        self.id == Some(id) || self.others.contains(&id)
+    }
+
+    /// All of the selected objects, starting with `id`.
+    pub fn ids(&self) -> impl Iterator<Item = ObjectID> + '_ {
+        self.id.into_iter().chain(
+            self.others
+                .iter()
+                .copied()
+                .filter(|&other| Some(other) != self.id),
+        )
+    }
+
+    /// Updates the selection after the header of `id` was clicked in a list showing `listed`.
+    /// A plain click selects only that object, ctrl toggles it and shift selects every object
+    /// listed between it and the last clicked one.
+    fn click(&mut self, modifiers: egui::Modifiers, id: ObjectID, listed: &[ObjectID]) {
+        if modifiers.command {
+            if self.id == Some(id) {
+                self.id = self.others.pop_first();
+            } else if !self.others.remove(&id) {
+                self.others.extend(self.id.replace(id));
+            }
+        } else if modifiers.shift
+            && let Some(anchor) = self.id
+            && let Some(start) = listed.iter().position(|&listed| listed == anchor)
+            && let Some(end) = listed.iter().position(|&listed| listed == id)
+        {
+            self.others = listed[start.min(end)..=start.max(end)]
+                .iter()
+                .copied()
+                .filter(|&listed| listed != anchor)
+                .collect();
+        } else {
+            self.id = Some(id);
+            self.others.clear();
+        }
+    }
+}
+
+/// An object as it is put on the clipboard. Group ids mean nothing to another scene, so the groups
+/// it is nested in are copied along with it, innermost first.
+#[derive(Serialize, Deserialize)]
+struct CopiedObject {
+    groups: Vec<Group>,
+    object: CopiedKind,
+}
+
+#[derive(Serialize, Deserialize)]
+enum CopiedKind {
+    Hypersphere(Hypersphere),
+    Hyperplane(Hyperplane),
+    Tesseract(Tesseract),
+    Torus(Torus),
+    Spherinder(Spherinder),
+    CsgPair(CsgPair),
+}
+
+impl CopiedObject {
+    fn new(groups: &SlotMap<GroupID, Group>, group: Option<GroupID>, object: CopiedKind) -> Self {
+        Self {
+            groups: Objects::ancestors(groups, group)
+                .map(|id| groups[id].clone())
+                .collect(),
+            object,
+        }
+    }
+
+    fn copy(self, ctx: &egui::Context) {
+        ctx.copy_text(serde_json::to_string(&self).unwrap());
+    }
+}
+
+/// The objects and groups directly inside of a group.
+#[derive(Default)]
+struct GroupedObjects {
+    groups: Vec<GroupID>,
+    hyperspheres: Vec<HypersphereID>,
+    hyperplanes: Vec<HyperplaneID>,
+    tesseracts: Vec<TesseractID>,
+    tori: Vec<TorusID>,
+    spherinders: Vec<SpherinderID>,
+    csg_pairs: Vec<CsgPairID>,
+}
+
+/// The changes made while drawing the grouped view, applied once it has been drawn.
+#[derive(Default)]
+struct GroupedEdits {
+    groups_to_duplicate: Vec<GroupID>,
+    groups_to_delete: Vec<GroupID>,
+    hyperspheres_to_insert: Vec<Hypersphere>,
+    hyperspheres_to_delete: Vec<HypersphereID>,
+    hyperplanes_to_insert: Vec<Hyperplane>,
+    hyperplanes_to_delete: Vec<HyperplaneID>,
+    tesseracts_to_insert: Vec<Tesseract>,
+    tesseracts_to_delete: Vec<TesseractID>,
+    tori_to_insert: Vec<Torus>,
+    tori_to_delete: Vec<TorusID>,
+    spherinders_to_insert: Vec<Spherinder>,
+    spherinders_to_delete: Vec<SpherinderID>,
+    csg_pairs_to_insert: Vec<CsgPair>,
+    csg_pairs_to_delete: Vec<CsgPairID>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -163,10 +700,34 @@ pub struct Objects {
     pub groups: SlotMap<GroupID, Group>,
     pub hyperspheres: SlotMap<HypersphereID, Hypersphere>,
     pub hyperplanes: SlotMap<HyperplaneID, Hyperplane>,
+    pub tesseracts: SlotMap<TesseractID, Tesseract>,
+    pub tori: SlotMap<TorusID, Torus>,
+    pub spherinders: SlotMap<SpherinderID, Spherinder>,
+    pub csg_pairs: SlotMap<CsgPairID, CsgPair>,
+    #[serde(skip)]
+    pub selection: Selection,
+    /// A group that was just created, the next time the panel is drawn it opens and scrolls to it.
+    #[serde(skip)]
+    scroll_to_group: Option<GroupID>,
+    /// While set only the objects in this group, or the ones in no group for `Some(None)`, are
+    /// rendered.
+    #[serde(skip)]
+    pub isolated: Option<Option<GroupID>>,
 }
 
 impl Objects {
     pub fn cleanup_invalid_ids(&mut self) {
+        // a group whose parent is gone, or which has ended up as its own ancestor, is moved to the
+        // top level
+        let group_ids = self.groups.keys().collect::<Vec<_>>();
+        for id in group_ids {
+            if let Some(parent) = self.groups[id].parent
+                && (!self.groups.contains_key(parent)
+                    || Self::ancestors(&self.groups, Some(parent)).any(|ancestor| ancestor == id))
+            {
+                self.groups[id].parent = None;
+            }
+        }
         for hypersphere in self.hyperspheres.values_mut() {
             if let Some(group) = hypersphere.group
                 && !self.groups.contains_key(group)
@@ -181,387 +742,2446 @@ impl Objects {
                 hyperplane.group = None;
             }
         }
+        for tesseract in self.tesseracts.values_mut() {
+            if let Some(group) = tesseract.group
+                && !self.groups.contains_key(group)
+            {
+                tesseract.group = None;
+            }
+        }
+        for torus in self.tori.values_mut() {
+            if let Some(group) = torus.group
+                && !self.groups.contains_key(group)
+            {
+                torus.group = None;
+            }
+        }
+        for spherinder in self.spherinders.values_mut() {
+            if let Some(group) = spherinder.group
+                && !self.groups.contains_key(group)
+            {
+                spherinder.group = None;
+            }
+        }
+        for csg_pair in self.csg_pairs.values_mut() {
+            if let Some(group) = csg_pair.group
+                && !self.groups.contains_key(group)
+            {
+                csg_pair.group = None;
+            }
+        }
+        if let Some(id) = self.selection.id
+            && self.object_group(id).is_none()
+        {
+            self.selection = Selection::default();
+        }
+        let others = std::mem::take(&mut self.selection.others);
+        self.selection.others = others
+            .into_iter()
+            .filter(|&id| self.object_group(id).is_some())
+            .collect();
+        if let Some(Some(id)) = self.isolated
+            && !self.groups.contains_key(id)
+        {
+            self.isolated = None;
+        }
     }
 
-    pub fn flat_ui(&mut self, ui: &mut egui::Ui) {
-        ui.collapsing("Groups", |ui| {
-            let mut new_id = None;
-            if ui.button("New Group").clicked() {
-                new_id = Some(self.groups.insert(Group::default()));
-            }
-            let mut to_delete = vec![];
-            for (id, group) in &mut self.groups {
-                let response =
-                    egui::CollapsingHeader::new(&group.name)
-                        .id_salt(id)
-                        .show(ui, |ui| {
-                            ui.horizontal(|ui| {
-                                ui.label("Name:");
-                                ui.text_edit_singleline(&mut group.name);
-                            });
-                            ui.collapsing("Transform", |ui| {
-                                group.transform.ui(ui);
-                            });
-                            if ui.button("Delete").clicked() {
-                                to_delete.push(id);
-                            }
-                        });
-                if new_id == Some(id) {
-                    ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
-                }
+    /// Returns the group of the object, or `None` if the object doesn't exist.
+    pub fn object_group(&self, id: ObjectID) -> Option<Option<GroupID>> {
+        match id {
+            ObjectID::Hypersphere(id) => self.hyperspheres.get(id).map(|object| object.group),
+            ObjectID::Hyperplane(id) => self.hyperplanes.get(id).map(|object| object.group),
+            ObjectID::Tesseract(id) => self.tesseracts.get(id).map(|object| object.group),
+            ObjectID::Torus(id) => self.tori.get(id).map(|object| object.group),
+            ObjectID::Spherinder(id) => self.spherinders.get(id).map(|object| object.group),
+            ObjectID::CsgPair(id) => self.csg_pairs.get(id).map(|object| object.group),
+        }
+    }
+
+    /// Moves an object by `offset` in world space, whatever groups it is in.
+    pub fn translate(&mut self, id: ObjectID, offset: cgmath::Vector4<f32>) {
+        let Some((transform, group)) = (match id {
+            ObjectID::Hypersphere(id) => self
+                .hyperspheres
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+            ObjectID::Hyperplane(id) => self
+                .hyperplanes
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+            ObjectID::Tesseract(id) => self
+                .tesseracts
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+            ObjectID::Torus(id) => self
+                .tori
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+            ObjectID::Spherinder(id) => self
+                .spherinders
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+            ObjectID::CsgPair(id) => self
+                .csg_pairs
+                .get_mut(id)
+                .map(|object| (&mut object.transform, object.group)),
+        }) else {
+            return;
+        };
+        let parent = Self::group_transform(&self.groups, math::Transform::identity(), group);
+        transform.position += parent.reverse().transform_direction(offset);
+    }
+
+    /// Returns the global position of the object and the radius of a hypersphere around that
+    /// position that contains it, or `None` if the object doesn't exist.
+    pub fn focus_target(&self, id: ObjectID) -> Option<(cgmath::Vector4<f32>, f32)> {
+        Some(match id {
+            ObjectID::Hypersphere(id) => {
+                let hypersphere = self.hyperspheres.get(id)?;
+                let scale = hypersphere.scale.map(f32::abs);
+                (
+                    Self::global_transform(&self.groups, &hypersphere.transform, hypersphere.group)
+                        .position(),
+                    hypersphere.radius.abs() * scale.x.max(scale.y).max(scale.z).max(scale.w),
+                )
             }
-            for id in to_delete {
-                self.groups.remove(id);
+            ObjectID::Hyperplane(id) => {
+                let hyperplane = self.hyperplanes.get(id)?;
+                let extents = cgmath::Vector3 {
+                    x: hyperplane.height * hyperplane.scale.x,
+                    y: hyperplane.width * hyperplane.scale.z,
+                    z: hyperplane.depth * hyperplane.scale.w,
+                };
+                (
+                    Self::global_transform(&self.groups, &hyperplane.transform, hyperplane.group)
+                        .position(),
+                    // an infinite hyperplane has no size to frame, so the camera just looks at it
+                    if hyperplane.infinite {
+                        1.0
+                    } else {
+                        extents.magnitude() * 0.5
+                    },
+                )
             }
-        });
-        ui.collapsing("Hyperspheres", |ui| {
-            let mut new_id = None;
-            if ui.button("New Hypersphere").clicked() {
-                new_id = Some(self.hyperspheres.insert(Hypersphere::default()));
-            }
-            let mut to_insert = vec![];
-            let mut to_delete = vec![];
-            let ids = self.hyperspheres.keys().collect::<Vec<_>>();
-            Self::hyperspheres_ui(
-                ui,
-                &self.groups,
-                &mut self.hyperspheres,
-                ids.into_iter(),
-                new_id,
-                &mut to_insert,
-                &mut to_delete,
-            );
-            for id in to_delete {
-                self.hyperspheres.remove(id);
+            ObjectID::Tesseract(id) => {
+                let tesseract = self.tesseracts.get(id)?;
+                (
+                    Self::global_transform(&self.groups, &tesseract.transform, tesseract.group)
+                        .position(),
+                    tesseract.size.mul_element_wise(tesseract.scale).magnitude() * 0.5,
+                )
             }
-            for hypersphere in to_insert {
-                self.hyperspheres.insert(hypersphere);
+            ObjectID::Torus(id) => {
+                let torus = self.tori.get(id)?;
+                (
+                    Self::global_transform(&self.groups, &torus.transform, torus.group).position(),
+                    torus.bounding_radius(),
+                )
             }
-        });
-        ui.collapsing("Hyperplanes", |ui| {
-            let mut new_id = None;
-            if ui.button("New Hyperplane").clicked() {
-                new_id = Some(self.hyperplanes.insert(Hyperplane::default()));
-            }
-            let mut to_insert = vec![];
-            let mut to_delete = vec![];
-            let ids = self.hyperplanes.keys().collect::<Vec<_>>();
-            Self::hyperplanes_ui(
-                ui,
-                &self.groups,
-                &mut self.hyperplanes,
-                ids.into_iter(),
-                new_id,
-                &mut to_insert,
-                &mut to_delete,
-            );
-            for id in to_delete {
-                self.hyperplanes.remove(id);
+            ObjectID::Spherinder(id) => {
+                let spherinder = self.spherinders.get(id)?;
+                (
+                    Self::global_transform(&self.groups, &spherinder.transform, spherinder.group)
+                        .position(),
+                    spherinder.bounding_radius(),
+                )
             }
-            for hyperplane in to_insert {
-                self.hyperplanes.insert(hyperplane);
+            ObjectID::CsgPair(id) => {
+                let csg_pair = self.csg_pairs.get(id)?;
+                (
+                    Self::global_transform(&self.groups, &csg_pair.transform, csg_pair.group)
+                        .position(),
+                    csg_pair.bounding_radius(),
+                )
             }
-        });
-        self.cleanup_invalid_ids();
+        })
     }
 
-    pub fn grouped_ui(&mut self, ui: &mut egui::Ui) {
-        let mut new_group_id = None;
-        if ui.button("New Group").clicked() {
-            new_group_id = Some(self.groups.insert(Group::default()));
+    /// Maps an object picked on the GPU back to its key, this relies on the visible objects being
+    /// uploaded in the order that the slotmaps iterate in.
+    pub fn object_from_gpu(&self, id: rendering::objects::ObjectId) -> Option<ObjectID> {
+        match id {
+            rendering::objects::ObjectId::Hypersphere(index) => self
+                .hyperspheres
+                .iter()
+                .filter(|(_, hypersphere)| self.is_visible(hypersphere.visible, hypersphere.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::Hypersphere(id)),
+            rendering::objects::ObjectId::Hyperplane(index) => self
+                .hyperplanes
+                .iter()
+                .filter(|(_, hyperplane)| self.is_visible(hyperplane.visible, hyperplane.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::Hyperplane(id)),
+            rendering::objects::ObjectId::Tesseract(index) => self
+                .tesseracts
+                .iter()
+                .filter(|(_, tesseract)| self.is_visible(tesseract.visible, tesseract.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::Tesseract(id)),
+            rendering::objects::ObjectId::Torus(index) => self
+                .tori
+                .iter()
+                .filter(|(_, torus)| self.is_visible(torus.visible, torus.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::Torus(id)),
+            rendering::objects::ObjectId::Spherinder(index) => self
+                .spherinders
+                .iter()
+                .filter(|(_, spherinder)| self.is_visible(spherinder.visible, spherinder.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::Spherinder(id)),
+            rendering::objects::ObjectId::CsgPair(index) => self
+                .csg_pairs
+                .iter()
+                .filter(|(_, csg_pair)| self.is_visible(csg_pair.visible, csg_pair.group))
+                .nth(index as usize)
+                .map(|(id, _)| ObjectID::CsgPair(id)),
         }
-        let mut groups_to_delete = vec![];
+    }
 
-        let mut new_hypersphere_id = None;
-        if ui.button("New Hypersphere").clicked() {
-            new_hypersphere_id = Some(self.hyperspheres.insert(Hypersphere::default()));
+    /// The inverse of [`Objects::object_from_gpu`], `None` if the object doesn't exist or isn't
+    /// drawn.
+    pub fn gpu_object_id(&self, id: ObjectID) -> Option<rendering::objects::ObjectId> {
+        fn position<K: slotmap::Key, V>(
+            objects: &SlotMap<K, V>,
+            id: K,
+            is_visible: impl Fn(&V) -> bool,
+        ) -> Option<u32> {
+            if !is_visible(objects.get(id)?) {
+                return None;
+            }
+            let index = objects
+                .iter()
+                .filter(|(_, object)| is_visible(object))
+                .position(|(other, _)| other == id)?;
+            Some(index.try_into().unwrap())
         }
-        let mut hyperspheres_to_insert = vec![];
-        let mut hyperspheres_to_delete = vec![];
 
-        let mut new_hyperplane_id = None;
-        if ui.button("New Hyperplane").clicked() {
-            new_hyperplane_id = Some(self.hyperplanes.insert(Hyperplane::default()));
-        }
-        let mut hyperplanes_to_insert = vec![];
-        let mut hyperplanes_to_delete = vec![];
+        Some(match id {
+            ObjectID::Hypersphere(id) => rendering::objects::ObjectId::Hypersphere(position(
+                &self.hyperspheres,
+                id,
+                |object| self.is_visible(object.visible, object.group),
+            )?),
+            ObjectID::Hyperplane(id) => rendering::objects::ObjectId::Hyperplane(position(
+                &self.hyperplanes,
+                id,
+                |object| self.is_visible(object.visible, object.group),
+            )?),
+            ObjectID::Tesseract(id) => {
+                rendering::objects::ObjectId::Tesseract(position(&self.tesseracts, id, |object| {
+                    self.is_visible(object.visible, object.group)
+                })?)
+            }
+            ObjectID::Torus(id) => {
+                rendering::objects::ObjectId::Torus(position(&self.tori, id, |object| {
+                    self.is_visible(object.visible, object.group)
+                })?)
+            }
+            ObjectID::Spherinder(id) => rendering::objects::ObjectId::Spherinder(position(
+                &self.spherinders,
+                id,
+                |object| self.is_visible(object.visible, object.group),
+            )?),
+            ObjectID::CsgPair(id) => {
+                rendering::objects::ObjectId::CsgPair(position(&self.csg_pairs, id, |object| {
+                    self.is_visible(object.visible, object.group)
+                })?)
+            }
+        })
+    }
 
-        #[derive(Default)]
-        struct GroupedObjects {
-            hyperspheres: Vec<HypersphereID>,
-            hyperplanes: Vec<HyperplaneID>,
-        }
-        let mut grouped_objects = BTreeMap::<Option<GroupID>, GroupedObjects>::new();
-        for id in self.groups.keys() {
-            grouped_objects.entry(Some(id)).or_default();
-        }
-        for (id, hypersphere) in &self.hyperspheres {
-            grouped_objects
-                .entry(hypersphere.group)
-                .or_default()
-                .hyperspheres
-                .push(id);
-        }
+    /// Whether an object is drawn, which it isn't if it or any of the groups it is in are hidden,
+    /// or if it is outside of the isolated group.
+    fn is_visible(&self, visible: bool, group: Option<GroupID>) -> bool {
+        let in_isolated = match self.isolated {
+            None => true,
+            Some(None) => group.is_none(),
+            Some(Some(isolated)) => Self::ancestors(&self.groups, group).any(|id| id == isolated),
+        };
+        in_isolated
+            && visible
+            && Self::ancestors(&self.groups, group).all(|id| self.groups[id].visible)
+    }
+
+    /// Shows which group is isolated, if any, with a button to render everything again.
+    pub fn isolation_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(isolated) = self.isolated else {
+            return;
+        };
+        let name = match isolated {
+            Some(id) => self.groups.get(id).map_or("Invalid", |group| &group.name),
+            None => "None",
+        };
+        egui::Frame::group(ui.style())
+            .stroke(egui::Stroke::new(1.0, ui.visuals().warn_fg_color))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Only rendering group '{name}'"),
+                    );
+                    if ui.button("Show All").clicked() {
+                        self.isolated = None;
+                    }
+                });
+            });
+    }
+
+    /// Draws the actions that apply to every selected object, while more than one is selected.
+    pub fn bulk_ui(&mut self, ui: &mut egui::Ui) {
+        let ids = self.selection.ids().collect::<Vec<_>>();
+        let &[first, _, ..] = ids.as_slice() else {
+            return;
+        };
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} objects selected", ids.len()));
+                if ui.button("Clear").clicked() {
+                    self.selection.others.clear();
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Show").clicked() {
+                    for &id in &ids {
+                        if let Some((visible, _, _)) = self.shared_mut(id) {
+                            *visible = true;
+                        }
+                    }
+                }
+                if ui.button("Hide").clicked() {
+                    for &id in &ids {
+                        if let Some((visible, _, _)) = self.shared_mut(id) {
+                            *visible = false;
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Group:");
+                let mut new_group = None;
+                egui::ComboBox::new("Bulk Group", "")
+                    .selected_text("Set")
+                    .show_ui(ui, |ui| {
+                        if ui.selectable_label(false, "None").clicked() {
+                            new_group = Some(None);
+                        }
+                        for (id, group) in &self.groups {
+                            if ui.selectable_label(false, &group.name).clicked() {
+                                new_group = Some(Some(id));
+                            }
+                        }
+                    });
+                if let Some(new_group) = new_group {
+                    for &id in &ids {
+                        if let Some((_, group, _)) = self.shared_mut(id) {
+                            *group = new_group;
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                // starts from the color of the last clicked object
+                let Some((_, _, material)) = self.shared_mut(first) else {
+                    return;
+                };
+                let mut color = material.color;
+                if ui.color_edit_button_rgb(color.as_mut()).changed() {
+                    for &id in &ids {
+                        if let Some((_, _, material)) = self.shared_mut(id) {
+                            material.color = color;
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Duplicate Mirrored:");
+                for (axis, name) in AXIS_NAMES.into_iter().enumerate() {
+                    if ui.button(name).clicked() {
+                        self.selection.mirror = Some(axis);
+                    }
+                }
+            });
+            if ui.button("Delete").clicked() {
+                for &id in &ids {
+                    self.remove(id);
+                }
+                self.selection = Selection::default();
+            }
+        });
+    }
+
+    /// Duplicates every selected object mirrored across `axis` of the space of the group it is
+    /// in, using [`Transform::mirrored`] on its transform and keyframes, and selects the copies.
+    /// Mirroring a copy across the same axis again gives an exact copy of the original.
+    pub fn duplicate_mirrored(&mut self, axis: usize) {
+        fn mirror(
+            transform: &mut Transform,
+            keyframes: &mut [Keyframe],
+            name: &mut String,
+            axis: usize,
+        ) {
+            *transform = transform.mirrored(axis);
+            for keyframe in keyframes {
+                keyframe.transform = keyframe.transform.mirrored(axis);
+            }
+            *name += &format!(" (mirrored {})", AXIS_NAMES[axis]);
+        }
+
+        let mut copies = vec![];
+        for id in self.selection.ids().collect::<Vec<_>>() {
+            copies.push(match id {
+                ObjectID::Hypersphere(id) => {
+                    let Some(mut object) = self.hyperspheres.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    ObjectID::Hypersphere(self.hyperspheres.insert(object))
+                }
+                ObjectID::Hyperplane(id) => {
+                    let Some(mut object) = self.hyperplanes.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    ObjectID::Hyperplane(self.hyperplanes.insert(object))
+                }
+                ObjectID::Tesseract(id) => {
+                    let Some(mut object) = self.tesseracts.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    ObjectID::Tesseract(self.tesseracts.insert(object))
+                }
+                ObjectID::Torus(id) => {
+                    let Some(mut object) = self.tori.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    ObjectID::Torus(self.tori.insert(object))
+                }
+                ObjectID::Spherinder(id) => {
+                    let Some(mut object) = self.spherinders.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    ObjectID::Spherinder(self.spherinders.insert(object))
+                }
+                ObjectID::CsgPair(id) => {
+                    let Some(mut object) = self.csg_pairs.get(id).cloned() else {
+                        continue;
+                    };
+                    mirror(
+                        &mut object.transform,
+                        &mut object.keyframes,
+                        &mut object.name,
+                        axis,
+                    );
+                    // the hyperspheres are placed relative to the pair, so they are mirrored too
+                    // to keep the pair's shape mirrored
+                    object.a.transform = object.a.transform.mirrored(axis);
+                    object.b.transform = object.b.transform.mirrored(axis);
+                    ObjectID::CsgPair(self.csg_pairs.insert(object))
+                }
+            });
+        }
+        let mut copies = copies.into_iter();
+        if let Some(id) = copies.next() {
+            self.selection = Selection::reveal(id);
+            self.selection.others = copies.collect();
+        }
+    }
+
+    /// Returns the visibility, group and material of an object, which every kind of object has,
+    /// or `None` if the object doesn't exist.
+    fn shared_mut(
+        &mut self,
+        id: ObjectID,
+    ) -> Option<(&mut bool, &mut Option<GroupID>, &mut Material)> {
+        match id {
+            ObjectID::Hypersphere(id) => self
+                .hyperspheres
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+            ObjectID::Hyperplane(id) => self
+                .hyperplanes
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+            ObjectID::Tesseract(id) => self
+                .tesseracts
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+            ObjectID::Torus(id) => self
+                .tori
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+            ObjectID::Spherinder(id) => self
+                .spherinders
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+            ObjectID::CsgPair(id) => self
+                .csg_pairs
+                .get_mut(id)
+                .map(|object| (&mut object.visible, &mut object.group, &mut object.material)),
+        }
+    }
+
+    fn remove(&mut self, id: ObjectID) {
+        match id {
+            ObjectID::Hypersphere(id) => {
+                self.hyperspheres.remove(id);
+            }
+            ObjectID::Hyperplane(id) => {
+                self.hyperplanes.remove(id);
+            }
+            ObjectID::Tesseract(id) => {
+                self.tesseracts.remove(id);
+            }
+            ObjectID::Torus(id) => {
+                self.tori.remove(id);
+            }
+            ObjectID::Spherinder(id) => {
+                self.spherinders.remove(id);
+            }
+            ObjectID::CsgPair(id) => {
+                self.csg_pairs.remove(id);
+            }
+        }
+    }
+
+    /// Copies a group along with every object and group nested in it, and returns the id of the
+    /// copy.
+    pub fn duplicate_group(&mut self, id: GroupID) -> GroupID {
+        let new_id = self.copy_group(id, self.groups[id].parent);
+        self.groups[new_id].name += " (copy)";
+        new_id
+    }
+
+    fn copy_group(&mut self, id: GroupID, parent: Option<GroupID>) -> GroupID {
+        let mut new_group = self.groups[id].clone();
+        new_group.parent = parent;
+        let new_id = self.groups.insert(new_group);
+
+        let new_hyperspheres = self
+            .hyperspheres
+            .values()
+            .filter(|hypersphere| hypersphere.group == Some(id))
+            .map(|hypersphere| {
+                let mut new_hypersphere = hypersphere.clone();
+                new_hypersphere.group = Some(new_id);
+                new_hypersphere
+            })
+            .collect::<Vec<_>>();
+        for hypersphere in new_hyperspheres {
+            self.hyperspheres.insert(hypersphere);
+        }
+
+        let new_hyperplanes = self
+            .hyperplanes
+            .values()
+            .filter(|hyperplane| hyperplane.group == Some(id))
+            .map(|hyperplane| {
+                let mut new_hyperplane = hyperplane.clone();
+                new_hyperplane.group = Some(new_id);
+                new_hyperplane
+            })
+            .collect::<Vec<_>>();
+        for hyperplane in new_hyperplanes {
+            self.hyperplanes.insert(hyperplane);
+        }
+
+        let new_tesseracts = self
+            .tesseracts
+            .values()
+            .filter(|tesseract| tesseract.group == Some(id))
+            .map(|tesseract| {
+                let mut new_tesseract = tesseract.clone();
+                new_tesseract.group = Some(new_id);
+                new_tesseract
+            })
+            .collect::<Vec<_>>();
+        for tesseract in new_tesseracts {
+            self.tesseracts.insert(tesseract);
+        }
+
+        let new_tori = self
+            .tori
+            .values()
+            .filter(|torus| torus.group == Some(id))
+            .map(|torus| {
+                let mut new_torus = torus.clone();
+                new_torus.group = Some(new_id);
+                new_torus
+            })
+            .collect::<Vec<_>>();
+        for torus in new_tori {
+            self.tori.insert(torus);
+        }
+
+        let new_spherinders = self
+            .spherinders
+            .values()
+            .filter(|spherinder| spherinder.group == Some(id))
+            .map(|spherinder| {
+                let mut new_spherinder = spherinder.clone();
+                new_spherinder.group = Some(new_id);
+                new_spherinder
+            })
+            .collect::<Vec<_>>();
+        for spherinder in new_spherinders {
+            self.spherinders.insert(spherinder);
+        }
+
+        let new_csg_pairs = self
+            .csg_pairs
+            .values()
+            .filter(|csg_pair| csg_pair.group == Some(id))
+            .map(|csg_pair| {
+                let mut new_csg_pair = csg_pair.clone();
+                new_csg_pair.group = Some(new_id);
+                new_csg_pair
+            })
+            .collect::<Vec<_>>();
+        for csg_pair in new_csg_pairs {
+            self.csg_pairs.insert(csg_pair);
+        }
+
+        let children = self
+            .groups
+            .iter()
+            .filter(|(_, group)| group.parent == Some(id))
+            .map(|(child, _)| child)
+            .collect::<Vec<_>>();
+        for child in children {
+            self.copy_group(child, Some(new_id));
+        }
+
+        new_id
+    }
+
+    /// Adds an object copied to the clipboard with its "Copy" button, from this scene or another.
+    /// It is put back into groups with the same names as the ones it was copied from, any that are
+    /// missing are created.
+    pub fn paste(&mut self, text: &str) -> Result<ObjectID, String> {
+        let copied = serde_json::from_str::<CopiedObject>(text)
+            .map_err(|e| format!("The clipboard does not contain a copied object: {e}"))?;
+
+        let mut group = None;
+        for copied_group in copied.groups.into_iter().rev() {
+            let existing = self
+                .groups
+                .iter()
+                .find(|(_, existing)| {
+                    existing.parent == group && existing.name == copied_group.name
+                })
+                .map(|(id, _)| id);
+            group = Some(existing.unwrap_or_else(|| {
+                self.groups.insert(Group {
+                    parent: group,
+                    ..copied_group
+                })
+            }));
+        }
+
+        let id = match copied.object {
+            CopiedKind::Hypersphere(hypersphere) => {
+                ObjectID::Hypersphere(self.hyperspheres.insert(Hypersphere {
+                    group,
+                    ..hypersphere
+                }))
+            }
+            CopiedKind::Hyperplane(hyperplane) => {
+                ObjectID::Hyperplane(self.hyperplanes.insert(Hyperplane {
+                    group,
+                    ..hyperplane
+                }))
+            }
+            CopiedKind::Tesseract(tesseract) => {
+                ObjectID::Tesseract(self.tesseracts.insert(Tesseract { group, ..tesseract }))
+            }
+            CopiedKind::Torus(torus) => ObjectID::Torus(self.tori.insert(Torus { group, ..torus })),
+            CopiedKind::Spherinder(spherinder) => {
+                ObjectID::Spherinder(self.spherinders.insert(Spherinder {
+                    group,
+                    ..spherinder
+                }))
+            }
+            CopiedKind::CsgPair(csg_pair) => {
+                ObjectID::CsgPair(self.csg_pairs.insert(CsgPair { group, ..csg_pair }))
+            }
+        };
+        self.selection = Selection::reveal(id);
+        Ok(id)
+    }
+
+    /// Adds a group of hyperspheres and bounded hyperplanes with random positions, sizes and
+    /// colors, spread through a box that grows with the number of objects. The same seed always
+    /// generates the same objects.
+    pub fn generate_random(&mut self, hyperspheres: usize, hyperplanes: usize, seed: u64) {
+        let mut random = Random(seed);
+        let group = Some(self.groups.insert(Group {
+            name: format!("Random {seed}"),
+            ..Default::default()
+        }));
+        // keeps roughly the same number of objects per unit of volume
+        let half_size = 2.0 * ((hyperspheres + hyperplanes) as f32).powf(0.25).max(1.0);
+        let position = |random: &mut Random| cgmath::Vector4 {
+            x: random.range(-half_size..half_size),
+            y: random.range(0.0..half_size * 2.0),
+            z: random.range(-half_size..half_size),
+            w: random.range(-half_size..half_size),
+        };
+        let material = |random: &mut Random| Material {
+            color: cgmath::Vector3::new(
+                random.range(0.0..1.0),
+                random.range(0.0..1.0),
+                random.range(0.0..1.0),
+            ),
+            ..Default::default()
+        };
+
+        for index in 0..hyperspheres {
+            self.hyperspheres.insert(Hypersphere {
+                name: format!("Random Hypersphere {index}"),
+                group,
+                transform: Transform {
+                    position: position(&mut random),
+                    ..Default::default()
+                },
+                radius: random.range(0.2..0.8),
+                material: material(&mut random),
+                ..Default::default()
+            });
+        }
+        for index in 0..hyperplanes {
+            let position = position(&mut random);
+            let [
+                xy_rotation,
+                xz_rotation,
+                xw_rotation,
+                yz_rotation,
+                yw_rotation,
+                zw_rotation,
+            ] = std::array::from_fn(|_| random.range(0.0..TAU));
+            self.hyperplanes.insert(Hyperplane {
+                name: format!("Random Hyperplane {index}"),
+                group,
+                transform: Transform {
+                    position,
+                    xy_rotation,
+                    xz_rotation,
+                    xw_rotation,
+                    yz_rotation,
+                    yw_rotation,
+                    zw_rotation,
+                },
+                width: random.range(0.5..3.0),
+                height: random.range(0.5..3.0),
+                depth: random.range(0.5..3.0),
+                material: material(&mut random),
+                ..Default::default()
+            });
+        }
+        self.scroll_to_group = group;
+    }
+
+    /// Deletes a group along with every object and group nested in it.
+    pub fn delete_group(&mut self, id: GroupID) {
+        let to_delete = self
+            .groups
+            .keys()
+            .filter(|&group| {
+                Self::ancestors(&self.groups, Some(group)).any(|ancestor| ancestor == id)
+            })
+            .collect::<Vec<_>>();
+        for id in to_delete {
+            self.groups.remove(id);
+            self.hyperspheres
+                .retain(|_, hypersphere| hypersphere.group != Some(id));
+            self.hyperplanes
+                .retain(|_, hyperplane| hyperplane.group != Some(id));
+            self.tesseracts
+                .retain(|_, tesseract| tesseract.group != Some(id));
+            self.tori.retain(|_, torus| torus.group != Some(id));
+            self.spherinders
+                .retain(|_, spherinder| spherinder.group != Some(id));
+            self.csg_pairs
+                .retain(|_, csg_pair| csg_pair.group != Some(id));
+        }
+    }
+
+    /// Draws every group and object in a list for each kind. Only the ones whose names contain
+    /// `filter` are listed, ignoring case. New objects are placed at `new_position`.
+    pub fn flat_ui(&mut self, ui: &mut egui::Ui, filter: &str, new_position: cgmath::Vector4<f32>) {
+        let filter = filter.to_lowercase();
+        let mut duplicated = None;
+        let mut duplicated_group = None;
+        egui::CollapsingHeader::new("Groups")
+            .open(self.scroll_to_group.is_some().then_some(true))
+            .show(ui, |ui| {
+                if ui.button("New Group").clicked() {
+                    self.scroll_to_group = Some(self.groups.insert(Group::default()));
+                }
+                let mut to_duplicate = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .groups
+                    .iter()
+                    .filter(|&(id, group)| {
+                        is_listed(&group.name, &filter, self.scroll_to_group == Some(id))
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                for id in ids {
+                    let response = egui::CollapsingHeader::new(group_header(&self.groups[id]))
+                        .id_salt(id)
+                        .open((self.scroll_to_group == Some(id)).then_some(true))
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Name:");
+                                ui.text_edit_singleline(&mut self.groups[id].name);
+                            });
+                            visible_ui(ui, &mut self.groups[id].visible);
+                            Self::parent_ui(ui, &mut self.groups, id);
+                            ui.collapsing("Transform", |ui| {
+                                self.groups[id].transform.ui(ui);
+                            });
+                            if ui.button("Duplicate").clicked() {
+                                to_duplicate.push(id);
+                            }
+                            if ui.button("Delete").clicked() {
+                                to_delete.push(id);
+                            }
+                        });
+                    if self.scroll_to_group == Some(id) {
+                        ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
+                    }
+                }
+                for id in to_duplicate {
+                    duplicated_group = Some(self.duplicate_group(id));
+                }
+                for id in to_delete {
+                    self.groups.remove(id);
+                }
+            });
+        egui::CollapsingHeader::new("Hyperspheres")
+            .open(
+                (self.selection.reveal
+                    && matches!(self.selection.id, Some(ObjectID::Hypersphere(_))))
+                .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New Hypersphere").clicked() {
+                    let id = self.hyperspheres.insert(Hypersphere {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::Hypersphere(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .hyperspheres
+                    .iter()
+                    .filter(|&(id, hypersphere)| {
+                        is_listed(
+                            &hypersphere.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::Hypersphere(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::hyperspheres_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.hyperspheres,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.hyperspheres.remove(id);
+                }
+                for hypersphere in to_insert {
+                    let id = self.hyperspheres.insert(hypersphere);
+                    duplicated = Some(ObjectID::Hypersphere(id));
+                }
+            });
+        egui::CollapsingHeader::new("Hyperplanes")
+            .open(
+                (self.selection.reveal
+                    && matches!(self.selection.id, Some(ObjectID::Hyperplane(_))))
+                .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New Hyperplane").clicked() {
+                    let id = self.hyperplanes.insert(Hyperplane {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::Hyperplane(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .hyperplanes
+                    .iter()
+                    .filter(|&(id, hyperplane)| {
+                        is_listed(
+                            &hyperplane.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::Hyperplane(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::hyperplanes_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.hyperplanes,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.hyperplanes.remove(id);
+                }
+                for hyperplane in to_insert {
+                    let id = self.hyperplanes.insert(hyperplane);
+                    duplicated = Some(ObjectID::Hyperplane(id));
+                }
+            });
+        egui::CollapsingHeader::new("Tesseracts")
+            .open(
+                (self.selection.reveal
+                    && matches!(self.selection.id, Some(ObjectID::Tesseract(_))))
+                .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New Tesseract").clicked() {
+                    let id = self.tesseracts.insert(Tesseract {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::Tesseract(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .tesseracts
+                    .iter()
+                    .filter(|&(id, tesseract)| {
+                        is_listed(
+                            &tesseract.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::Tesseract(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::tesseracts_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.tesseracts,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.tesseracts.remove(id);
+                }
+                for tesseract in to_insert {
+                    let id = self.tesseracts.insert(tesseract);
+                    duplicated = Some(ObjectID::Tesseract(id));
+                }
+            });
+        egui::CollapsingHeader::new("Tori")
+            .open(
+                (self.selection.reveal && matches!(self.selection.id, Some(ObjectID::Torus(_))))
+                    .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New Torus").clicked() {
+                    let id = self.tori.insert(Torus {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::Torus(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .tori
+                    .iter()
+                    .filter(|&(id, torus)| {
+                        is_listed(
+                            &torus.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::Torus(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::tori_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.tori,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.tori.remove(id);
+                }
+                for torus in to_insert {
+                    let id = self.tori.insert(torus);
+                    duplicated = Some(ObjectID::Torus(id));
+                }
+            });
+        egui::CollapsingHeader::new("Spherinders")
+            .open(
+                (self.selection.reveal
+                    && matches!(self.selection.id, Some(ObjectID::Spherinder(_))))
+                .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New Spherinder").clicked() {
+                    let id = self.spherinders.insert(Spherinder {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::Spherinder(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .spherinders
+                    .iter()
+                    .filter(|&(id, spherinder)| {
+                        is_listed(
+                            &spherinder.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::Spherinder(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::spherinders_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.spherinders,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.spherinders.remove(id);
+                }
+                for spherinder in to_insert {
+                    let id = self.spherinders.insert(spherinder);
+                    duplicated = Some(ObjectID::Spherinder(id));
+                }
+            });
+        egui::CollapsingHeader::new("CSG")
+            .open(
+                (self.selection.reveal && matches!(self.selection.id, Some(ObjectID::CsgPair(_))))
+                    .then_some(true),
+            )
+            .show(ui, |ui| {
+                if ui.button("New CSG Pair").clicked() {
+                    let id = self.csg_pairs.insert(CsgPair {
+                        transform: Transform::at(new_position),
+                        ..Default::default()
+                    });
+                    self.selection = Selection::reveal(ObjectID::CsgPair(id));
+                }
+                let mut to_insert = vec![];
+                let mut to_delete = vec![];
+                let ids = self
+                    .csg_pairs
+                    .iter()
+                    .filter(|&(id, csg_pair)| {
+                        is_listed(
+                            &csg_pair.name,
+                            &filter,
+                            self.selection.id == Some(ObjectID::CsgPair(id)),
+                        )
+                    })
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                Self::csg_pairs_ui(
+                    ui,
+                    &self.groups,
+                    &mut self.csg_pairs,
+                    ids.into_iter(),
+                    &mut self.selection,
+                    &mut to_insert,
+                    &mut to_delete,
+                );
+                for id in to_delete {
+                    self.csg_pairs.remove(id);
+                }
+                for csg_pair in to_insert {
+                    let id = self.csg_pairs.insert(csg_pair);
+                    duplicated = Some(ObjectID::CsgPair(id));
+                }
+            });
+        self.selection.reveal = false;
+        self.scroll_to_group = duplicated_group;
+        if let Some(id) = duplicated {
+            self.selection = Selection::reveal(id);
+        }
+        if let Some(axis) = self.selection.mirror.take() {
+            self.duplicate_mirrored(axis);
+        }
+        self.cleanup_invalid_ids();
+    }
+
+    /// Draws the groups as a tree with their objects inside of them. Only the objects whose names
+    /// contain `filter` are listed, ignoring case, along with the groups that they are in and the
+    /// groups whose own names match. New objects are placed at `new_position`.
+    pub fn grouped_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        filter: &str,
+        new_position: cgmath::Vector4<f32>,
+    ) {
+        let filter = filter.to_lowercase();
+        if ui.button("New Group").clicked() {
+            self.scroll_to_group = Some(self.groups.insert(Group::default()));
+        }
+
+        if ui.button("New Hypersphere").clicked() {
+            let id = self.hyperspheres.insert(Hypersphere {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::Hypersphere(id));
+        }
+
+        if ui.button("New Hyperplane").clicked() {
+            let id = self.hyperplanes.insert(Hyperplane {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::Hyperplane(id));
+        }
+
+        if ui.button("New Tesseract").clicked() {
+            let id = self.tesseracts.insert(Tesseract {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::Tesseract(id));
+        }
+
+        if ui.button("New Torus").clicked() {
+            let id = self.tori.insert(Torus {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::Torus(id));
+        }
+
+        if ui.button("New Spherinder").clicked() {
+            let id = self.spherinders.insert(Spherinder {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::Spherinder(id));
+        }
+        if ui.button("New CSG Pair").clicked() {
+            let id = self.csg_pairs.insert(CsgPair {
+                transform: Transform::at(new_position),
+                ..Default::default()
+            });
+            self.selection = Selection::reveal(ObjectID::CsgPair(id));
+        }
+
+        let mut grouped_objects = BTreeMap::<Option<GroupID>, GroupedObjects>::new();
+        grouped_objects.entry(None).or_default();
+        // the groups that are drawn, which are those on the way down to every listed object
+        let mut listed_groups = BTreeSet::new();
+        for (id, hypersphere) in &self.hyperspheres {
+            if !is_listed(
+                &hypersphere.name,
+                &filter,
+                self.selection.id == Some(ObjectID::Hypersphere(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, hypersphere.group));
+            grouped_objects
+                .entry(hypersphere.group)
+                .or_default()
+                .hyperspheres
+                .push(id);
+        }
         for (id, hyperplane) in &self.hyperplanes {
+            if !is_listed(
+                &hyperplane.name,
+                &filter,
+                self.selection.id == Some(ObjectID::Hyperplane(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, hyperplane.group));
             grouped_objects
                 .entry(hyperplane.group)
                 .or_default()
                 .hyperplanes
                 .push(id);
         }
+        for (id, tesseract) in &self.tesseracts {
+            if !is_listed(
+                &tesseract.name,
+                &filter,
+                self.selection.id == Some(ObjectID::Tesseract(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, tesseract.group));
+            grouped_objects
+                .entry(tesseract.group)
+                .or_default()
+                .tesseracts
+                .push(id);
+        }
+        for (id, torus) in &self.tori {
+            if !is_listed(
+                &torus.name,
+                &filter,
+                self.selection.id == Some(ObjectID::Torus(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, torus.group));
+            grouped_objects
+                .entry(torus.group)
+                .or_default()
+                .tori
+                .push(id);
+        }
+        for (id, spherinder) in &self.spherinders {
+            if !is_listed(
+                &spherinder.name,
+                &filter,
+                self.selection.id == Some(ObjectID::Spherinder(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, spherinder.group));
+            grouped_objects
+                .entry(spherinder.group)
+                .or_default()
+                .spherinders
+                .push(id);
+        }
+        for (id, csg_pair) in &self.csg_pairs {
+            if !is_listed(
+                &csg_pair.name,
+                &filter,
+                self.selection.id == Some(ObjectID::CsgPair(id)),
+            ) {
+                continue;
+            }
+            listed_groups.extend(Self::ancestors(&self.groups, csg_pair.group));
+            grouped_objects
+                .entry(csg_pair.group)
+                .or_default()
+                .csg_pairs
+                .push(id);
+        }
+        for (id, group) in &self.groups {
+            grouped_objects.entry(Some(id)).or_default();
+            if is_listed(&group.name, &filter, self.scroll_to_group == Some(id)) {
+                listed_groups.extend(Self::ancestors(&self.groups, Some(id)));
+            }
+        }
+        for (id, group) in &self.groups {
+            if listed_groups.contains(&id) {
+                grouped_objects
+                    .entry(group.parent)
+                    .or_default()
+                    .groups
+                    .push(id);
+            }
+        }
 
-        let mut groups_to_clone = vec![];
+        let revealed_group = self
+            .selection
+            .id
+            .filter(|_| self.selection.reveal)
+            .and_then(|id| self.object_group(id));
+        // every group on the way down to the revealed object or group has to be opened
+        let open_groups = Self::ancestors(&self.groups, revealed_group.flatten())
+            .chain(Self::ancestors(&self.groups, self.scroll_to_group))
+            .collect::<Vec<_>>();
 
-        for (&id, grouped_objects) in &grouped_objects {
-            let response = egui::CollapsingHeader::new(if let Some(group_id) = id {
-                if let Some(group) = self.groups.get(group_id) {
-                    &group.name
-                } else {
-                    "Invalid"
-                }
-            } else {
-                "None"
-            })
+        let mut edits = GroupedEdits::default();
+        self.group_tree_ui(
+            ui,
+            None,
+            &grouped_objects,
+            revealed_group,
+            &open_groups,
+            &mut edits,
+        );
+        for &id in &grouped_objects[&None].groups {
+            self.group_tree_ui(
+                ui,
+                Some(id),
+                &grouped_objects,
+                revealed_group,
+                &open_groups,
+                &mut edits,
+            );
+        }
+
+        let mut duplicated_group = None;
+        for id in edits.groups_to_duplicate {
+            duplicated_group = Some(self.duplicate_group(id));
+        }
+
+        for id in edits.groups_to_delete {
+            self.delete_group(id);
+        }
+        for id in edits.hyperspheres_to_delete {
+            self.hyperspheres.remove(id);
+        }
+        for id in edits.hyperplanes_to_delete {
+            self.hyperplanes.remove(id);
+        }
+        for id in edits.tesseracts_to_delete {
+            self.tesseracts.remove(id);
+        }
+        for id in edits.tori_to_delete {
+            self.tori.remove(id);
+        }
+        for id in edits.spherinders_to_delete {
+            self.spherinders.remove(id);
+        }
+        for id in edits.csg_pairs_to_delete {
+            self.csg_pairs.remove(id);
+        }
+
+        let mut duplicated = None;
+        for hypersphere in edits.hyperspheres_to_insert {
+            let id = self.hyperspheres.insert(hypersphere);
+            duplicated = Some(ObjectID::Hypersphere(id));
+        }
+        for hyperplane in edits.hyperplanes_to_insert {
+            let id = self.hyperplanes.insert(hyperplane);
+            duplicated = Some(ObjectID::Hyperplane(id));
+        }
+        for tesseract in edits.tesseracts_to_insert {
+            let id = self.tesseracts.insert(tesseract);
+            duplicated = Some(ObjectID::Tesseract(id));
+        }
+        for torus in edits.tori_to_insert {
+            let id = self.tori.insert(torus);
+            duplicated = Some(ObjectID::Torus(id));
+        }
+        for spherinder in edits.spherinders_to_insert {
+            let id = self.spherinders.insert(spherinder);
+            duplicated = Some(ObjectID::Spherinder(id));
+        }
+        for csg_pair in edits.csg_pairs_to_insert {
+            let id = self.csg_pairs.insert(csg_pair);
+            duplicated = Some(ObjectID::CsgPair(id));
+        }
+
+        self.selection.reveal = false;
+        self.scroll_to_group = duplicated_group;
+        if let Some(id) = duplicated {
+            self.selection = Selection::reveal(id);
+        }
+        if let Some(axis) = self.selection.mirror.take() {
+            self.duplicate_mirrored(axis);
+        }
+        self.cleanup_invalid_ids();
+    }
+
+    /// Draws a group with its objects, followed by the groups nested in it. `None` draws the
+    /// objects that aren't in any group.
+    fn group_tree_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: Option<GroupID>,
+        grouped_objects: &BTreeMap<Option<GroupID>, GroupedObjects>,
+        revealed_group: Option<Option<GroupID>>,
+        open_groups: &[GroupID],
+        edits: &mut GroupedEdits,
+    ) {
+        let reveal = revealed_group == Some(id);
+        let open = match id {
+            Some(id) => open_groups.contains(&id),
+            None => reveal,
+        };
+        let objects = &grouped_objects[&id];
+        let mut header = if let Some(group_id) = id {
+            group_header(&self.groups[group_id])
+        } else {
+            egui::RichText::new("None")
+        };
+        if self.isolated == Some(id) {
+            header = header.color(ui.visuals().warn_fg_color);
+        }
+        let response = egui::CollapsingHeader::new(header)
             .id_salt(id)
+            .open(open.then_some(true))
             .show(ui, |ui| {
-                if let Some(group_id) = id
-                    && let Some(group) = self.groups.get_mut(group_id)
+                if let Some(group_id) = id {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut self.groups[group_id].name);
+                    });
+                    visible_ui(ui, &mut self.groups[group_id].visible);
+                    Self::parent_ui(ui, &mut self.groups, group_id);
+                    ui.collapsing("Transform", |ui| {
+                        self.groups[group_id].transform.ui(ui);
+                    });
+                    if ui.button("Duplicate").clicked() {
+                        edits.groups_to_duplicate.push(group_id);
+                    }
+                    if ui.button("Delete").clicked() {
+                        edits.groups_to_delete.push(group_id);
+                    }
+                }
+                let mut isolated = self.isolated == Some(id);
+                if ui
+                    .checkbox(&mut isolated, "Isolate")
+                    .on_hover_text(if id.is_some() {
+                        "Renders only the objects in this group and the groups nested in it"
+                    } else {
+                        "Renders only the objects that aren't in a group"
+                    })
+                    .changed()
                 {
+                    self.isolated = isolated.then_some(id);
+                }
+                egui::CollapsingHeader::new("Hyperspheres")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::Hypersphere(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::hyperspheres_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.hyperspheres,
+                            objects.hyperspheres.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.hyperspheres_to_insert,
+                            &mut edits.hyperspheres_to_delete,
+                        );
+                    });
+                egui::CollapsingHeader::new("Hyperplanes")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::Hyperplane(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::hyperplanes_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.hyperplanes,
+                            objects.hyperplanes.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.hyperplanes_to_insert,
+                            &mut edits.hyperplanes_to_delete,
+                        );
+                    });
+                egui::CollapsingHeader::new("Tesseracts")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::Tesseract(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::tesseracts_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.tesseracts,
+                            objects.tesseracts.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.tesseracts_to_insert,
+                            &mut edits.tesseracts_to_delete,
+                        );
+                    });
+                egui::CollapsingHeader::new("Tori")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::Torus(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::tori_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.tori,
+                            objects.tori.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.tori_to_insert,
+                            &mut edits.tori_to_delete,
+                        );
+                    });
+                egui::CollapsingHeader::new("Spherinders")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::Spherinder(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::spherinders_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.spherinders,
+                            objects.spherinders.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.spherinders_to_insert,
+                            &mut edits.spherinders_to_delete,
+                        );
+                    });
+                egui::CollapsingHeader::new("CSG")
+                    .open(
+                        (reveal && matches!(self.selection.id, Some(ObjectID::CsgPair(_))))
+                            .then_some(true),
+                    )
+                    .show(ui, |ui| {
+                        Self::csg_pairs_ui(
+                            ui,
+                            &self.groups,
+                            &mut self.csg_pairs,
+                            objects.csg_pairs.iter().copied(),
+                            &mut self.selection,
+                            &mut edits.csg_pairs_to_insert,
+                            &mut edits.csg_pairs_to_delete,
+                        );
+                    });
+                if id.is_some() {
+                    for &child in &objects.groups {
+                        self.group_tree_ui(
+                            ui,
+                            Some(child),
+                            grouped_objects,
+                            revealed_group,
+                            open_groups,
+                            edits,
+                        );
+                    }
+                }
+            });
+
+        if id.is_some() && self.scroll_to_group == id {
+            ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
+        }
+    }
+
+    /// How many groups deep the most deeply nested group is, 1 for a group with no parent and 0
+    /// when there are no groups.
+    pub fn max_group_depth(&self) -> usize {
+        self.groups
+            .keys()
+            .map(|id| Self::ancestors(&self.groups, Some(id)).count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn world_bounds(&self) -> Option<(cgmath::Vector4<f32>, cgmath::Vector4<f32>)> {
+        let mut bounds = None::<(cgmath::Vector4<f32>, cgmath::Vector4<f32>)>;
+        let mut extend = |min: cgmath::Vector4<f32>, max: cgmath::Vector4<f32>| {
+            bounds = Some(match bounds {
+                Some((old_min, old_max)) => {
+                    (old_min.zip(min, f32::min), old_max.zip(max, f32::max))
+                }
+                None => (min, max),
+            });
+        };
+
+        for hypersphere in self.hyperspheres.values() {
+            let position =
+                Self::global_transform(&self.groups, &hypersphere.transform, hypersphere.group)
+                    .position();
+            let scale = hypersphere.scale.map(f32::abs);
+            let radius = cgmath::Vector4::from_value(
+                hypersphere.radius.abs() * scale.x.max(scale.y).max(scale.z).max(scale.w),
+            );
+            extend(position - radius, position + radius);
+        }
+        for hyperplane in self.hyperplanes.values() {
+            let transform =
+                Self::global_transform(&self.groups, &hyperplane.transform, hyperplane.group);
+            // an infinite hyperplane would make the bounds useless, so only its position counts
+            if hyperplane.infinite {
+                let position = transform.position();
+                extend(position, position);
+                continue;
+            }
+            for corner in 0..8 {
+                let side = |bit: u32| if corner & bit != 0 { 0.5 } else { -0.5 };
+                let point = transform.transform_point(cgmath::Vector4 {
+                    x: hyperplane.height * hyperplane.scale.x * side(1),
+                    y: 0.0,
+                    z: hyperplane.width * hyperplane.scale.z * side(2),
+                    w: hyperplane.depth * hyperplane.scale.w * side(4),
+                });
+                extend(point, point);
+            }
+        }
+        for tesseract in self.tesseracts.values() {
+            let transform =
+                Self::global_transform(&self.groups, &tesseract.transform, tesseract.group);
+            for corner in 0..16 {
+                let side = |bit: u32| if corner & bit != 0 { 0.5 } else { -0.5 };
+                let point = transform.transform_point(cgmath::Vector4 {
+                    x: tesseract.size.x * tesseract.scale.x * side(1),
+                    y: tesseract.size.y * tesseract.scale.y * side(2),
+                    z: tesseract.size.z * tesseract.scale.z * side(4),
+                    w: tesseract.size.w * tesseract.scale.w * side(8),
+                });
+                extend(point, point);
+            }
+        }
+        for torus in self.tori.values() {
+            let position =
+                Self::global_transform(&self.groups, &torus.transform, torus.group).position();
+            let radius = cgmath::Vector4::from_value(torus.bounding_radius());
+            extend(position - radius, position + radius);
+        }
+        for spherinder in self.spherinders.values() {
+            let position =
+                Self::global_transform(&self.groups, &spherinder.transform, spherinder.group)
+                    .position();
+            let radius = cgmath::Vector4::from_value(spherinder.bounding_radius());
+            extend(position - radius, position + radius);
+        }
+        for csg_pair in self.csg_pairs.values() {
+            let position =
+                Self::global_transform(&self.groups, &csg_pair.transform, csg_pair.group)
+                    .position();
+            let radius = cgmath::Vector4::from_value(csg_pair.bounding_radius());
+            extend(position - radius, position + radius);
+        }
+
+        bounds
+    }
+
+    pub fn centroid(&self) -> Option<cgmath::Vector4<f32>> {
+        let positions = self
+            .hyperspheres
+            .values()
+            .map(|hypersphere| {
+                Self::global_transform(&self.groups, &hypersphere.transform, hypersphere.group)
+                    .position()
+            })
+            .chain(self.hyperplanes.values().map(|hyperplane| {
+                Self::global_transform(&self.groups, &hyperplane.transform, hyperplane.group)
+                    .position()
+            }))
+            .chain(self.tesseracts.values().map(|tesseract| {
+                Self::global_transform(&self.groups, &tesseract.transform, tesseract.group)
+                    .position()
+            }))
+            .chain(self.tori.values().map(|torus| {
+                Self::global_transform(&self.groups, &torus.transform, torus.group).position()
+            }))
+            .chain(self.spherinders.values().map(|spherinder| {
+                Self::global_transform(&self.groups, &spherinder.transform, spherinder.group)
+                    .position()
+            }))
+            .chain(self.csg_pairs.values().map(|csg_pair| {
+                Self::global_transform(&self.groups, &csg_pair.transform, csg_pair.group).position()
+            }))
+            .collect::<Vec<_>>();
+        (!positions.is_empty())
+            .then(|| positions.iter().sum::<cgmath::Vector4<f32>>() / positions.len() as f32)
+    }
+
+    pub fn gpu_hyperspheres(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::Hypersphere> {
+        self.hyperspheres
+            .values()
+            .filter(|hypersphere| self.is_visible(hypersphere.visible, hypersphere.group))
+            .map(
+                |&Hypersphere {
+                     name: _,
+                     visible: _,
+                     views,
+                     group,
+                     ref transform,
+                     scale,
+                     radius,
+                     ref material,
+                     ref keyframes,
+                 }| rendering::objects::Hypersphere {
+                    transform: Self::group_transform(
+                        &self.groups,
+                        Self::animated_transform(transform, keyframes, time),
+                        group,
+                    ),
+                    scale,
+                    material: material.gpu(),
+                    radius,
+                    views: views.0,
+                    _padding: [0.0; 2],
+                },
+            )
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn gpu_hyperplanes(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::Hyperplane> {
+        self.hyperplanes
+            .values()
+            .filter(|hyperplane| self.is_visible(hyperplane.visible, hyperplane.group))
+            .map(
+                |&Hyperplane {
+                     name: _,
+                     visible: _,
+                     views,
+                     group,
+                     ref transform,
+                     scale,
+                     width,
+                     height,
+                     depth,
+                     infinite,
+                     double_sided,
+                     ref material,
+                     ref keyframes,
+                 }| rendering::objects::Hyperplane {
+                    transform: Self::group_transform(
+                        &self.groups,
+                        Self::animated_transform(transform, keyframes, time),
+                        group,
+                    ),
+                    material: material.gpu(),
+                    // a flat hyperplane is only stretched along its extents, so the scale is folded
+                    // into them
+                    width: width * scale.z,
+                    height: height * scale.x,
+                    depth: depth * scale.w,
+                    infinite: infinite.into(),
+                    cull_back: (!double_sided).into(),
+                    views: views.0,
+                    _padding: [0.0; 2],
+                },
+            )
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn gpu_tesseracts(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::Tesseract> {
+        self.tesseracts
+            .values()
+            .filter(|tesseract| self.is_visible(tesseract.visible, tesseract.group))
+            .map(
+                |&Tesseract {
+                     name: _,
+                     visible: _,
+                     views,
+                     group,
+                     ref transform,
+                     scale,
+                     size,
+                     ref material,
+                     ref keyframes,
+                 }| rendering::objects::Tesseract {
+                    transform: Self::group_transform(
+                        &self.groups,
+                        Self::animated_transform(transform, keyframes, time),
+                        group,
+                    ),
+                    size: size.mul_element_wise(scale),
+                    material: material.gpu(),
+                    views: views.0,
+                    _padding: [0.0; 3],
+                },
+            )
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn gpu_tori(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::Torus> {
+        self.tori
+            .values()
+            .filter(|torus| self.is_visible(torus.visible, torus.group))
+            .map(
+                |&Torus {
+                     name: _,
+                     visible: _,
+                     views,
+                     group,
+                     ref transform,
+                     major_radius,
+                     minor_radius,
+                     ref material,
+                     ref keyframes,
+                 }| rendering::objects::Torus {
+                    transform: Self::group_transform(
+                        &self.groups,
+                        Self::animated_transform(transform, keyframes, time),
+                        group,
+                    ),
+                    material: material.gpu(),
+                    major_radius,
+                    minor_radius,
+                    views: views.0,
+                    _padding: 0.0,
+                },
+            )
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn gpu_spherinders(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::Spherinder> {
+        self.spherinders
+            .values()
+            .filter(|spherinder| self.is_visible(spherinder.visible, spherinder.group))
+            .map(
+                |&Spherinder {
+                     name: _,
+                     visible: _,
+                     views,
+                     group,
+                     ref transform,
+                     radius,
+                     length,
+                     ref material,
+                     ref keyframes,
+                 }| rendering::objects::Spherinder {
+                    transform: Self::group_transform(
+                        &self.groups,
+                        Self::animated_transform(transform, keyframes, time),
+                        group,
+                    ),
+                    material: material.gpu(),
+                    radius,
+                    length,
+                    views: views.0,
+                    _padding: 0.0,
+                },
+            )
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn gpu_csg_pairs(
+        &self,
+        time: Option<f32>,
+    ) -> impl ExactSizeIterator<Item = rendering::objects::CsgPair> {
+        self.csg_pairs
+            .values()
+            .filter(|csg_pair| self.is_visible(csg_pair.visible, csg_pair.group))
+            .map(|csg_pair| {
+                let transform = Self::group_transform(
+                    &self.groups,
+                    Self::animated_transform(&csg_pair.transform, &csg_pair.keyframes, time),
+                    csg_pair.group,
+                );
+                rendering::objects::CsgPair {
+                    a: csg_pair.a.gpu(transform),
+                    b: csg_pair.b.gpu(transform),
+                    material: csg_pair.material.gpu(),
+                    op: csg_pair.op.into(),
+                    views: csg_pair.views.0,
+                    _padding: [0.0; 2],
+                }
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn hyperspheres_ui(
+        ui: &mut egui::Ui,
+        groups: &SlotMap<GroupID, Group>,
+        hyperspheres: &mut SlotMap<HypersphereID, Hypersphere>,
+        hypersphere_ids: impl Iterator<Item = HypersphereID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<Hypersphere>,
+        to_delete: &mut Vec<HypersphereID>,
+    ) {
+        let hypersphere_ids = hypersphere_ids.collect::<Vec<_>>();
+        let listed = hypersphere_ids
+            .iter()
+            .copied()
+            .map(ObjectID::Hypersphere)
+            .collect::<Vec<_>>();
+        for id in hypersphere_ids {
+            let hypersphere = &mut hyperspheres[id];
+            let selected = selection.id == Some(ObjectID::Hypersphere(id));
+            let mut header = egui::RichText::new(&hypersphere.name)
+                .color(color_to_egui(hypersphere.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !hypersphere.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::Hypersphere(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut hypersphere.name);
+                    });
+                    visible_ui(ui, &mut hypersphere.visible);
+                    views_ui(ui, &mut hypersphere.views);
+                    Self::group_ui(ui, groups, &mut hypersphere.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut hypersphere.transform,
+                        Some(&mut hypersphere.scale),
+                        &mut hypersphere.keyframes,
+                        hypersphere.group,
+                    );
+                    size_ui(ui, "Radius:", &mut hypersphere.radius);
+                    material_ui(ui, &mut hypersphere.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::Hypersphere(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_hypersphere = hypersphere.clone();
+                        new_hypersphere.name += " (copy)";
+                        to_insert.push(new_hypersphere);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, hypersphere.group, CopiedKind::Hypersphere(hypersphere.clone()))
+                            .copy(ui.ctx());
+                    }
+                    if ui.button("Delete").clicked() {
+                        to_delete.push(id);
+                    }
+                });
+            if response.header_response.clicked() {
+                selection.click(
+                    ui.input(|i| i.modifiers),
+                    ObjectID::Hypersphere(id),
+                    &listed,
+                );
+            }
+            object_context_menu(
+                &response.header_response,
+                selection,
+                ObjectID::Hypersphere(id),
+            );
+            if selected && selection.reveal {
+                ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
+            }
+        }
+    }
+
+    fn hyperplanes_ui(
+        ui: &mut egui::Ui,
+        groups: &SlotMap<GroupID, Group>,
+        hyperplanes: &mut SlotMap<HyperplaneID, Hyperplane>,
+        hyperplane_ids: impl Iterator<Item = HyperplaneID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<Hyperplane>,
+        to_delete: &mut Vec<HyperplaneID>,
+    ) {
+        let hyperplane_ids = hyperplane_ids.collect::<Vec<_>>();
+        let listed = hyperplane_ids
+            .iter()
+            .copied()
+            .map(ObjectID::Hyperplane)
+            .collect::<Vec<_>>();
+        for id in hyperplane_ids {
+            let hyperplane = &mut hyperplanes[id];
+            let selected = selection.id == Some(ObjectID::Hyperplane(id));
+            let mut header = egui::RichText::new(&hyperplane.name)
+                .color(color_to_egui(hyperplane.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !hyperplane.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::Hyperplane(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut hyperplane.name);
+                    });
+                    visible_ui(ui, &mut hyperplane.visible);
+                    views_ui(ui, &mut hyperplane.views);
+                    Self::group_ui(ui, groups, &mut hyperplane.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut hyperplane.transform,
+                        Some(&mut hyperplane.scale),
+                        &mut hyperplane.keyframes,
+                        hyperplane.group,
+                    );
+                    ui.checkbox(&mut hyperplane.infinite, "Infinite");
+                    ui.checkbox(&mut hyperplane.double_sided, "Double Sided")
+                        .on_hover_text("When unchecked the hyperplane is only visible from the side its local +y axis points towards");
+                    ui.add_enabled_ui(!hyperplane.infinite, |ui| {
+                        size_ui(ui, "Width:", &mut hyperplane.width);
+                        size_ui(ui, "Height:", &mut hyperplane.height);
+                        size_ui(ui, "Depth:", &mut hyperplane.depth);
+                    });
+                    material_ui(ui, &mut hyperplane.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::Hyperplane(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_hyperplane = hyperplane.clone();
+                        new_hyperplane.name += " (copy)";
+                        to_insert.push(new_hyperplane);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, hyperplane.group, CopiedKind::Hyperplane(hyperplane.clone()))
+                            .copy(ui.ctx());
+                    }
+                    if ui.button("Delete").clicked() {
+                        to_delete.push(id);
+                    }
+                });
+            if response.header_response.clicked() {
+                selection.click(ui.input(|i| i.modifiers), ObjectID::Hyperplane(id), &listed);
+            }
+            object_context_menu(
+                &response.header_response,
+                selection,
+                ObjectID::Hyperplane(id),
+            );
+            if selected && selection.reveal {
+                ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
+            }
+        }
+    }
+
+    fn tesseracts_ui(
+        ui: &mut egui::Ui,
+        groups: &SlotMap<GroupID, Group>,
+        tesseracts: &mut SlotMap<TesseractID, Tesseract>,
+        tesseract_ids: impl Iterator<Item = TesseractID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<Tesseract>,
+        to_delete: &mut Vec<TesseractID>,
+    ) {
+        let tesseract_ids = tesseract_ids.collect::<Vec<_>>();
+        let listed = tesseract_ids
+            .iter()
+            .copied()
+            .map(ObjectID::Tesseract)
+            .collect::<Vec<_>>();
+        for id in tesseract_ids {
+            let tesseract = &mut tesseracts[id];
+            let selected = selection.id == Some(ObjectID::Tesseract(id));
+            let mut header =
+                egui::RichText::new(&tesseract.name).color(color_to_egui(tesseract.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !tesseract.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::Tesseract(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut tesseract.name);
+                    });
+                    visible_ui(ui, &mut tesseract.visible);
+                    views_ui(ui, &mut tesseract.views);
+                    Self::group_ui(ui, groups, &mut tesseract.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut tesseract.transform,
+                        Some(&mut tesseract.scale),
+                        &mut tesseract.keyframes,
+                        tesseract.group,
+                    );
+                    vector_size_ui(ui, "Size:", &mut tesseract.size, false);
+                    material_ui(ui, &mut tesseract.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::Tesseract(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_tesseract = tesseract.clone();
+                        new_tesseract.name += " (copy)";
+                        to_insert.push(new_tesseract);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, tesseract.group, CopiedKind::Tesseract(tesseract.clone()))
+                            .copy(ui.ctx());
+                    }
+                    if ui.button("Delete").clicked() {
+                        to_delete.push(id);
+                    }
+                });
+            if response.header_response.clicked() {
+                selection.click(ui.input(|i| i.modifiers), ObjectID::Tesseract(id), &listed);
+            }
+            object_context_menu(
+                &response.header_response,
+                selection,
+                ObjectID::Tesseract(id),
+            );
+            if selected && selection.reveal {
+                ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
+            }
+        }
+    }
+
+    fn tori_ui(
+        ui: &mut egui::Ui,
+        groups: &SlotMap<GroupID, Group>,
+        tori: &mut SlotMap<TorusID, Torus>,
+        torus_ids: impl Iterator<Item = TorusID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<Torus>,
+        to_delete: &mut Vec<TorusID>,
+    ) {
+        let torus_ids = torus_ids.collect::<Vec<_>>();
+        let listed = torus_ids
+            .iter()
+            .copied()
+            .map(ObjectID::Torus)
+            .collect::<Vec<_>>();
+        for id in torus_ids {
+            let torus = &mut tori[id];
+            let selected = selection.id == Some(ObjectID::Torus(id));
+            let mut header =
+                egui::RichText::new(&torus.name).color(color_to_egui(torus.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !torus.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::Torus(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
                     ui.horizontal(|ui| {
                         ui.label("Name:");
-                        ui.text_edit_singleline(&mut group.name);
+                        ui.text_edit_singleline(&mut torus.name);
                     });
-                    ui.collapsing("Transform", |ui| {
-                        group.transform.ui(ui);
-                    });
-                    if ui.button("Clone").clicked() {
-                        groups_to_clone.push(group_id);
+                    visible_ui(ui, &mut torus.visible);
+                    views_ui(ui, &mut torus.views);
+                    Self::group_ui(ui, groups, &mut torus.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut torus.transform,
+                        None,
+                        &mut torus.keyframes,
+                        torus.group,
+                    );
+                    size_ui(ui, "Major Radius:", &mut torus.major_radius);
+                    size_ui(ui, "Minor Radius:", &mut torus.minor_radius);
+                    material_ui(ui, &mut torus.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::Torus(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_torus = torus.clone();
+                        new_torus.name += " (copy)";
+                        to_insert.push(new_torus);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, torus.group, CopiedKind::Torus(torus.clone()))
+                            .copy(ui.ctx());
                     }
                     if ui.button("Delete").clicked() {
-                        groups_to_delete.push(group_id);
+                        to_delete.push(id);
                     }
-                }
-                ui.collapsing("Hyperspheres", |ui| {
-                    Self::hyperspheres_ui(
-                        ui,
-                        &self.groups,
-                        &mut self.hyperspheres,
-                        grouped_objects.hyperspheres.iter().copied(),
-                        new_hypersphere_id,
-                        &mut hyperspheres_to_insert,
-                        &mut hyperspheres_to_delete,
-                    );
-                });
-                ui.collapsing("Hyperplanes", |ui| {
-                    Self::hyperplanes_ui(
-                        ui,
-                        &self.groups,
-                        &mut self.hyperplanes,
-                        grouped_objects.hyperplanes.iter().copied(),
-                        new_hyperplane_id,
-                        &mut hyperplanes_to_insert,
-                        &mut hyperplanes_to_delete,
-                    );
                 });
-            });
-
-            if let Some(id) = id
-                && new_group_id == Some(id)
-            {
-                ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
-            }
-        }
-
-        for id in groups_to_clone {
-            let mut new_group = self.groups[id].clone();
-            new_group.name += " Clone";
-            let new_id = self.groups.insert(new_group);
-
-            let new_hyperspheres = self
-                .hyperspheres
-                .values()
-                .filter(|hypersphere| hypersphere.group == Some(id))
-                .map(|hypersphere| {
-                    let mut new_hypersphere = hypersphere.clone();
-                    new_hypersphere.group = Some(new_id);
-                    new_hypersphere
-                })
-                .collect::<Vec<_>>();
-            for hypersphere in new_hyperspheres {
-                self.hyperspheres.insert(hypersphere);
+            if response.header_response.clicked() {
+                selection.click(ui.input(|i| i.modifiers), ObjectID::Torus(id), &listed);
             }
-
-            let new_hyperplanes = self
-                .hyperplanes
-                .values()
-                .filter(|hyperplane| hyperplane.group == Some(id))
-                .map(|hyperplane| {
-                    let mut new_hyperplane = hyperplane.clone();
-                    new_hyperplane.group = Some(new_id);
-                    new_hyperplane
-                })
-                .collect::<Vec<_>>();
-            for hypersphere in new_hyperplanes {
-                self.hyperplanes.insert(hypersphere);
+            object_context_menu(&response.header_response, selection, ObjectID::Torus(id));
+            if selected && selection.reveal {
+                ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
             }
         }
-
-        for id in groups_to_delete {
-            self.groups.remove(id);
-            self.hyperspheres
-                .retain(|_, hypersphere| hypersphere.group != Some(id));
-            self.hyperplanes
-                .retain(|_, hyperplane| hyperplane.group != Some(id));
-        }
-        for id in hyperspheres_to_delete {
-            self.hyperspheres.remove(id);
-        }
-        for id in hyperplanes_to_delete {
-            self.hyperplanes.remove(id);
-        }
-
-        for hypersphere in hyperspheres_to_insert {
-            self.hyperspheres.insert(hypersphere);
-        }
-        for hyperplane in hyperplanes_to_insert {
-            self.hyperplanes.insert(hyperplane);
-        }
-
-        self.cleanup_invalid_ids();
-    }
-
-    pub fn gpu_hyperspheres(
-        &self,
-    ) -> impl ExactSizeIterator<Item = rendering::objects::Hypersphere> {
-        self.hyperspheres.values().map(
-            |&Hypersphere {
-                 name: _,
-                 group,
-                 ref transform,
-                 radius,
-                 color,
-             }| rendering::objects::Hypersphere {
-                transform: Self::global_transform(&self.groups, transform, group),
-                color,
-                radius,
-            },
-        )
-    }
-
-    pub fn gpu_hyperplanes(&self) -> impl ExactSizeIterator<Item = rendering::objects::Hyperplane> {
-        self.hyperplanes.values().map(
-            |&Hyperplane {
-                 name: _,
-                 group,
-                 ref transform,
-                 width,
-                 height,
-                 depth,
-                 color,
-             }| rendering::objects::Hyperplane {
-                transform: Self::global_transform(&self.groups, transform, group),
-                color,
-                width,
-                height,
-                depth,
-                _padding: Default::default(),
-            },
-        )
     }
 
-    fn hyperspheres_ui(
+    fn spherinders_ui(
         ui: &mut egui::Ui,
         groups: &SlotMap<GroupID, Group>,
-        hyperspheres: &mut SlotMap<HypersphereID, Hypersphere>,
-        hypersphere_ids: impl Iterator<Item = HypersphereID>,
-        scroll_to_id: Option<HypersphereID>,
-        to_insert: &mut Vec<Hypersphere>,
-        to_delete: &mut Vec<HypersphereID>,
+        spherinders: &mut SlotMap<SpherinderID, Spherinder>,
+        spherinder_ids: impl Iterator<Item = SpherinderID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<Spherinder>,
+        to_delete: &mut Vec<SpherinderID>,
     ) {
-        for id in hypersphere_ids {
-            let hypersphere = &mut hyperspheres[id];
-            let response = egui::CollapsingHeader::new(
-                egui::RichText::new(&hypersphere.name).color(color_to_egui(hypersphere.color)),
-            )
-            .id_salt(id)
-            .show(ui, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Name:");
-                    ui.text_edit_singleline(&mut hypersphere.name);
-                });
-                Self::group_ui(ui, groups, &mut hypersphere.group);
-                Self::transform_ui(ui, groups, &mut hypersphere.transform, hypersphere.group);
-                ui.horizontal(|ui| {
-                    ui.label("Radius:");
-                    ui.add(egui::DragValue::new(&mut hypersphere.radius).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Color:");
-                    ui.color_edit_button_rgb(hypersphere.color.as_mut());
+        let spherinder_ids = spherinder_ids.collect::<Vec<_>>();
+        let listed = spherinder_ids
+            .iter()
+            .copied()
+            .map(ObjectID::Spherinder)
+            .collect::<Vec<_>>();
+        for id in spherinder_ids {
+            let spherinder = &mut spherinders[id];
+            let selected = selection.id == Some(ObjectID::Spherinder(id));
+            let mut header = egui::RichText::new(&spherinder.name)
+                .color(color_to_egui(spherinder.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !spherinder.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::Spherinder(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut spherinder.name);
+                    });
+                    visible_ui(ui, &mut spherinder.visible);
+                    views_ui(ui, &mut spherinder.views);
+                    Self::group_ui(ui, groups, &mut spherinder.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut spherinder.transform,
+                        None,
+                        &mut spherinder.keyframes,
+                        spherinder.group,
+                    );
+                    size_ui(ui, "Radius:", &mut spherinder.radius);
+                    size_ui(ui, "Length:", &mut spherinder.length);
+                    material_ui(ui, &mut spherinder.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::Spherinder(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_spherinder = spherinder.clone();
+                        new_spherinder.name += " (copy)";
+                        to_insert.push(new_spherinder);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, spherinder.group, CopiedKind::Spherinder(spherinder.clone()))
+                            .copy(ui.ctx());
+                    }
+                    if ui.button("Delete").clicked() {
+                        to_delete.push(id);
+                    }
                 });
-                if ui.button("Clone").clicked() {
-                    let mut new_hypersphere = hypersphere.clone();
-                    new_hypersphere.name += " Cloned";
-                    to_insert.push(new_hypersphere);
-                }
-                if ui.button("Delete").clicked() {
-                    to_delete.push(id);
-                }
-            });
-            if scroll_to_id == Some(id) {
+            if response.header_response.clicked() {
+                selection.click(ui.input(|i| i.modifiers), ObjectID::Spherinder(id), &listed);
+            }
+            object_context_menu(
+                &response.header_response,
+                selection,
+                ObjectID::Spherinder(id),
+            );
+            if selected && selection.reveal {
                 ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
             }
         }
     }
 
-    fn hyperplanes_ui(
+    fn csg_pairs_ui(
         ui: &mut egui::Ui,
         groups: &SlotMap<GroupID, Group>,
-        hyperplanes: &mut SlotMap<HyperplaneID, Hyperplane>,
-        hyperplane_ids: impl Iterator<Item = HyperplaneID>,
-        scroll_to_id: Option<HyperplaneID>,
-        to_insert: &mut Vec<Hyperplane>,
-        to_delete: &mut Vec<HyperplaneID>,
+        csg_pairs: &mut SlotMap<CsgPairID, CsgPair>,
+        csg_pair_ids: impl Iterator<Item = CsgPairID>,
+        selection: &mut Selection,
+        to_insert: &mut Vec<CsgPair>,
+        to_delete: &mut Vec<CsgPairID>,
     ) {
-        for id in hyperplane_ids {
-            let hyperplane = &mut hyperplanes[id];
-            let response = egui::CollapsingHeader::new(
-                egui::RichText::new(&hyperplane.name).color(color_to_egui(hyperplane.color)),
-            )
-            .id_salt(id)
-            .show(ui, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Name:");
-                    ui.text_edit_singleline(&mut hyperplane.name);
-                });
-                Self::group_ui(ui, groups, &mut hyperplane.group);
-                Self::transform_ui(ui, groups, &mut hyperplane.transform, hyperplane.group);
-                ui.horizontal(|ui| {
-                    ui.label("Width:");
-                    ui.add(egui::DragValue::new(&mut hyperplane.width).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Height:");
-                    ui.add(egui::DragValue::new(&mut hyperplane.height).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Depth:");
-                    ui.add(egui::DragValue::new(&mut hyperplane.depth).speed(0.1));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Color:");
-                    ui.color_edit_button_rgb(hyperplane.color.as_mut());
+        let csg_pair_ids = csg_pair_ids.collect::<Vec<_>>();
+        let listed = csg_pair_ids
+            .iter()
+            .copied()
+            .map(ObjectID::CsgPair)
+            .collect::<Vec<_>>();
+        for id in csg_pair_ids {
+            let csg_pair = &mut csg_pairs[id];
+            let selected = selection.id == Some(ObjectID::CsgPair(id));
+            let mut header =
+                egui::RichText::new(&csg_pair.name).color(color_to_egui(csg_pair.material.color));
+            if selected {
+                header = header.underline();
+            }
+            if !csg_pair.visible {
+                header = header.strikethrough();
+            }
+            if !selection.others.is_empty() && selection.contains(ObjectID::CsgPair(id)) {
+                header = header.background_color(ui.visuals().selection.bg_fill);
+            }
+            let response = egui::CollapsingHeader::new(header)
+                .id_salt(id)
+                .open((selected && selection.reveal).then_some(true))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut csg_pair.name);
+                    });
+                    visible_ui(ui, &mut csg_pair.visible);
+                    views_ui(ui, &mut csg_pair.views);
+                    Self::group_ui(ui, groups, &mut csg_pair.group);
+                    Self::transform_ui(
+                        ui,
+                        groups,
+                        &mut csg_pair.transform,
+                        None,
+                        &mut csg_pair.keyframes,
+                        csg_pair.group,
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Operation:");
+                        egui::ComboBox::new("Operation", "")
+                            .selected_text(format!("{:?}", csg_pair.op))
+                            .show_ui(ui, |ui| {
+                                for op in [CsgOp::Union, CsgOp::Intersection, CsgOp::Difference] {
+                                    ui.selectable_value(&mut csg_pair.op, op, format!("{op:?}"));
+                                }
+                            });
+                    });
+                    csg_sphere_ui(ui, "A", &mut csg_pair.a);
+                    csg_sphere_ui(ui, "B", &mut csg_pair.b);
+                    material_ui(ui, &mut csg_pair.material);
+                    if ui
+                        .button("Focus")
+                        .on_hover_text("Moves the camera to frame the object, press . to focus the selected object")
+                        .clicked()
+                    {
+                        selection.id = Some(ObjectID::CsgPair(id));
+                        selection.focus = true;
+                    }
+                    if ui.button("Duplicate").clicked() {
+                        let mut new_csg_pair = csg_pair.clone();
+                        new_csg_pair.name += " (copy)";
+                        to_insert.push(new_csg_pair);
+                    }
+                    if ui
+                        .button("Copy")
+                        .on_hover_text("Copies the object to the clipboard, to paste it into this or another scene")
+                        .clicked()
+                    {
+                        CopiedObject::new(groups, csg_pair.group, CopiedKind::CsgPair(csg_pair.clone()))
+                            .copy(ui.ctx());
+                    }
+                    if ui.button("Delete").clicked() {
+                        to_delete.push(id);
+                    }
                 });
-                if ui.button("Clone").clicked() {
-                    let mut new_hyperplane = hyperplane.clone();
-                    new_hyperplane.name += " Clone";
-                    to_insert.push(new_hyperplane);
-                }
-                if ui.button("Delete").clicked() {
-                    to_delete.push(id);
-                }
-            });
-            if scroll_to_id == Some(id) {
+            if response.header_response.clicked() {
+                selection.click(ui.input(|i| i.modifiers), ObjectID::CsgPair(id), &listed);
+            }
+            object_context_menu(&response.header_response, selection, ObjectID::CsgPair(id));
+            if selected && selection.reveal {
                 ui.scroll_to_rect(response.header_response.rect, Some(egui::Align::TOP));
             }
         }
@@ -593,14 +3213,47 @@ impl Objects {
         });
     }
 
+    /// Picks the parent of a group, leaving out the groups nested in it so that it can't become its
+    /// own ancestor.
+    fn parent_ui(ui: &mut egui::Ui, groups: &mut SlotMap<GroupID, Group>, id: GroupID) {
+        let mut parent = groups[id].parent;
+        ui.horizontal(|ui| {
+            ui.label("Parent:");
+            egui::ComboBox::new("Parent", "")
+                .selected_text(if let Some(parent) = parent {
+                    if let Some(group) = groups.get(parent) {
+                        &group.name
+                    } else {
+                        "Invalid"
+                    }
+                } else {
+                    "None"
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut parent, None, "None");
+                    for (other_id, other) in groups.iter() {
+                        if !Self::ancestors(groups, Some(other_id)).any(|ancestor| ancestor == id) {
+                            ui.selectable_value(&mut parent, Some(other_id), &other.name);
+                        }
+                    }
+                });
+        });
+        groups[id].parent = parent;
+    }
+
     fn transform_ui(
         ui: &mut egui::Ui,
         groups: &SlotMap<GroupID, Group>,
         transform: &mut Transform,
+        scale: Option<&mut cgmath::Vector4<f32>>,
+        keyframes: &mut Vec<Keyframe>,
         group: Option<GroupID>,
     ) {
         ui.collapsing("Transform", |ui| {
             transform.ui(ui);
+            if let Some(scale) = scale {
+                vector_size_ui(ui, "Scale:", scale, true);
+            }
             ui.add_enabled_ui(false, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("Global Position:");
@@ -610,6 +3263,16 @@ impl Objects {
                     );
                 });
             });
+            let drift =
+                (Self::composed_transform(groups, transform.transform(), group).magnitude() - 1.0)
+                    .abs();
+            if drift > MAX_TRANSFORM_DRIFT {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Global transform drifted from unit magnitude by {drift:.2e}, it is renormalized before rendering"),
+                );
+            }
+            ui.collapsing("Keyframes", |ui| keyframes_ui(ui, transform, keyframes));
         });
     }
 
@@ -618,20 +3281,440 @@ impl Objects {
         transform: &Transform,
         group: Option<GroupID>,
     ) -> math::Transform {
-        if let Some(group_id) = group
-            && let Some(group) = groups.get(group_id)
+        Self::group_transform(groups, transform.transform(), group)
+    }
+
+    /// Applies the transforms of `group` and its parents to a transform relative to `group`.
+    ///
+    /// The result is renormalized, since rounding errors from composing many transforms would
+    /// otherwise shear the object.
+    fn group_transform(
+        groups: &SlotMap<GroupID, Group>,
+        transform: math::Transform,
+        group: Option<GroupID>,
+    ) -> math::Transform {
+        Self::composed_transform(groups, transform, group).normalize()
+    }
+
+    fn composed_transform(
+        groups: &SlotMap<GroupID, Group>,
+        transform: math::Transform,
+        group: Option<GroupID>,
+    ) -> math::Transform {
+        Self::ancestors(groups, group).fold(transform, |transform, group_id| {
+            groups[group_id].transform.transform().then(transform)
+        })
+    }
+
+    fn animated_transform(
+        transform: &Transform,
+        keyframes: &[Keyframe],
+        time: Option<f32>,
+    ) -> math::Transform {
+        time.and_then(|time| Keyframe::sample(keyframes, time))
+            .unwrap_or_else(|| transform.transform())
+    }
+
+    /// The time of the last keyframe of any object.
+    pub fn animation_duration(&self) -> f32 {
+        self.hyperspheres
+            .values()
+            .flat_map(|hypersphere| &hypersphere.keyframes)
+            .chain(
+                self.hyperplanes
+                    .values()
+                    .flat_map(|hyperplane| &hyperplane.keyframes),
+            )
+            .chain(
+                self.tesseracts
+                    .values()
+                    .flat_map(|tesseract| &tesseract.keyframes),
+            )
+            .chain(self.tori.values().flat_map(|torus| &torus.keyframes))
+            .chain(
+                self.spherinders
+                    .values()
+                    .flat_map(|spherinder| &spherinder.keyframes),
+            )
+            .chain(
+                self.csg_pairs
+                    .values()
+                    .flat_map(|csg_pair| &csg_pair.keyframes),
+            )
+            .map(|keyframe| keyframe.time)
+            .fold(0.0, f32::max)
+    }
+
+    /// Iterates over `group` and then each of its parents in turn, stopping at a missing group or
+    /// at the first group that was already visited, so a cycle in the parents is only walked once.
+    fn ancestors(
+        groups: &SlotMap<GroupID, Group>,
+        group: Option<GroupID>,
+    ) -> impl Iterator<Item = GroupID> {
+        let mut visited = BTreeSet::new();
+        std::iter::successors(group.filter(|&id| groups.contains_key(id)), |&id| {
+            groups[id]
+                .parent
+                .filter(|&parent| groups.contains_key(parent))
+        })
+        .take_while(move |&id| visited.insert(id))
+    }
+}
+
+fn group_header(group: &Group) -> egui::RichText {
+    let header = egui::RichText::new(&group.name);
+    if group.visible {
+        header
+    } else {
+        header.strikethrough()
+    }
+}
+
+fn keyframes_ui(ui: &mut egui::Ui, transform: &Transform, keyframes: &mut Vec<Keyframe>) {
+    if ui
+        .button("Add Keyframe")
+        .on_hover_text("Adds the current transform one second after the last keyframe")
+        .clicked()
+    {
+        let time = keyframes
+            .iter()
+            .map(|keyframe| keyframe.time + 1.0)
+            .fold(0.0, f32::max);
+        keyframes.push(Keyframe {
+            time,
+            transform: *transform,
+        });
+    }
+
+    let mut to_delete = None;
+    for (index, keyframe) in keyframes.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label("Time:");
+            ui.add(
+                egui::DragValue::new(&mut keyframe.time)
+                    .speed(0.1)
+                    .range(0.0..=f32::INFINITY)
+                    .suffix("s"),
+            );
+            if ui
+                .button("Set")
+                .on_hover_text("Replaces the keyframe with the current transform")
+                .clicked()
+            {
+                keyframe.transform = *transform;
+            }
+            if ui.button("Delete").clicked() {
+                to_delete = Some(index);
+            }
+        });
+    }
+    if let Some(index) = to_delete {
+        keyframes.remove(index);
+    }
+}
+
+/// Whether an object or group named `name` is listed while the objects panel is filtered by
+/// `filter`, which has to be lowercase. The selected object is always listed so that it doesn't
+/// disappear while it is being edited.
+fn is_listed(name: &str, filter: &str, selected: bool) -> bool {
+    selected || name.to_lowercase().contains(filter)
+}
+
+fn visible_ui(ui: &mut egui::Ui, visible: &mut bool) {
+    ui.toggle_value(visible, "👁 Visible");
+}
+
+fn views_ui(ui: &mut egui::Ui, views: &mut ViewMask) {
+    ui.horizontal(|ui| {
+        ui.label("Views:");
+        for (view, name) in [
+            (ViewMask::XYZ, "XYZ"),
+            (ViewMask::XWZ, "XWZ"),
+            (ViewMask::XYW, "XYW"),
+        ] {
+            let mut shown = views.contains(view);
+            ui.checkbox(&mut shown, name);
+            views.set(view, shown);
+        }
+    })
+    .response
+    .on_hover_text("The views are named after the axes they show by default");
+}
+
+/// Edits a radius or size, which can't go below [`MIN_SIZE`]. The value is tinted as a warning
+/// while it is held at the minimum.
+fn size_ui(ui: &mut egui::Ui, label: &str, value: &mut f32) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let at_min = *value <= MIN_SIZE;
+        if at_min {
+            ui.visuals_mut().override_text_color = Some(ui.visuals().warn_fg_color);
+        }
+        let response = ui.add(egui::DragValue::new(value).speed(0.1));
+        if at_min {
+            response.on_hover_text(format!("Can't be smaller than {MIN_SIZE}"));
+        }
+        *value = value.max(MIN_SIZE);
+    });
+}
+
+/// Edits each component of a size or scale like [`size_ui`]. When `signed` the components can be
+/// negative to mirror along that axis, and it is their magnitude that can't go below [`MIN_SIZE`].
+fn vector_size_ui(ui: &mut egui::Ui, label: &str, value: &mut cgmath::Vector4<f32>, signed: bool) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let components: &mut [f32; 4] = value.as_mut();
+        for (prefix, component) in ["x:", "y:", "z:", "w:"].into_iter().zip(components) {
+            ui.scope(|ui| {
+                let at_min = if signed {
+                    component.abs() <= MIN_SIZE
+                } else {
+                    *component <= MIN_SIZE
+                };
+                if at_min {
+                    ui.visuals_mut().override_text_color = Some(ui.visuals().warn_fg_color);
+                }
+                let response = ui.add(egui::DragValue::new(component).speed(0.1).prefix(prefix));
+                if at_min {
+                    response.on_hover_text(format!("Can't be closer to 0 than {MIN_SIZE}"));
+                }
+            });
+            *component = if signed {
+                component.abs().max(MIN_SIZE).copysign(*component)
+            } else {
+                component.max(MIN_SIZE)
+            };
+        }
+    });
+}
+
+const AXIS_NAMES: [&str; 4] = ["X", "Y", "Z", "W"];
+
+/// The menu shown when an object's header in the list is right clicked.
+fn object_context_menu(response: &egui::Response, selection: &mut Selection, id: ObjectID) {
+    response.context_menu(|ui| {
+        if ui
+            .button("Look At")
+            .on_hover_text("Turns the camera to face the object without moving it")
+            .clicked()
         {
-            group.transform.transform().then(transform.transform())
-        } else {
-            transform.transform()
+            selection.id = Some(id);
+            selection.look_at = true;
         }
+        ui.menu_button("Duplicate Mirrored", |ui| {
+            for (axis, name) in AXIS_NAMES.into_iter().enumerate() {
+                if ui
+                    .button(name)
+                    .on_hover_text("Duplicates the selected objects, or this object if it isn't selected, mirrored across the axis")
+                    .clicked()
+                {
+                    if !selection.contains(id) {
+                        selection.id = Some(id);
+                        selection.others.clear();
+                    }
+                    selection.mirror = Some(axis);
+                }
+            }
+        });
+    });
+}
+
+/// The placement and size of one of the hyperspheres in a CSG pair, relative to the pair.
+fn csg_sphere_ui(ui: &mut egui::Ui, name: &str, sphere: &mut CsgSphere) {
+    ui.collapsing(name, |ui| {
+        sphere.transform.ui(ui);
+        vector_size_ui(ui, "Scale:", &mut sphere.scale, true);
+        size_ui(ui, "Radius:", &mut sphere.radius);
+    });
+}
+
+/// The color, pattern, reflectivity, specular highlights, emission, opacity and IOR of any kind of
+/// object.
+fn material_ui(ui: &mut egui::Ui, material: &mut Material) {
+    ui.horizontal(|ui| {
+        ui.label("Color:");
+        ui.color_edit_button_rgb(material.color.as_mut());
+    });
+    ui.horizontal(|ui| {
+        ui.label("Pattern:");
+        egui::ComboBox::new("Pattern", "")
+            .selected_text(format!("{:?}", material.pattern))
+            .show_ui(ui, |ui| {
+                for pattern in [Pattern::Solid, Pattern::Checker, Pattern::Grid] {
+                    ui.selectable_value(&mut material.pattern, pattern, format!("{pattern:?}"));
+                }
+            });
+        ui.add_enabled(
+            material.pattern != Pattern::Solid,
+            egui::DragValue::new(&mut material.pattern_scale)
+                .speed(0.1)
+                .prefix("scale:"),
+        );
+        material.pattern_scale = material.pattern_scale.max(0.01);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Reflectivity:");
+        ui.add(egui::Slider::new(&mut material.reflectivity, 0.0..=1.0));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Specular:");
+        ui.add(egui::Slider::new(&mut material.specular, 0.0..=1.0));
+        ui.add_enabled(
+            material.specular > 0.0,
+            egui::Slider::new(&mut material.shininess, 1.0..=256.0)
+                .logarithmic(true)
+                .text("shininess"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Emission:");
+        ui.color_edit_button_rgb(material.emission_color.as_mut());
+        ui.add(egui::DragValue::new(&mut material.emission_intensity).speed(0.1));
+        material.emission_intensity = material.emission_intensity.max(0.0);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Opacity:");
+        ui.add(egui::Slider::new(&mut material.opacity, 0.0..=1.0));
+    });
+    ui.add_enabled_ui(material.opacity < 1.0, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("IOR:");
+            ui.add(egui::Slider::new(&mut material.ior, 1.0..=3.0));
+        });
+    });
+}
+
+/// The SplitMix64 generator, which is plenty for scattering objects around and keeps generated
+/// scenes the same across platforms and versions.
+struct Random(u64);
+
+impl Random {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn range(&mut self, range: std::ops::Range<f32>) -> f32 {
+        // the top 24 bits fill an f32's mantissa exactly
+        let t = (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32;
+        range.start + (range.end - range.start) * t
     }
 }
 
+/// Converts a linear color, like the ones the ray tracer works with and `color_edit_button_rgb`
+/// edits, to how it looks on screen.
 fn color_to_egui(color: cgmath::Vector3<f32>) -> egui::Color32 {
-    egui::Color32::from_rgb(
-        (color.x.clamp(0.0, 1.0) * 255.0) as u8,
-        (color.y.clamp(0.0, 1.0) * 255.0) as u8,
-        (color.z.clamp(0.0, 1.0) * 255.0) as u8,
-    )
+    let channel = |channel: f32| (linear_to_srgb(channel.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    egui::Color32::from_rgb(channel(color.x), channel(color.y), channel(color.z))
+}
+
+/// The same sRGB encoding as `srgb_encode` in full_screen_quad.wgsl.
+fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    fn example_transform() -> Transform {
+        Transform {
+            position: cgmath::vec4(1.0, -2.0, 3.0, -4.0),
+            xy_rotation: 0.3,
+            xz_rotation: -0.7,
+            xw_rotation: 1.1,
+            yz_rotation: 0.2,
+            yw_rotation: -1.4,
+            zw_rotation: 0.9,
+        }
+    }
+
+    fn fields(transform: Transform) -> [f32; 10] {
+        let Transform {
+            position,
+            xy_rotation,
+            xz_rotation,
+            xw_rotation,
+            yz_rotation,
+            yw_rotation,
+            zw_rotation,
+        } = transform;
+        [
+            position.x,
+            position.y,
+            position.z,
+            position.w,
+            xy_rotation,
+            xz_rotation,
+            xw_rotation,
+            yz_rotation,
+            yw_rotation,
+            zw_rotation,
+        ]
+    }
+
+    #[test]
+    fn group_depth_survives_cycles() {
+        let mut objects = Objects::default();
+        assert_eq!(objects.max_group_depth(), 0);
+
+        let outer = objects.groups.insert(Group::default());
+        let middle = objects.groups.insert(Group {
+            parent: Some(outer),
+            ..Default::default()
+        });
+        objects.groups.insert(Group {
+            parent: Some(middle),
+            ..Default::default()
+        });
+        assert_eq!(objects.max_group_depth(), 3);
+
+        // a cycle can only come from a broken file, each group in it is only counted once
+        objects.groups[outer].parent = Some(middle);
+        assert_eq!(
+            Objects::ancestors(&objects.groups, Some(outer)).collect::<Vec<_>>(),
+            [outer, middle]
+        );
+        // the innermost group leads into the cycle without being part of it
+        assert_eq!(objects.max_group_depth(), 3);
+
+        objects.groups.retain(|id, _| id == outer || id == middle);
+        assert_eq!(objects.max_group_depth(), 2);
+    }
+
+    #[test]
+    fn mirroring_twice_gives_back_the_original() {
+        let transform = example_transform();
+        for axis in 0..4 {
+            assert_eq!(
+                fields(transform.mirrored(axis).mirrored(axis)),
+                fields(transform)
+            );
+        }
+    }
+
+    #[test]
+    fn mirroring_reflects_on_both_sides() {
+        let transform = example_transform();
+        let point = cgmath::vec4(0.5, 1.5, -2.5, 3.5);
+        for axis in 0..4 {
+            let reflect = |mut point: cgmath::Vector4<f32>| {
+                point[axis] = -point[axis];
+                point
+            };
+            assert_abs_diff_eq!(
+                transform.mirrored(axis).transform().transform_point(point),
+                reflect(transform.transform().transform_point(reflect(point))),
+                epsilon = 1e-5
+            );
+        }
+    }
 }
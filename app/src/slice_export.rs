@@ -0,0 +1,297 @@
+use cgmath::{ElementWise, InnerSpace};
+use rendering::objects::{Hyperplane, Hypersphere, Tesseract};
+use std::{f32::consts::PI, fmt::Write};
+
+/// The number of vertices around each ring of an exported sphere.
+const SPHERE_SEGMENTS: usize = 32;
+/// The number of bands an exported sphere is split into from pole to pole.
+const SPHERE_RINGS: usize = 16;
+/// Intersections closer together than this are merged into one polygon vertex.
+const MERGE_DISTANCE: f32 = 1e-5;
+
+/// Writes the 3d cross section of the objects at `w` as a triangulated OBJ mesh, with the x, y
+/// and z coordinates of the slice as the mesh's coordinates.
+///
+/// Hyperspheres become ellipsoids, tesseracts become closed polyhedra, and bounded hyperplanes
+/// become flat polygons. Infinite hyperplanes, tori, spherinders and CSG pairs are left out.
+pub fn slice_obj(
+    hyperspheres: impl Iterator<Item = Hypersphere>,
+    hyperplanes: impl Iterator<Item = Hyperplane>,
+    tesseracts: impl Iterator<Item = Tesseract>,
+    w: f32,
+) -> String {
+    let mut obj = Obj::default();
+    writeln!(obj.text, "# the cross section of a 4d scene at w = {w}").unwrap();
+
+    for (index, hypersphere) in hyperspheres.enumerate() {
+        if let Some((center, axes)) = hypersphere_slice(&hypersphere, w) {
+            writeln!(obj.text, "o Hypersphere{index}").unwrap();
+            obj.ellipsoid(center, axes);
+        }
+    }
+
+    for (index, hyperplane) in hyperplanes.enumerate() {
+        if hyperplane.infinite != 0 {
+            continue;
+        }
+        let half_extents = [
+            (cgmath::Vector4::unit_x(), hyperplane.height * 0.5),
+            (cgmath::Vector4::unit_z(), hyperplane.width * 0.5),
+            (cgmath::Vector4::unit_w(), hyperplane.depth * 0.5),
+        ];
+        let polygon = box_slice(
+            hyperplane.transform,
+            cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0),
+            half_extents,
+            w,
+        );
+        if polygon.len() >= 3 {
+            writeln!(obj.text, "o Hyperplane{index}").unwrap();
+            obj.polygon(polygon, None);
+        }
+    }
+
+    for (index, tesseract) in tesseracts.enumerate() {
+        // the cross section is bounded by the cross sections of the tesseract's 8 cubic cells
+        let half_size = tesseract.size.map(f32::abs) * 0.5;
+        let axes = [
+            cgmath::Vector4::unit_x(),
+            cgmath::Vector4::unit_y(),
+            cgmath::Vector4::unit_z(),
+            cgmath::Vector4::unit_w(),
+        ];
+        let polygons = (0..4)
+            .flat_map(|axis| [-1.0, 1.0].map(|side| (axis, side)))
+            .map(|(axis, side)| {
+                let others = (0..4)
+                    .filter(|&other| other != axis)
+                    .map(|other| (axes[other], half_size[other]))
+                    .collect::<Vec<_>>();
+                box_slice(
+                    tesseract.transform,
+                    axes[axis] * half_size[axis] * side,
+                    [others[0], others[1], others[2]],
+                    w,
+                )
+            })
+            .filter(|polygon| polygon.len() >= 3)
+            .collect::<Vec<_>>();
+        if polygons.is_empty() {
+            continue;
+        }
+
+        let points = polygons.iter().flatten();
+        let center = points
+            .clone()
+            .fold(cgmath::Vector3::new(0.0, 0.0, 0.0), |a, &b| a + b)
+            / points.count() as f32;
+        writeln!(obj.text, "o Tesseract{index}").unwrap();
+        for polygon in polygons {
+            let outward =
+                polygon.iter().sum::<cgmath::Vector3<f32>>() / polygon.len() as f32 - center;
+            obj.polygon(polygon, Some(outward));
+        }
+    }
+
+    obj.text
+}
+
+/// The ellipsoid where a hypersphere crosses the slice, as its center and the three axes of the
+/// slice that it is stretched along.
+fn hypersphere_slice(
+    hypersphere: &Hypersphere,
+    w: f32,
+) -> Option<(cgmath::Vector4<f32>, [cgmath::Vector4<f32>; 3])> {
+    // in the hypersphere's unscaled space the slice is the hyperplane `normal . q = offset`, which
+    // cuts the round hypersphere in a round sphere
+    let normal = hypersphere
+        .transform
+        .reverse()
+        .transform_direction(cgmath::Vector4::unit_w())
+        .mul_element_wise(hypersphere.scale);
+    let offset = w - hypersphere.transform.position().w;
+    let normal_squared = normal.magnitude2();
+    if normal_squared == 0.0 {
+        return None;
+    }
+    let radius_squared = hypersphere.radius * hypersphere.radius - offset * offset / normal_squared;
+    if radius_squared <= 0.0 {
+        return None;
+    }
+    let radius = radius_squared.sqrt();
+    let center = normal * (offset / normal_squared);
+
+    let to_world = |direction: cgmath::Vector4<f32>| {
+        hypersphere
+            .transform
+            .transform_direction(direction.mul_element_wise(hypersphere.scale))
+    };
+    let [a, b, c] = perpendicular_basis(normal).map(|axis| to_world(axis * radius));
+    Some((
+        hypersphere
+            .transform
+            .transform_point(center.mul_element_wise(hypersphere.scale)),
+        [a, b, c],
+    ))
+}
+
+/// Three unit vectors that are perpendicular to each other and to `normal`.
+fn perpendicular_basis(normal: cgmath::Vector4<f32>) -> [cgmath::Vector4<f32>; 3] {
+    let mut basis = vec![normal.normalize()];
+    for axis in [
+        cgmath::Vector4::unit_x(),
+        cgmath::Vector4::unit_y(),
+        cgmath::Vector4::unit_z(),
+        cgmath::Vector4::unit_w(),
+    ] {
+        let perpendicular = basis
+            .iter()
+            .fold(axis, |axis, &other| axis - other * axis.dot(other));
+        // the axis closest to the ones already picked is skipped, at most one of them can be
+        if perpendicular.magnitude() > 0.5 && basis.len() < 4 {
+            basis.push(perpendicular.normalize());
+        }
+    }
+    [basis[1], basis[2], basis[3]]
+}
+
+/// The corners of the polygon where the slice crosses a box in an object's space, centered on
+/// `center` and reaching along each of the three axes by its half extent.
+fn box_slice(
+    transform: math::Transform,
+    center: cgmath::Vector4<f32>,
+    half_extents: [(cgmath::Vector4<f32>, f32); 3],
+    w: f32,
+) -> Vec<cgmath::Vector3<f32>> {
+    let distance = |point| transform.transform_point(point).w - w;
+
+    let mut points: Vec<cgmath::Vector3<f32>> = vec![];
+    // each of the box's 12 edges runs along one axis at one corner of the other two
+    for axis in 0..3 {
+        let (direction, extent) = half_extents[axis];
+        let (a, a_extent) = half_extents[(axis + 1) % 3];
+        let (b, b_extent) = half_extents[(axis + 2) % 3];
+        for (a_side, b_side) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+            let middle = center + a * a_extent * a_side + b * b_extent * b_side;
+            let start = middle - direction * extent;
+            let end = middle + direction * extent;
+            let (start_distance, end_distance) = (distance(start), distance(end));
+            if start_distance * end_distance > 0.0 || start_distance == end_distance {
+                continue;
+            }
+            let t = start_distance / (start_distance - end_distance);
+            let point = transform
+                .transform_point(start + (end - start) * t)
+                .truncate();
+            if points
+                .iter()
+                .all(|&other| (other - point).magnitude() > MERGE_DISTANCE)
+            {
+                points.push(point);
+            }
+        }
+    }
+    points
+}
+
+#[derive(Default)]
+struct Obj {
+    text: String,
+    vertex_count: usize,
+}
+
+impl Obj {
+    /// Adds a vertex and returns its index, OBJ indices start at 1.
+    fn vertex(&mut self, position: cgmath::Vector3<f32>) -> usize {
+        writeln!(self.text, "v {} {} {}", position.x, position.y, position.z).unwrap();
+        self.vertex_count += 1;
+        self.vertex_count
+    }
+
+    fn triangle(&mut self, a: usize, b: usize, c: usize) {
+        writeln!(self.text, "f {a} {b} {c}").unwrap();
+    }
+
+    /// A closed UV sphere mapped onto the ellipsoid with the given center and axes.
+    fn ellipsoid(&mut self, center: cgmath::Vector4<f32>, mut axes: [cgmath::Vector4<f32>; 3]) {
+        // the triangles face outwards when the axes are right handed
+        let [a, b, c] = axes.map(|axis| axis.truncate());
+        if a.cross(b).dot(c) < 0.0 {
+            axes.swap(1, 2);
+        }
+        let [a, b, c] = axes.map(|axis| axis.truncate());
+        let center = center.truncate();
+
+        let top = self.vertex(center + b);
+        let rings = (1..SPHERE_RINGS)
+            .map(|ring| {
+                let theta = PI * ring as f32 / SPHERE_RINGS as f32;
+                (0..SPHERE_SEGMENTS)
+                    .map(|segment| {
+                        let phi = 2.0 * PI * segment as f32 / SPHERE_SEGMENTS as f32;
+                        self.vertex(
+                            center
+                                + a * (theta.sin() * phi.cos())
+                                + b * theta.cos()
+                                + c * (theta.sin() * phi.sin()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let bottom = self.vertex(center - b);
+
+        for segment in 0..SPHERE_SEGMENTS {
+            let next = (segment + 1) % SPHERE_SEGMENTS;
+            self.triangle(top, rings[0][next], rings[0][segment]);
+            for ring in rings.windows(2) {
+                let (upper, lower) = (&ring[0], &ring[1]);
+                self.triangle(upper[segment], upper[next], lower[next]);
+                self.triangle(upper[segment], lower[next], lower[segment]);
+            }
+            let last = &rings[rings.len() - 1];
+            self.triangle(last[segment], last[next], bottom);
+        }
+    }
+
+    /// A convex polygon given by its corners in any order, fanned into triangles that face
+    /// `outward` if it is given.
+    fn polygon(
+        &mut self,
+        mut points: Vec<cgmath::Vector3<f32>>,
+        outward: Option<cgmath::Vector3<f32>>,
+    ) {
+        let center = points.iter().sum::<cgmath::Vector3<f32>>() / points.len() as f32;
+        let Some(normal) = points
+            .iter()
+            .map(|&point| (points[0] - center).cross(point - center))
+            .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+            .filter(|normal| normal.magnitude2() > 0.0)
+        else {
+            return;
+        };
+        let normal = match outward {
+            Some(outward) if normal.dot(outward) < 0.0 => -normal,
+            _ => normal,
+        };
+
+        // sorting the corners by their angle around the normal puts them in counterclockwise order
+        let u = (points[0] - center).normalize();
+        let v = normal.normalize().cross(u);
+        points.sort_by(|&a, &b| {
+            let angle = |point: cgmath::Vector3<f32>| {
+                let offset = point - center;
+                offset.dot(v).atan2(offset.dot(u))
+            };
+            angle(a).total_cmp(&angle(b))
+        });
+
+        let indices = points
+            .into_iter()
+            .map(|point| self.vertex(point))
+            .collect::<Vec<_>>();
+        for pair in indices[1..].windows(2) {
+            self.triangle(indices[0], pair[0], pair[1]);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use eframe::egui;
+
+#[derive(Debug, Default)]
+pub struct Timeline {
+    pub time: f32,
+    state: PlaybackState,
+    /// Starts over from the beginning once the last keyframe is reached.
+    pub looping: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum PlaybackState {
+    /// The objects are drawn with their own transforms, ignoring their keyframes.
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+impl Timeline {
+    /// The time the objects should be animated at, or `None` when the timeline is stopped.
+    pub fn animation_time(&self) -> Option<f32> {
+        (self.state != PlaybackState::Stopped).then_some(self.time)
+    }
+
+    pub fn update(&mut self, ts: f32, duration: f32) {
+        if self.state != PlaybackState::Playing {
+            return;
+        }
+
+        self.time += ts;
+        if self.time > duration {
+            if self.looping && duration > 0.0 {
+                self.time %= duration;
+            } else {
+                self.time = duration;
+                self.state = PlaybackState::Paused;
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, duration: f32) {
+        ui.horizontal(|ui| {
+            if self.state == PlaybackState::Playing {
+                if ui.button("Pause").clicked() {
+                    self.state = PlaybackState::Paused;
+                }
+            } else if ui.button("Play").clicked() {
+                if self.time >= duration {
+                    self.time = 0.0;
+                }
+                self.state = PlaybackState::Playing;
+            }
+            if ui
+                .add_enabled(
+                    self.state != PlaybackState::Stopped,
+                    egui::Button::new("Stop"),
+                )
+                .clicked()
+            {
+                self.state = PlaybackState::Stopped;
+                self.time = 0.0;
+            }
+            ui.checkbox(&mut self.looping, "Loop");
+        });
+
+        self.time = self.time.min(duration);
+        let scrubbed = ui
+            .add(egui::Slider::new(&mut self.time, 0.0..=duration).suffix("s"))
+            .changed();
+        if scrubbed && self.state == PlaybackState::Stopped {
+            self.state = PlaybackState::Paused;
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Compares [`Transform::transform_points`] and [`Transform::transform_directions`] against calling
+//! [`Transform::transform_point`] and [`Transform::transform_direction`] on each element.
+//!
+//! Run with `cargo bench -p math`.
+
+use math::Transform;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const COUNT: usize = 100_000;
+const RUNS: u32 = 100;
+
+/// The average time of `f` over [`RUNS`] runs, after one run to warm up.
+fn time(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let transform = Transform::translation(cgmath::vec4(1.0, -2.0, 3.0, -4.0))
+        .then(Transform::rotate_xy(0.3))
+        .then(Transform::rotate_xw(1.1))
+        .then(Transform::rotate_yz(0.2))
+        .then(Transform::rotate_zw(0.9));
+    let points: Vec<_> = (0..COUNT)
+        .map(|i| {
+            let i = i as f32;
+            cgmath::vec4(i.sin(), i.cos(), (i * 0.3).sin(), (i * 0.7).cos())
+        })
+        .collect();
+    let mut out = vec![cgmath::vec4(0.0, 0.0, 0.0, 0.0); COUNT];
+
+    // the transform goes through `black_box` on every call so the loops can't hoist the motor out
+    let results = [
+        (
+            "transform_point loop",
+            time(|| {
+                for (&point, out) in points.iter().zip(&mut out) {
+                    *out = black_box(transform).transform_point(point);
+                }
+                black_box(&mut out);
+            }),
+        ),
+        (
+            "transform_points",
+            time(|| {
+                black_box(transform).transform_points(&points, &mut out);
+                black_box(&mut out);
+            }),
+        ),
+        (
+            "transform_direction loop",
+            time(|| {
+                for (&direction, out) in points.iter().zip(&mut out) {
+                    *out = black_box(transform).transform_direction(direction);
+                }
+                black_box(&mut out);
+            }),
+        ),
+        (
+            "transform_directions",
+            time(|| {
+                black_box(transform).transform_directions(&points, &mut out);
+                black_box(&mut out);
+            }),
+        ),
+    ];
+    for (name, duration) in results {
+        println!(
+            "{name:<26}{:>8.2} ms for {COUNT} points",
+            duration.as_secs_f64() * 1000.0
+        );
+    }
+}
@@ -1,512 +1,1497 @@
-pub use impls::{Rotor, Transform};
+pub use self::f32::{PgaBivector, Rotor, Transform, VgaBivector};
 
-mod impls {
-    use bytemuck::{Pod, Zeroable};
-    use serde::{Deserialize, Serialize};
+/// Compares the components like cgmath does for its vectors and matrices.
+macro_rules! impl_approx {
+    ($t:ident, $type:ident, $count:literal) => {
+        impl approx::AbsDiffEq for $type {
+            type Epsilon = $t;
 
-    ga_generator::ga! {
-        element_type = f32;
-        scalar_name = s;
-        elements = [e0 = zero, e1 = positive_one, e2 = positive_one, e3 = positive_one, e4 = positive_one];
-
-        group Scalar = s;
+            #[inline]
+            fn default_epsilon() -> $t {
+                $t::default_epsilon()
+            }
 
-        group VgaVector      = e1 + e2 + e3 + e4;
-        group VgaBivector    = VgaVector ^ VgaVector;
-        group VgaTrivector   = VgaVector ^ VgaBivector;
-        group VgaQuadvector  = VgaVector ^ VgaTrivector;
-        group VgaPentavector = VgaVector ^ VgaQuadvector;
+            #[inline]
+            fn abs_diff_eq(&self, other: &Self, epsilon: $t) -> bool {
+                bytemuck::cast_ref::<_, [$t; $count]>(self)[..]
+                    .abs_diff_eq(&bytemuck::cast_ref::<_, [$t; $count]>(other)[..], epsilon)
+            }
+        }
 
-        group #[derive(Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Rotor = Scalar + VgaBivector + VgaQuadvector;
+        impl approx::RelativeEq for $type {
+            #[inline]
+            fn default_max_relative() -> $t {
+                $t::default_max_relative()
+            }
 
-        group RotorSquaredMagnitude = Scalar + VgaQuadvector;
-        fn rotor_squared_magnitude(rotor: Rotor) -> RotorSquaredMagnitude {
-            return ~rotor * rotor;
+            #[inline]
+            fn relative_eq(&self, other: &Self, epsilon: $t, max_relative: $t) -> bool {
+                bytemuck::cast_ref::<_, [$t; $count]>(self)[..].relative_eq(
+                    &bytemuck::cast_ref::<_, [$t; $count]>(other)[..],
+                    epsilon,
+                    max_relative,
+                )
+            }
         }
 
-        fn rotor_then(a: Rotor, b: Rotor) -> Rotor {
-            return b * a;
-        }
+        impl approx::UlpsEq for $type {
+            #[inline]
+            fn default_max_ulps() -> u32 {
+                $t::default_max_ulps()
+            }
 
-        fn rotor_reverse(rotor: Rotor) -> Rotor {
-            return ~rotor;
+            #[inline]
+            fn ulps_eq(&self, other: &Self, epsilon: $t, max_ulps: u32) -> bool {
+                bytemuck::cast_ref::<_, [$t; $count]>(self)[..].ulps_eq(
+                    &bytemuck::cast_ref::<_, [$t; $count]>(other)[..],
+                    epsilon,
+                    max_ulps,
+                )
+            }
         }
+    };
+}
 
-        fn rotate_direction(rotor: Rotor, x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - x*e0;
-            let y = e2 - y*e0;
-            let z = e3 - z*e0;
-            let w = e4 - w*e0;
-            let origin = ((e1 ^ e2) ^ e3) ^ e4;
-            // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
-            let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
+macro_rules! impls {
+    ($t:ident) => {
+        use bytemuck::{Pod, Zeroable};
+        use cgmath::{InnerSpace, SquareMatrix};
+        use serde::{Deserialize, Serialize};
+        use std::$t::consts::TAU;
 
-            let transformed = (~rotor * point) * rotor;
+        ga_generator::ga! {
+            element_type = $t;
+            scalar_name = s;
+            elements = [e0 = zero, e1 = positive_one, e2 = positive_one, e3 = positive_one, e4 = positive_one];
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_rotor = point | (1 - (~rotor * rotor));
+            group Scalar = s;
 
-            let result = transformed + assume_normalised_rotor;
+            group VgaVector      = e1 + e2 + e3 + e4;
+            group VgaBivector    = VgaVector ^ VgaVector;
+            group VgaTrivector   = VgaVector ^ VgaBivector;
+            group VgaQuadvector  = VgaVector ^ VgaTrivector;
+            group VgaPentavector = VgaVector ^ VgaQuadvector;
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+            group #[derive(PartialEq, Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Rotor = Scalar + VgaBivector + VgaQuadvector;
 
-        fn rotor_x(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - 1*e0;
-            let y = e2 - 0*e0;
-            let z = e3 - 0*e0;
-            let w = e4 - 0*e0;
-            let origin = ((e1 ^ e2) ^ e3) ^ e4;
-            // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
-            let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
+            group RotorSquaredMagnitude = Scalar + VgaQuadvector;
+            fn rotor_squared_magnitude(rotor: Rotor) -> RotorSquaredMagnitude {
+                return ~rotor * rotor;
+            }
 
-            let transformed = (~rotor * point) * rotor;
+            fn rotor_scale(rotor: Rotor, factor: RotorSquaredMagnitude) -> Rotor {
+                return rotor * factor;
+            }
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_rotor = point | (1 - (~rotor * rotor));
+            fn rotor_dot(a: Rotor, b: Rotor) -> Scalar {
+                return grade_part(0, ~a * b);
+            }
 
-            let result = transformed + assume_normalised_rotor;
+            fn rotor_then(a: Rotor, b: Rotor) -> Rotor {
+                return b * a;
+            }
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+            fn rotor_reverse(rotor: Rotor) -> Rotor {
+                return ~rotor;
+            }
 
-        fn rotor_y(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - 0*e0;
-            let y = e2 - 1*e0;
-            let z = e3 - 0*e0;
-            let w = e4 - 0*e0;
-            let origin = ((e1 ^ e2) ^ e3) ^ e4;
-            // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
-            let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
+            fn rotate_direction(rotor: Rotor, x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - x*e0;
+                let y = e2 - y*e0;
+                let z = e3 - z*e0;
+                let w = e4 - w*e0;
+                let origin = ((e1 ^ e2) ^ e3) ^ e4;
+                // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
+                let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
 
-            let transformed = (~rotor * point) * rotor;
+                let transformed = (~rotor * point) * rotor;
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_rotor = point | (1 - (~rotor * rotor));
+                // without this it tries to return an extra scalar
+                let assume_normalised_rotor = point | (1 - (~rotor * rotor));
 
-            let result = transformed + assume_normalised_rotor;
+                let result = transformed + assume_normalised_rotor;
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
 
-        fn rotor_z(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - 0*e0;
-            let y = e2 - 0*e0;
-            let z = e3 - 1*e0;
-            let w = e4 - 0*e0;
-            let origin = ((e1 ^ e2) ^ e3) ^ e4;
-            // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
-            let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
+            fn rotor_x(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - 1*e0;
+                let y = e2 - 0*e0;
+                let z = e3 - 0*e0;
+                let w = e4 - 0*e0;
+                let origin = ((e1 ^ e2) ^ e3) ^ e4;
+                // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
+                let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
 
-            let transformed = (~rotor * point) * rotor;
+                let transformed = (~rotor * point) * rotor;
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_rotor = point | (1 - (~rotor * rotor));
+                // without this it tries to return an extra scalar
+                let assume_normalised_rotor = point | (1 - (~rotor * rotor));
 
-            let result = transformed + assume_normalised_rotor;
+                let result = transformed + assume_normalised_rotor;
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
 
-        fn rotor_w(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - 0*e0;
-            let y = e2 - 0*e0;
-            let z = e3 - 0*e0;
-            let w = e4 - 1*e0;
-            let origin = ((e1 ^ e2) ^ e3) ^ e4;
-            // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
-            let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
+            fn rotor_y(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - 0*e0;
+                let y = e2 - 1*e0;
+                let z = e3 - 0*e0;
+                let w = e4 - 0*e0;
+                let origin = ((e1 ^ e2) ^ e3) ^ e4;
+                // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
+                let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
 
-            let transformed = (~rotor * point) * rotor;
+                let transformed = (~rotor * point) * rotor;
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_rotor = point | (1 - (~rotor * rotor));
+                // without this it tries to return an extra scalar
+                let assume_normalised_rotor = point | (1 - (~rotor * rotor));
 
-            let result = transformed + assume_normalised_rotor;
+                let result = transformed + assume_normalised_rotor;
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
 
-        group PgaVector      = e0 + e1 + e2 + e3 + e4;
-        group PgaBivector    = PgaVector ^ PgaVector;
-        group PgaTrivector   = PgaVector ^ PgaBivector;
-        group PgaQuadvector  = PgaVector ^ PgaTrivector;
-        group PgaPentavector = PgaVector ^ PgaQuadvector;
+            fn rotor_z(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - 0*e0;
+                let y = e2 - 0*e0;
+                let z = e3 - 1*e0;
+                let w = e4 - 0*e0;
+                let origin = ((e1 ^ e2) ^ e3) ^ e4;
+                // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
+                let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
 
-        group #[derive(Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Transform = Scalar + PgaBivector + PgaQuadvector;
+                let transformed = (~rotor * point) * rotor;
 
-        group TransformSquaredMagnitude = Scalar + PgaQuadvector;
-        fn transform_squared_magnitude(transform: Transform) -> TransformSquaredMagnitude {
-            return ~transform * transform;
-        }
+                // without this it tries to return an extra scalar
+                let assume_normalised_rotor = point | (1 - (~rotor * rotor));
 
-        fn transform_then(a: Transform, b: Transform) -> Transform {
-            return b * a;
-        }
+                let result = transformed + assume_normalised_rotor;
 
-        fn transform_reverse(transform: Transform) -> Transform {
-            return ~transform;
-        }
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
 
-        fn transform_point(transform: Transform, x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - x*e0;
-            let y = e2 - y*e0;
-            let z = e3 - z*e0;
-            let w = e4 - w*e0;
-            let point = ((x ^ y) ^ z) ^ w;
+            fn rotor_w(rotor: Rotor) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - 0*e0;
+                let y = e2 - 0*e0;
+                let z = e3 - 0*e0;
+                let w = e4 - 1*e0;
+                let origin = ((e1 ^ e2) ^ e3) ^ e4;
+                // join the point to the origin to make a line, then get the lines intersection with the hyperplane at infinity
+                let point = (origin & (((x ^ y) ^ z) ^ w)) ^ e0;
 
-            let transformed = (~transform * point) * transform;
+                let transformed = (~rotor * point) * rotor;
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_transform = point | (1 - (~transform * transform));
+                // without this it tries to return an extra scalar
+                let assume_normalised_rotor = point | (1 - (~rotor * rotor));
 
-            let result = transformed + assume_normalised_transform;
+                let result = transformed + assume_normalised_rotor;
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
 
-        fn transform_position(transform: Transform) -> [Scalar, Scalar, Scalar, Scalar] {
-            let x = e1 - 0*e0;
-            let y = e2 - 0*e0;
-            let z = e3 - 0*e0;
-            let w = e4 - 0*e0;
-            let point = ((x ^ y) ^ z) ^ w;
+            group PgaVector      = e0 + e1 + e2 + e3 + e4;
+            group PgaBivector    = PgaVector ^ PgaVector;
+            group PgaTrivector   = PgaVector ^ PgaBivector;
+            group PgaQuadvector  = PgaVector ^ PgaTrivector;
+            group PgaPentavector = PgaVector ^ PgaQuadvector;
 
-            let transformed = (~transform * point) * transform;
+            group #[derive(PartialEq, Zeroable, Pod, Serialize, Deserialize)] #[repr(C)] Transform = Scalar + PgaBivector + PgaQuadvector;
 
-            // without this it tries to return an extra scalar
-            let assume_normalised_transform = point | (1 - (~transform * transform));
+            group TransformSquaredMagnitude = Scalar + PgaQuadvector;
+            fn transform_squared_magnitude(transform: Transform) -> TransformSquaredMagnitude {
+                return ~transform * transform;
+            }
 
-            let result = transformed + assume_normalised_transform;
+            fn transform_scale(transform: Transform, factor: TransformSquaredMagnitude) -> Transform {
+                return transform * factor;
+            }
 
-            return [
-                result & e1,
-                result & e2,
-                result & e3,
-                result & e4,
-            ];
-        }
-    }
+            // the e0 part of exp(bivector + e0 * ideal), where rotor = exp(bivector)
+            // and a, b, c are the coefficients from `ideal_exp_coefficients`
+            fn transform_exp(rotor: Rotor, bivector: VgaBivector, ideal: VgaVector, a: Scalar, b: Scalar, c: Scalar) -> Transform {
+                let symmetric = (bivector * ideal) + (ideal * bivector);
+                let sandwich = (bivector * ideal) * bivector;
+                let integral = a * ideal + b * symmetric + c * sandwich;
+                return rotor + (e0 * integral);
+            }
 
-    impl Rotor {
-        #[inline]
-        pub fn identity() -> Self {
-            Self {
-                s: 1.0,
-                ..Self::zero()
+            fn transform_remove_rotor(transform: Transform, rotor: Rotor) -> Transform {
+                return transform * ~rotor;
             }
-        }
 
-        #[inline]
-        pub fn rotate_xy(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e1e2: sin,
-                ..Self::zero()
+            fn pga_bivector_scale(bivector: PgaBivector, factor: Scalar) -> PgaBivector {
+                return bivector * factor;
             }
-        }
 
-        #[inline]
-        pub fn rotate_xz(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e1e3: sin,
-                ..Self::zero()
+            fn transform_then(a: Transform, b: Transform) -> Transform {
+                return b * a;
             }
-        }
 
-        #[inline]
-        pub fn rotate_xw(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e1e4: sin,
-                ..Self::zero()
+            fn transform_reverse(transform: Transform) -> Transform {
+                return ~transform;
+            }
+
+            fn transform_point(transform: Transform, x: Scalar, y: Scalar, z: Scalar, w: Scalar) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - x*e0;
+                let y = e2 - y*e0;
+                let z = e3 - z*e0;
+                let w = e4 - w*e0;
+                let point = ((x ^ y) ^ z) ^ w;
+
+                let transformed = (~transform * point) * transform;
+
+                // without this it tries to return an extra scalar
+                let assume_normalised_transform = point | (1 - (~transform * transform));
+
+                let result = transformed + assume_normalised_transform;
+
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
+            }
+
+            fn transform_plane(transform: Transform, plane: PgaVector) -> PgaVector {
+                // the pentavector part is only zero for normalised transforms
+                return grade_part(1, (~transform * plane) * transform);
+            }
+
+            fn transform_position(transform: Transform) -> [Scalar, Scalar, Scalar, Scalar] {
+                let x = e1 - 0*e0;
+                let y = e2 - 0*e0;
+                let z = e3 - 0*e0;
+                let w = e4 - 0*e0;
+                let point = ((x ^ y) ^ z) ^ w;
+
+                let transformed = (~transform * point) * transform;
+
+                // without this it tries to return an extra scalar
+                let assume_normalised_transform = point | (1 - (~transform * transform));
+
+                let result = transformed + assume_normalised_transform;
+
+                return [
+                    result & e1,
+                    result & e2,
+                    result & e3,
+                    result & e4,
+                ];
             }
         }
 
-        #[inline]
-        pub fn rotate_yz(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e2e3: sin,
-                ..Self::zero()
+        /// Computes `(s + e1e2e3e4)^(-1/2)`.
+        ///
+        /// `e1e2e3e4` squares to 1, so `(1 + e1e2e3e4) / 2` and `(1 - e1e2e3e4) / 2` split the value into
+        /// two independent real parts whose inverse square roots can be taken separately.
+        fn inverse_sqrt(
+            RotorSquaredMagnitude { s, e1e2e3e4 }: RotorSquaredMagnitude,
+        ) -> RotorSquaredMagnitude {
+            let positive = (s + e1e2e3e4).sqrt().recip();
+            let negative = (s - e1e2e3e4).sqrt().recip();
+            RotorSquaredMagnitude {
+                s: (positive + negative) * 0.5,
+                e1e2e3e4: (positive - negative) * 0.5,
             }
         }
 
-        #[inline]
-        pub fn rotate_yw(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e2e4: sin,
-                ..Self::zero()
+        /// Computes `(s + e1e2e3e4)^-1`, which is `(s - e1e2e3e4) / (s^2 - e1e2e3e4^2)` because
+        /// `e1e2e3e4` squares to 1.
+        fn inverse(
+            RotorSquaredMagnitude { s, e1e2e3e4 }: RotorSquaredMagnitude,
+        ) -> RotorSquaredMagnitude {
+            let determinant = (s * s - e1e2e3e4 * e1e2e3e4).recip();
+            RotorSquaredMagnitude {
+                s: s * determinant,
+                e1e2e3e4: -e1e2e3e4 * determinant,
             }
         }
 
-        #[inline]
-        pub fn rotate_zw(angle: f32) -> Self {
-            let (sin, cos) = (angle * 0.5).sin_cos();
-            Self {
-                s: cos,
-                e3e4: sin,
-                ..Self::zero()
+        /// `sin(x) / x`, falling back to its first order expansion when `x` is close to 0.
+        fn sinc(x: f64) -> f64 {
+            if x.abs() < 1e-4 {
+                1.0 - x * x / 6.0
+            } else {
+                x.sin() / x
             }
         }
 
-        #[inline]
-        pub fn then(self, then: Self) -> Self {
-            rotor_then(self, then)
+        /// The angles of the two halves of `bivector` split by `(1 + e1e2e3e4) / 2` and
+        /// `(1 - e1e2e3e4) / 2`.
+        ///
+        /// Each half squares to minus its angle squared, so it exponentiates like a quaternion. A simple
+        /// rotation has two equal angles, an isoclinic one has one of them be 0. This is `bivector *
+        /// bivector` written out in `f64`, so that the angle of a simple rotation comes out exact.
+        fn bivector_angles(
+            VgaBivector {
+                e1e2,
+                e1e3,
+                e1e4,
+                e2e3,
+                e2e4,
+                e3e4,
+            }: VgaBivector,
+        ) -> (f64, f64) {
+            let [e1e2, e1e3, e1e4, e2e3, e2e4, e3e4] =
+                [e1e2, e1e3, e1e4, e2e3, e2e4, e3e4].map(f64::from);
+            let s = e1e2 * e1e2 + e1e3 * e1e3 + e1e4 * e1e4 + e2e3 * e2e3 + e2e4 * e2e4 + e3e4 * e3e4;
+            let e1e2e3e4 = 2.0 * (e1e2 * e3e4 - e1e3 * e2e4 + e1e4 * e2e3);
+            (
+                (s - e1e2e3e4).max(0.0).sqrt(),
+                (s + e1e2e3e4).max(0.0).sqrt(),
+            )
         }
 
-        #[inline]
-        pub fn reverse(self) -> Self {
-            rotor_reverse(self)
+        /// Computes `bivector * (s + e1e2e3e4)` in `f64`.
+        fn bivector_scale(
+            VgaBivector {
+                e1e2,
+                e1e3,
+                e1e4,
+                e2e3,
+                e2e4,
+                e3e4,
+            }: VgaBivector,
+            s: f64,
+            e1e2e3e4: f64,
+        ) -> VgaBivector {
+            let [e1e2, e1e3, e1e4, e2e3, e2e4, e3e4] =
+                [e1e2, e1e3, e1e4, e2e3, e2e4, e3e4].map(f64::from);
+            VgaBivector {
+                e1e2: (s * e1e2 - e1e2e3e4 * e3e4) as $t,
+                e1e3: (s * e1e3 + e1e2e3e4 * e2e4) as $t,
+                e1e4: (s * e1e4 - e1e2e3e4 * e2e3) as $t,
+                e2e3: (s * e2e3 - e1e2e3e4 * e1e4) as $t,
+                e2e4: (s * e2e4 + e1e2e3e4 * e1e3) as $t,
+                e3e4: (s * e3e4 - e1e2e3e4 * e1e2) as $t,
+            }
         }
 
-        #[inline]
-        pub fn transform_direction(self, direction: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                rotate_direction(
+        /// Coefficients of `a * t + b * (B * t + t * B) + c * B * t * B`, which is the integral from 0 to
+        /// 1 of `exp(x * B) * t * exp((1 - x) * B)` for a vector `t`, and so the first order change in
+        /// `exp(B)` when `e0 * t` is added to `B`.
+        ///
+        /// Everything containing `e0` squares to 0, so for a motor that change is exact.
+        fn ideal_exp_coefficients(bivector: VgaBivector) -> (Scalar, Scalar, Scalar) {
+            let (positive, negative) = bivector_angles(bivector);
+            let half_sum = (positive + negative) * 0.5;
+            let half_difference = (positive - negative) * 0.5;
+
+            let a =
+                (half_sum.cos() * sinc(half_difference) + half_difference.cos() * sinc(half_sum)) * 0.5;
+            let b = sinc(half_sum) * sinc(half_difference) * 0.5;
+
+            // (sinc(negative) - sinc(positive)) / (positive^2 - negative^2), which needs the derivative
+            // of sinc with respect to the squared angle when the angles are equal
+            let difference = positive * positive - negative * negative;
+            let c = if difference.abs() > 1e-4 {
+                (sinc(negative) - sinc(positive)) / difference
+            } else {
+                let squared = (positive * positive + negative * negative) * 0.5;
+                let angle = squared.sqrt();
+                if angle < 1e-2 {
+                    1.0 / 6.0 - squared / 120.0
+                } else {
+                    (angle.sin() - angle * angle.cos()) / (2.0 * squared * angle)
+                }
+            };
+
+            (
+                Scalar { s: a as $t },
+                Scalar { s: b as $t },
+                Scalar { s: c as $t },
+            )
+        }
+
+        impl VgaBivector {
+            #[inline]
+            pub fn scale(self, factor: $t) -> Self {
+                bivector_scale(self, factor.into(), 0.0)
+            }
+
+            /// The rotor `exp(self)`, which rotates by twice the angle of each plane of `self`.
+            pub fn exp(self) -> Rotor {
+                // the trigonometry is done in the element type like in `Rotor::rotate_xy`, so that a rotation in one
+                // of the basis planes comes out exactly the same
+                let cos_sinc = |angle: f64| {
+                    let (sin, cos) = (angle as $t).sin_cos();
+                    let sinc = if angle < 1e-4 {
+                        sinc(angle)
+                    } else {
+                        f64::from(sin) / angle
+                    };
+                    (f64::from(cos), sinc)
+                };
+                let (positive, negative) = bivector_angles(self);
+                let (positive_cos, positive_sinc) = cos_sinc(positive);
+                let (negative_cos, negative_sinc) = cos_sinc(negative);
+
+                let Self {
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                } = bivector_scale(
                     self,
-                    Scalar { s: direction.x },
-                    Scalar { s: direction.y },
-                    Scalar { s: direction.z },
-                    Scalar { s: direction.w },
+                    (positive_sinc + negative_sinc) * 0.5,
+                    (positive_sinc - negative_sinc) * 0.5,
                 );
-            cgmath::Vector4 { x, y, z, w }
+                Rotor {
+                    s: ((positive_cos + negative_cos) * 0.5) as $t,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4: ((positive_cos - negative_cos) * 0.5) as $t,
+                }
+            }
         }
 
-        #[inline]
-        pub fn x(self) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                rotor_x(self);
-            cgmath::Vector4 { x, y, z, w }
-        }
+        impl PgaBivector {
+            #[inline]
+            pub fn scale(self, factor: $t) -> Self {
+                pga_bivector_scale(self, Scalar { s: factor })
+            }
 
-        #[inline]
-        pub fn y(self) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                rotor_y(self);
-            cgmath::Vector4 { x, y, z, w }
+            /// The motor `exp(self)`, the screw motion generated by `self`.
+            pub fn exp(self) -> Transform {
+                let Self {
+                    e0e1,
+                    e0e2,
+                    e0e3,
+                    e0e4,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                } = self;
+                let bivector = VgaBivector {
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                };
+                let ideal = VgaVector {
+                    e1: e0e1,
+                    e2: e0e2,
+                    e3: e0e3,
+                    e4: e0e4,
+                };
+                let (a, b, c) = ideal_exp_coefficients(bivector);
+                transform_exp(bivector.exp(), bivector, ideal, a, b, c)
+            }
         }
 
-        #[inline]
-        pub fn z(self) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                rotor_z(self);
-            cgmath::Vector4 { x, y, z, w }
-        }
+        impl_approx!($t, Rotor, 8);
+        impl_approx!($t, Transform, 16);
 
-        #[inline]
-        pub fn w(self) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                rotor_w(self);
-            cgmath::Vector4 { x, y, z, w }
+        impl Default for Rotor {
+            #[inline]
+            fn default() -> Self {
+                Self::identity()
+            }
         }
-    }
 
-    impl Transform {
-        #[inline]
-        pub fn identity() -> Self {
-            Self {
-                s: 1.0,
-                ..Self::zero()
+        impl Rotor {
+            #[inline]
+            pub fn identity() -> Self {
+                Self {
+                    s: 1.0,
+                    ..Self::zero()
+                }
             }
-        }
 
-        #[inline]
-        pub fn translation(offset: cgmath::Vector4<f32>) -> Self {
-            Self {
-                s: 1.0,
-                e0e1: offset.x * 0.5,
-                e0e2: offset.y * 0.5,
-                e0e3: offset.z * 0.5,
-                e0e4: offset.w * 0.5,
-                ..Self::zero()
+            /// The components in the order they are laid out in memory, which is the order of the
+            /// fields: `s`, `e1e2`, `e1e3`, `e1e4`, `e2e3`, `e2e4`, `e3e4`, `e1e2e3e4`.
+            #[inline]
+            pub fn to_array(self) -> [$t; 8] {
+                let Self {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4,
+                } = self;
+                [s, e1e2, e1e3, e1e4, e2e3, e2e4, e3e4, e1e2e3e4]
             }
-        }
 
-        #[inline]
-        pub fn rotate_xy(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_xy(angle))
-        }
+            /// The inverse of [`Rotor::to_array`].
+            #[inline]
+            pub fn from_array(array: [$t; 8]) -> Self {
+                let [s, e1e2, e1e3, e1e4, e2e3, e2e4, e3e4, e1e2e3e4] = array;
+                Self {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4,
+                }
+            }
 
-        #[inline]
-        pub fn rotate_xz(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_xz(angle))
-        }
+            #[inline]
+            pub fn magnitude(self) -> $t {
+                rotor_squared_magnitude(self).s.sqrt()
+            }
 
-        #[inline]
-        pub fn rotate_xw(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_xw(angle))
-        }
+            /// Rescales the rotor so that `~rotor * rotor` is exactly 1.
+            ///
+            /// The squared magnitude of a 4D rotor has a quadvector part as well as a scalar part, which
+            /// is only zero for rotors that are already normalized. Both parts are divided out here, so the
+            /// result is a true unit rotor rather than one with just a unit scalar magnitude.
+            #[inline]
+            pub fn normalize(self) -> Self {
+                rotor_scale(self, inverse_sqrt(rotor_squared_magnitude(self)))
+            }
 
-        #[inline]
-        pub fn rotate_yz(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_yz(angle))
-        }
+            /// The rotor that undoes this one, which is only equal to [`Rotor::reverse`] when the rotor
+            /// is normalized.
+            #[inline]
+            pub fn inverse(self) -> Self {
+                let reverse = self.reverse();
+                rotor_scale(reverse, inverse(rotor_squared_magnitude(reverse)))
+            }
 
-        #[inline]
-        pub fn rotate_yw(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_yw(angle))
-        }
+            /// The rotor that rotates half as far as this one, so `rotor.sqrt().then(rotor.sqrt())`
+            /// is `rotor`.
+            ///
+            /// This is `(1 + rotor).normalize()`, unless one of the halves of the rotor (see
+            /// [`VgaBivector::exp`]) is close to a full turn, where that would lose all precision and
+            /// half of [`Rotor::log`] is exponentiated instead.
+            pub fn sqrt(self) -> Self {
+                let rotor = self.normalize();
+                if rotor.s + rotor.e1e2e3e4 > -0.9 && rotor.s - rotor.e1e2e3e4 > -0.9 {
+                    Self {
+                        s: rotor.s + 1.0,
+                        ..rotor
+                    }
+                    .normalize()
+                } else {
+                    rotor.log().scale(0.5).exp()
+                }
+            }
 
-        #[inline]
-        pub fn rotate_zw(angle: f32) -> Self {
-            Self::from_rotor(Rotor::rotate_zw(angle))
-        }
+            #[inline]
+            pub fn exp(bivector: VgaBivector) -> Self {
+                bivector.exp()
+            }
 
-        #[inline]
-        pub fn then(self, then: Self) -> Self {
-            transform_then(self, then)
+            /// The bivector whose [`VgaBivector::exp`] is this rotor, choosing the smallest angles.
+            ///
+            /// The magnitude of the rotor is ignored. For a rotor that is exactly a half turn in some
+            /// plane the plane can't be recovered, so the result is unspecified there.
+            pub fn log(self) -> VgaBivector {
+                let Self {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4,
+                } = self;
+                let bivector = VgaBivector {
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                };
+
+                // inverse of `sinc` for each half, see `bivector_angles`
+                let factor = |sin: f64, cos: f64| {
+                    if sin < f64::EPSILON {
+                        1.0
+                    } else {
+                        sin.atan2(cos) / sin
+                    }
+                };
+                let (positive, negative) = bivector_angles(bivector);
+                let positive = factor(positive, (s + e1e2e3e4).into());
+                let negative = factor(negative, (s - e1e2e3e4).into());
+
+                bivector_scale(
+                    bivector,
+                    (positive + negative) * 0.5,
+                    (positive - negative) * 0.5,
+                )
+            }
+
+            /// The shortest rotation taking the direction of `from` to the direction of `to`, neither of
+            /// which needs to be normalized.
+            ///
+            /// This is always a simple rotation in the plane containing both directions. When they point
+            /// in opposite directions any such plane works, so one is picked.
+            pub fn from_two_directions(from: cgmath::Vector4<$t>, to: cgmath::Vector4<$t>) -> Self {
+                let from = from.normalize();
+                let to = to.normalize();
+                let wedge = |a: cgmath::Vector4<$t>, b: cgmath::Vector4<$t>| VgaBivector {
+                    e1e2: a.x * b.y - a.y * b.x,
+                    e1e3: a.x * b.z - a.z * b.x,
+                    e1e4: a.x * b.w - a.w * b.x,
+                    e2e3: a.y * b.z - a.z * b.y,
+                    e2e4: a.y * b.w - a.w * b.y,
+                    e3e4: a.z * b.w - a.w * b.z,
+                };
+
+                let s = 1.0 + from.dot(to);
+                if s < 1e-6 {
+                    // rotate half a turn in the plane of `from` and the axis furthest from it
+                    let axis = [
+                        cgmath::Vector4::unit_x(),
+                        cgmath::Vector4::unit_y(),
+                        cgmath::Vector4::unit_z(),
+                        cgmath::Vector4::unit_w(),
+                    ]
+                    .into_iter()
+                    .min_by(|a, b| from.dot(*a).abs().total_cmp(&from.dot(*b).abs()))
+                    .unwrap();
+                    return Self::rotate_in_plane(wedge(from, axis), TAU * 0.5);
+                }
+
+                let VgaBivector {
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                } = wedge(from, to);
+                Self {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4: 0.0,
+                }
+                .normalize()
+            }
+
+            /// Rotates by `angle` in `plane`, which doesn't need to be normalized.
+            ///
+            /// For a plane like `e1e2 + e3e4` that is the sum of two orthogonal planes, the rotation is
+            /// split between them by their share of the magnitude.
+            #[inline]
+            pub fn rotate_in_plane(plane: VgaBivector, angle: $t) -> Self {
+                let (positive, negative) = bivector_angles(plane);
+                let magnitude = ((positive * positive + negative * negative) * 0.5).sqrt() as $t;
+                if magnitude == 0.0 {
+                    return Self::identity();
+                }
+                plane.scale(angle * 0.5 / magnitude).exp()
+            }
+
+            #[inline]
+            pub fn rotate_xy(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e1e2: sin,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_xz(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e1e3: sin,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_xw(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e1e4: sin,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_yz(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e2e3: sin,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_yw(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e2e4: sin,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_zw(angle: $t) -> Self {
+                let (sin, cos) = (angle * 0.5).sin_cos();
+                Self {
+                    s: cos,
+                    e3e4: sin,
+                    ..Self::zero()
+                }
+            }
+
+            /// Interpolates from `self` at `t = 0` to `other` at `t = 1`, taking the shorter of the two
+            /// ways around.
+            ///
+            /// This follows the geodesic between the two rotors, including for double rotations.
+            pub fn slerp(self, other: Self, t: $t) -> Self {
+                let Scalar { s: dot } = rotor_dot(self, other);
+                // when both ways around are the same length (within rounding) `other` is kept as is
+                let other = if dot < -$t::EPSILON {
+                    rotor_scale(
+                        other,
+                        RotorSquaredMagnitude {
+                            s: -1.0,
+                            e1e2e3e4: 0.0,
+                        },
+                    )
+                } else {
+                    other
+                };
+                self.then(self.reverse().then(other).log().scale(t).exp())
+            }
+
+            /// Blends the components of `self` and `other` linearly and normalizes the result, taking
+            /// the shorter of the two ways around.
+            ///
+            /// This is cheaper than [`Rotor::slerp`] and passes through the same rotors, but not at a
+            /// constant rate, which is fine for rotors that are close to each other.
+            pub fn nlerp(self, other: Self, t: $t) -> Self {
+                let Scalar { s: dot } = rotor_dot(self, other);
+                let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+                let a = bytemuck::cast::<_, [$t; 8]>(self);
+                let b = bytemuck::cast::<_, [$t; 8]>(other);
+                let blended: [$t; 8] = std::array::from_fn(|i| a[i] * (1.0 - t) + b[i] * sign * t);
+                bytemuck::cast::<_, Self>(blended).normalize()
+            }
+
+            /// The rotor that is the weighted average of `rotors`, which pulls towards each one by its
+            /// weight.
+            ///
+            /// Starting from the most heavily weighted rotor, the [`Rotor::log`]s of the rotations
+            /// from the current estimate to every rotor are averaged by weight and the estimate is
+            /// moved by that, until it stops moving. Averaging in the tangent space is approximate
+            /// since 4D rotations don't commute, but for two rotors it lands on [`Rotor::slerp`]. The
+            /// identity is returned when there are no rotors or the weights don't add up to more than
+            /// 0.
+            pub fn weighted_average(rotors: &[(Self, $t)]) -> Self {
+                const MAX_ITERATIONS: usize = 32;
+
+                let total_weight = rotors.iter().map(|&(_, weight)| f64::from(weight)).sum::<f64>();
+                let Some(&(mut average, _)) =
+                    rotors.iter().max_by(|(_, a), (_, b)| a.total_cmp(b))
+                else {
+                    return Self::identity();
+                };
+                if total_weight.is_nan() || total_weight <= 0.0 {
+                    return Self::identity();
+                }
+                average = average.normalize();
+
+                for _ in 0..MAX_ITERATIONS {
+                    let mut step = [0.0f64; 6];
+                    for &(rotor, weight) in rotors {
+                        let mut offset = average.reverse().then(rotor.normalize());
+                        // `-offset` is the same rotation, the log of whichever is the shorter way around
+                        // is used
+                        if offset.s < 0.0 {
+                            offset = rotor_scale(
+                                offset,
+                                RotorSquaredMagnitude {
+                                    s: -1.0,
+                                    e1e2e3e4: 0.0,
+                                },
+                            );
+                        }
+                        let VgaBivector {
+                            e1e2,
+                            e1e3,
+                            e1e4,
+                            e2e3,
+                            e2e4,
+                            e3e4,
+                        } = offset.log();
+                        let weight = f64::from(weight) / total_weight;
+                        for (step, component) in
+                            step.iter_mut().zip([e1e2, e1e3, e1e4, e2e3, e2e4, e3e4])
+                        {
+                            *step += f64::from(component) * weight;
+                        }
+                    }
+
+                    let [e1e2, e1e3, e1e4, e2e3, e2e4, e3e4] = step.map(|x| x as $t);
+                    average = average
+                        .then(
+                            VgaBivector {
+                                e1e2,
+                                e1e3,
+                                e1e4,
+                                e2e3,
+                                e2e4,
+                                e3e4,
+                            }
+                            .exp(),
+                        )
+                        .normalize();
+
+                    if step.iter().map(|x| x * x).sum::<f64>().sqrt() < f64::from($t::EPSILON) {
+                        break;
+                    }
+                }
+                average
+            }
+
+            #[inline]
+            pub fn then(self, then: Self) -> Self {
+                rotor_then(self, then)
+            }
+
+            #[inline]
+            pub fn reverse(self) -> Self {
+                rotor_reverse(self)
+            }
+
+            #[inline]
+            pub fn transform_direction(self, direction: cgmath::Vector4<$t>) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    rotate_direction(
+                        self,
+                        Scalar { s: direction.x },
+                        Scalar { s: direction.y },
+                        Scalar { s: direction.z },
+                        Scalar { s: direction.w },
+                    );
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            /// The rotation matrix whose columns are [`Rotor::x`], [`Rotor::y`], [`Rotor::z`] and
+            /// [`Rotor::w`], which the camera uses as forward, up, right and ana.
+            #[inline]
+            pub fn to_matrix4(self) -> cgmath::Matrix4<$t> {
+                cgmath::Matrix4::from_cols(self.x(), self.y(), self.z(), self.w())
+            }
+
+            #[inline]
+            pub fn x(self) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    rotor_x(self);
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            #[inline]
+            pub fn y(self) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    rotor_y(self);
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            #[inline]
+            pub fn z(self) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    rotor_z(self);
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            #[inline]
+            pub fn w(self) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    rotor_w(self);
+                cgmath::Vector4 { x, y, z, w }
+            }
         }
 
-        #[inline]
-        pub fn reverse(self) -> Self {
-            transform_reverse(self)
+        impl Default for Transform {
+            #[inline]
+            fn default() -> Self {
+                Self::identity()
+            }
         }
 
-        #[inline]
-        pub fn transform_point(self, point: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                transform_point(
+        impl Transform {
+            #[inline]
+            pub fn identity() -> Self {
+                Self {
+                    s: 1.0,
+                    ..Self::zero()
+                }
+            }
+
+            /// The components in the order they are laid out in memory, which is the order of the
+            /// fields: `s`, `e0e1`, `e0e2`, `e0e3`, `e0e4`, `e1e2`, `e1e3`, `e1e4`, `e2e3`, `e2e4`,
+            /// `e3e4`, `e0e1e2e3`, `e0e1e2e4`, `e0e1e3e4`, `e0e2e3e4`, `e1e2e3e4`.
+            #[inline]
+            pub fn to_array(self) -> [$t; 16] {
+                let Self {
+                    s,
+                    e0e1,
+                    e0e2,
+                    e0e3,
+                    e0e4,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    e1e2e3e4,
+                } = self;
+                [
+                    s,
+                    e0e1,
+                    e0e2,
+                    e0e3,
+                    e0e4,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    e1e2e3e4,
+                ]
+            }
+
+            /// The inverse of [`Transform::to_array`].
+            #[inline]
+            pub fn from_array(array: [$t; 16]) -> Self {
+                let [
+                    s,
+                    e0e1,
+                    e0e2,
+                    e0e3,
+                    e0e4,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    e1e2e3e4,
+                ] = array;
+                Self {
+                    s,
+                    e0e1,
+                    e0e2,
+                    e0e3,
+                    e0e4,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    e1e2e3e4,
+                }
+            }
+
+            #[inline]
+            pub fn magnitude(self) -> $t {
+                transform_squared_magnitude(self).s.sqrt()
+            }
+
+            /// Rescales the transform so that `~transform * transform` is exactly 1.
+            ///
+            /// First the scalar and `e1e2e3e4` parts of the squared magnitude are divided out like in
+            /// [`Rotor::normalize`], then the remaining quadvector terms containing `e0` are removed by
+            /// multiplying with `1 - n / 2`, which is exact because they square to zero.
+            #[inline]
+            pub fn normalize(self) -> Self {
+                let TransformSquaredMagnitude { s, e1e2e3e4, .. } = transform_squared_magnitude(self);
+                let RotorSquaredMagnitude { s, e1e2e3e4 } =
+                    inverse_sqrt(RotorSquaredMagnitude { s, e1e2e3e4 });
+                let transform = transform_scale(
                     self,
-                    Scalar { s: point.x },
-                    Scalar { s: point.y },
-                    Scalar { s: point.z },
-                    Scalar { s: point.w },
+                    TransformSquaredMagnitude {
+                        s,
+                        e1e2e3e4,
+                        ..TransformSquaredMagnitude::zero()
+                    },
                 );
-            cgmath::Vector4 { x, y, z, w }
-        }
 
-        #[inline]
-        pub fn transform_direction(self, direction: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
-            self.rotor_part().transform_direction(direction)
-        }
+                let TransformSquaredMagnitude {
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    ..
+                } = transform_squared_magnitude(transform);
+                transform_scale(
+                    transform,
+                    TransformSquaredMagnitude {
+                        s: 1.0,
+                        e0e1e2e3: e0e1e2e3 * -0.5,
+                        e0e1e2e4: e0e1e2e4 * -0.5,
+                        e0e1e3e4: e0e1e3e4 * -0.5,
+                        e0e2e3e4: e0e2e3e4 * -0.5,
+                        e1e2e3e4: 0.0,
+                    },
+                )
+            }
 
-        #[inline]
-        pub fn position(self) -> cgmath::Vector4<f32> {
-            let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
-                transform_position(self);
-            cgmath::Vector4 { x, y, z, w }
-        }
+            /// The transform that undoes this one, which is only equal to [`Transform::reverse`] when the
+            /// transform is normalized.
+            ///
+            /// The `e0` quadvector terms of the squared magnitude anticommute with `e1e2e3e4` and square to
+            /// 0, so they are inverted by negating them like the `e1e2e3e4` term.
+            #[inline]
+            pub fn inverse(self) -> Self {
+                let reverse = self.reverse();
+                let TransformSquaredMagnitude {
+                    s,
+                    e0e1e2e3,
+                    e0e1e2e4,
+                    e0e1e3e4,
+                    e0e2e3e4,
+                    e1e2e3e4,
+                } = transform_squared_magnitude(reverse);
+                let determinant = (s * s - e1e2e3e4 * e1e2e3e4).recip();
+                transform_scale(
+                    reverse,
+                    TransformSquaredMagnitude {
+                        s: s * determinant,
+                        e0e1e2e3: -e0e1e2e3 * determinant,
+                        e0e1e2e4: -e0e1e2e4 * determinant,
+                        e0e1e3e4: -e0e1e3e4 * determinant,
+                        e0e2e3e4: -e0e2e3e4 * determinant,
+                        e1e2e3e4: -e1e2e3e4 * determinant,
+                    },
+                )
+            }
 
-        #[inline]
-        pub fn x(self) -> cgmath::Vector4<f32> {
-            self.rotor_part().x()
-        }
+            #[inline]
+            pub fn exp(bivector: PgaBivector) -> Self {
+                bivector.exp()
+            }
 
-        #[inline]
-        pub fn y(self) -> cgmath::Vector4<f32> {
-            self.rotor_part().y()
+            /// The bivector whose [`PgaBivector::exp`] is this transform, see [`Rotor::log`].
+            pub fn log(self) -> PgaBivector {
+                let transform = self.normalize();
+                let bivector = transform.rotor_part().log();
+                let rotor = bivector.exp();
+                let (a, b, c) = ideal_exp_coefficients(bivector);
+
+                // with the rotation taken off, the e0 part is a linear function of the e0 part of the
+                // bivector, so solve for it
+                let remaining = |transform: Transform| {
+                    let Self {
+                        e0e1,
+                        e0e2,
+                        e0e3,
+                        e0e4,
+                        ..
+                    } = transform_remove_rotor(transform, rotor);
+                    cgmath::Vector4 {
+                        x: e0e1,
+                        y: e0e2,
+                        z: e0e3,
+                        w: e0e4,
+                    }
+                };
+                let column = |ideal: cgmath::Vector4<$t>| {
+                    remaining(transform_exp(
+                        rotor,
+                        bivector,
+                        VgaVector {
+                            e1: ideal.x,
+                            e2: ideal.y,
+                            e3: ideal.z,
+                            e4: ideal.w,
+                        },
+                        a,
+                        b,
+                        c,
+                    ))
+                };
+                let matrix = cgmath::Matrix4::from_cols(
+                    column(cgmath::Vector4::unit_x()),
+                    column(cgmath::Vector4::unit_y()),
+                    column(cgmath::Vector4::unit_z()),
+                    column(cgmath::Vector4::unit_w()),
+                );
+                let target = remaining(transform);
+                let ideal = matrix.invert().map_or(target, |inverse| inverse * target);
+
+                let VgaBivector {
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                } = bivector;
+                PgaBivector {
+                    e0e1: ideal.x,
+                    e0e2: ideal.y,
+                    e0e3: ideal.z,
+                    e0e4: ideal.w,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                }
+            }
+
+            #[inline]
+            pub fn translation(offset: cgmath::Vector4<$t>) -> Self {
+                Self {
+                    s: 1.0,
+                    e0e1: offset.x * 0.5,
+                    e0e2: offset.y * 0.5,
+                    e0e3: offset.z * 0.5,
+                    e0e4: offset.w * 0.5,
+                    ..Self::zero()
+                }
+            }
+
+            #[inline]
+            pub fn rotate_xy(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_xy(angle))
+            }
+
+            #[inline]
+            pub fn rotate_xz(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_xz(angle))
+            }
+
+            #[inline]
+            pub fn rotate_xw(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_xw(angle))
+            }
+
+            #[inline]
+            pub fn rotate_yz(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_yz(angle))
+            }
+
+            #[inline]
+            pub fn rotate_yw(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_yw(angle))
+            }
+
+            #[inline]
+            pub fn rotate_zw(angle: $t) -> Self {
+                Self::from_rotor(Rotor::rotate_zw(angle))
+            }
+
+            #[inline]
+            pub fn then(self, then: Self) -> Self {
+                transform_then(self, then)
+            }
+
+            #[inline]
+            pub fn reverse(self) -> Self {
+                transform_reverse(self)
+            }
+
+            #[inline]
+            pub fn transform_point(self, point: cgmath::Vector4<$t>) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    transform_point(
+                        self,
+                        Scalar { s: point.x },
+                        Scalar { s: point.y },
+                        Scalar { s: point.z },
+                        Scalar { s: point.w },
+                    );
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            #[inline]
+            pub fn transform_direction(self, direction: cgmath::Vector4<$t>) -> cgmath::Vector4<$t> {
+                self.rotor_part().transform_direction(direction)
+            }
+
+            /// Transforms a ray, returning its origin transformed as a point and its direction as a
+            /// direction. The rotation is turned into a matrix once and used for both, rather than
+            /// working out the sandwich product twice with [`Transform::transform_point`] and
+            /// [`Transform::transform_direction`].
+            #[inline]
+            pub fn transform_ray(
+                self,
+                origin: cgmath::Vector4<$t>,
+                direction: cgmath::Vector4<$t>,
+            ) -> (cgmath::Vector4<$t>, cgmath::Vector4<$t>) {
+                let rotation = self.rotor_part().to_matrix4();
+                (rotation * origin + self.position(), rotation * direction)
+            }
+
+            /// Transforms each of `points` into the same index of `out`. The transform is turned into
+            /// a matrix once up front, which is much cheaper per point than calling
+            /// [`Transform::transform_point`] in a loop.
+            ///
+            /// Panics if the slices have different lengths.
+            pub fn transform_points(
+                self,
+                points: &[cgmath::Vector4<$t>],
+                out: &mut [cgmath::Vector4<$t>],
+            ) {
+                assert_eq!(points.len(), out.len());
+                let rotation = self.rotor_part().to_matrix4();
+                let position = self.position();
+                for (point, out) in points.iter().zip(out) {
+                    *out = rotation * point + position;
+                }
+            }
+
+            /// Like [`Transform::transform_points`], but for directions, which ignore the translation.
+            pub fn transform_directions(
+                self,
+                directions: &[cgmath::Vector4<$t>],
+                out: &mut [cgmath::Vector4<$t>],
+            ) {
+                assert_eq!(directions.len(), out.len());
+                let rotation = self.rotor_part().to_matrix4();
+                for (direction, out) in directions.iter().zip(out) {
+                    *out = rotation * direction;
+                }
+            }
+
+            /// Moves the hyperplane `normal . p + offset = 0`, which is the PGA vector
+            /// `offset * e0 + normal.x * e1 + normal.y * e2 + normal.z * e3 + normal.w * e4`, returning
+            /// the new normal and offset.
+            #[inline]
+            pub fn transform_plane(
+                self,
+                normal: cgmath::Vector4<$t>,
+                offset: $t,
+            ) -> (cgmath::Vector4<$t>, $t) {
+                let PgaVector { e0, e1, e2, e3, e4 } = transform_plane(
+                    self,
+                    PgaVector {
+                        e0: offset,
+                        e1: normal.x,
+                        e2: normal.y,
+                        e3: normal.z,
+                        e4: normal.w,
+                    },
+                );
+                (
+                    cgmath::Vector4 {
+                        x: e1,
+                        y: e2,
+                        z: e3,
+                        w: e4,
+                    },
+                    e0,
+                )
+            }
+
+            #[inline]
+            pub fn position(self) -> cgmath::Vector4<$t> {
+                let (Scalar { s: x }, Scalar { s: y }, Scalar { s: z }, Scalar { s: w }) =
+                    transform_position(self);
+                cgmath::Vector4 { x, y, z, w }
+            }
+
+            /// The 5x5 homogeneous matrix of this transform, stored as columns like cgmath, where the
+            /// last component of a point is 1 and the last column is [`Transform::position`].
+            pub fn to_matrix5(self) -> [[$t; 5]; 5] {
+                let column = |v: cgmath::Vector4<$t>, w: $t| [v.x, v.y, v.z, v.w, w];
+                [
+                    column(self.x(), 0.0),
+                    column(self.y(), 0.0),
+                    column(self.z(), 0.0),
+                    column(self.w(), 0.0),
+                    column(self.position(), 1.0),
+                ]
+            }
+
+            #[inline]
+            pub fn x(self) -> cgmath::Vector4<$t> {
+                self.rotor_part().x()
+            }
+
+            #[inline]
+            pub fn y(self) -> cgmath::Vector4<$t> {
+                self.rotor_part().y()
+            }
+
+            #[inline]
+            pub fn z(self) -> cgmath::Vector4<$t> {
+                self.rotor_part().z()
+            }
+
+            #[inline]
+            pub fn w(self) -> cgmath::Vector4<$t> {
+                self.rotor_part().w()
+            }
+
+            #[inline]
+            pub fn from_rotor(rotor: Rotor) -> Self {
+                let Rotor {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4,
+                } = rotor;
+                Self {
+                    s,
+                    e0e1: 0.0,
+                    e0e2: 0.0,
+                    e0e3: 0.0,
+                    e0e4: 0.0,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3: 0.0,
+                    e0e1e2e4: 0.0,
+                    e0e1e3e4: 0.0,
+                    e0e2e3e4: 0.0,
+                    e1e2e3e4,
+                }
+            }
+
+            #[inline]
+            pub fn rotor_part(self) -> Rotor {
+                let Self {
+                    s,
+                    e0e1: _,
+                    e0e2: _,
+                    e0e3: _,
+                    e0e4: _,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e0e1e2e3: _,
+                    e0e1e2e4: _,
+                    e0e1e3e4: _,
+                    e0e2e3e4: _,
+                    e1e2e3e4,
+                } = self;
+                Rotor {
+                    s,
+                    e1e2,
+                    e1e3,
+                    e1e4,
+                    e2e3,
+                    e2e4,
+                    e3e4,
+                    e1e2e3e4,
+                }
+            }
         }
+    };
+}
+
+pub mod f32 {
+    impls!(f32);
+}
+
+pub mod f64 {
+    impls!(f64);
 
+    impl From<super::f32::Rotor> for Rotor {
         #[inline]
-        pub fn z(self) -> cgmath::Vector4<f32> {
-            self.rotor_part().z()
+        fn from(rotor: super::f32::Rotor) -> Self {
+            bytemuck::cast(bytemuck::cast::<_, [f32; 8]>(rotor).map(f64::from))
         }
+    }
 
+    impl From<Rotor> for super::f32::Rotor {
         #[inline]
-        pub fn w(self) -> cgmath::Vector4<f32> {
-            self.rotor_part().w()
+        fn from(rotor: Rotor) -> Self {
+            bytemuck::cast(bytemuck::cast::<_, [f64; 8]>(rotor).map(|x| x as f32))
         }
+    }
 
+    impl From<super::f32::Transform> for Transform {
         #[inline]
-        pub fn from_rotor(rotor: Rotor) -> Self {
-            let Rotor {
-                s,
-                e1e2,
-                e1e3,
-                e1e4,
-                e2e3,
-                e2e4,
-                e3e4,
-                e1e2e3e4,
-            } = rotor;
-            Self {
-                s,
-                e0e1: 0.0,
-                e0e2: 0.0,
-                e0e3: 0.0,
-                e0e4: 0.0,
-                e1e2,
-                e1e3,
-                e1e4,
-                e2e3,
-                e2e4,
-                e3e4,
-                e0e1e2e3: 0.0,
-                e0e1e2e4: 0.0,
-                e0e1e3e4: 0.0,
-                e0e2e3e4: 0.0,
-                e1e2e3e4,
-            }
+        fn from(transform: super::f32::Transform) -> Self {
+            bytemuck::cast(bytemuck::cast::<_, [f32; 16]>(transform).map(f64::from))
         }
+    }
 
+    impl From<Transform> for super::f32::Transform {
         #[inline]
-        pub fn rotor_part(self) -> Rotor {
-            let Self {
-                s,
-                e0e1: _,
-                e0e2: _,
-                e0e3: _,
-                e0e4: _,
-                e1e2,
-                e1e3,
-                e1e4,
-                e2e3,
-                e2e4,
-                e3e4,
-                e0e1e2e3: _,
-                e0e1e2e4: _,
-                e0e1e3e4: _,
-                e0e2e3e4: _,
-                e1e2e3e4,
-            } = self;
-            Rotor {
-                s,
-                e1e2,
-                e1e3,
-                e1e4,
-                e2e3,
-                e2e4,
-                e3e4,
-                e1e2e3e4,
-            }
+        fn from(transform: Transform) -> Self {
+            bytemuck::cast(bytemuck::cast::<_, [f64; 16]>(transform).map(|x| x as f32))
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,361 @@
+macro_rules! tests {
+    ($t:ident) => {
+        use crate::$t::{Rotor, Transform, VgaBivector};
+        use approx::{assert_abs_diff_eq, assert_ulps_eq};
+        use cgmath::InnerSpace;
+        use std::$t::consts::TAU;
+
+        const EPSILON: $t = 1e-5;
+
+        #[test]
+        fn rotor_array_matches_memory_layout() {
+            let rotor = Rotor {
+                s: 1.0,
+                e1e2: 2.0,
+                e1e3: 3.0,
+                e1e4: 4.0,
+                e2e3: 5.0,
+                e2e4: 6.0,
+                e3e4: 7.0,
+                e1e2e3e4: 8.0,
+            };
+            let array = rotor.to_array();
+            assert_eq!(array, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+            assert_eq!(bytemuck::bytes_of(&rotor), bytemuck::bytes_of(&array));
+            assert_eq!(Rotor::from_array(array), rotor);
+        }
+
+        #[test]
+        fn transform_array_matches_memory_layout() {
+            let transform = Transform {
+                s: 1.0,
+                e0e1: 2.0,
+                e0e2: 3.0,
+                e0e3: 4.0,
+                e0e4: 5.0,
+                e1e2: 6.0,
+                e1e3: 7.0,
+                e1e4: 8.0,
+                e2e3: 9.0,
+                e2e4: 10.0,
+                e3e4: 11.0,
+                e0e1e2e3: 12.0,
+                e0e1e2e4: 13.0,
+                e0e1e3e4: 14.0,
+                e0e2e3e4: 15.0,
+                e1e2e3e4: 16.0,
+            };
+            let array = transform.to_array();
+            assert_eq!(array, std::array::from_fn(|i| (i + 1) as $t));
+            assert_eq!(bytemuck::bytes_of(&transform), bytemuck::bytes_of(&array));
+            assert_eq!(Transform::from_array(array), transform);
+        }
+
+        #[test]
+        fn slerp_halfway_in_a_basis_plane() {
+            let halfway = Rotor::identity().slerp(Rotor::rotate_xy(TAU / 2.0), 0.5);
+            assert_abs_diff_eq!(halfway, Rotor::rotate_xy(TAU / 4.0), epsilon = EPSILON);
+        }
+
+        #[test]
+        fn slerp_towards_an_isoclinic_rotor() {
+            let plane = VgaBivector {
+                e1e2: 1.0,
+                e3e4: 1.0,
+                ..VgaBivector::zero()
+            };
+            let target = Rotor::rotate_in_plane(plane, TAU / 3.0);
+            for t in [0.0, 0.25, 0.5, 0.75, 1.0] {
+                assert_abs_diff_eq!(
+                    Rotor::identity().slerp(target, t),
+                    Rotor::rotate_in_plane(plane, TAU / 3.0 * t),
+                    epsilon = EPSILON
+                );
+            }
+            let halfway = Rotor::identity().slerp(target, 0.5);
+            assert_abs_diff_eq!(halfway.then(halfway), target, epsilon = EPSILON);
+        }
+
+        #[test]
+        fn transform_then_inverse_is_identity() {
+            let transform = Transform::translation(cgmath::vec4(1.0, -2.0, 3.0, 0.5))
+                .then(Transform::rotate_xw(1.2));
+            assert_abs_diff_eq!(
+                transform.then(transform.inverse()),
+                Transform::identity(),
+                epsilon = EPSILON
+            );
+
+            // unlike the reverse, the inverse also undoes a transform that isn't normalized
+            let scaled = Transform::from_array(transform.to_array().map(|x| x * 2.0));
+            assert_abs_diff_eq!(
+                scaled.then(scaled.inverse()),
+                Transform::identity(),
+                epsilon = EPSILON
+            );
+        }
+
+        #[test]
+        fn rotate_in_a_basis_plane() {
+            let e1e2 = VgaBivector {
+                e1e2: 1.0,
+                ..VgaBivector::zero()
+            };
+            for angle in [0.0, 0.3, TAU / 4.0, 2.0, TAU / 2.0] {
+                assert_eq!(Rotor::rotate_in_plane(e1e2, angle), Rotor::rotate_xy(angle));
+                // the plane doesn't need to be normalized
+                assert_abs_diff_eq!(
+                    Rotor::rotate_in_plane(e1e2.scale(3.0), angle),
+                    Rotor::rotate_xy(angle),
+                    epsilon = EPSILON
+                );
+            }
+        }
+
+        #[test]
+        fn translated_ground_plane_keeps_its_normal() {
+            let up = cgmath::vec4(0.0, 1.0, 0.0, 0.0);
+            let offset = cgmath::vec4(1.0, 2.0, -3.0, 4.0);
+            let (normal, plane_offset) = Transform::translation(offset).transform_plane(up, 0.0);
+            assert_abs_diff_eq!(normal, up, epsilon = EPSILON);
+            // the plane `y = 0` moved to `y = 2`, which is `y - 2 = 0`
+            assert_abs_diff_eq!(plane_offset, -2.0, epsilon = EPSILON);
+
+            let (normal, plane_offset) = Transform::translation(offset).transform_plane(up, 1.5);
+            assert_abs_diff_eq!(normal, up, epsilon = EPSILON);
+            assert_abs_diff_eq!(plane_offset, -0.5, epsilon = EPSILON);
+        }
+
+        #[test]
+        fn sqrt_is_half_the_rotation() {
+            assert_abs_diff_eq!(
+                Rotor::rotate_xy(TAU / 4.0).sqrt(),
+                Rotor::rotate_xy(TAU / 8.0),
+                epsilon = EPSILON
+            );
+
+            // close to a full turn the rotor is close to -1, where the log is used instead
+            let rotor = Rotor::rotate_xy(TAU * 0.99).then(Rotor::rotate_zw(TAU * 0.02));
+            let sqrt = rotor.sqrt();
+            assert_abs_diff_eq!(sqrt.then(sqrt), rotor, epsilon = EPSILON);
+        }
+
+        #[test]
+        fn from_two_directions_maps_one_onto_the_other() {
+            let pairs = [
+                (
+                    cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+                    cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                ),
+                (
+                    cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+                    cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+                ),
+                // neither needs to be normalized
+                (
+                    cgmath::vec4(2.0, -1.0, 0.5, 3.0),
+                    cgmath::vec4(-0.3, 0.2, 4.0, 1.0),
+                ),
+                (
+                    cgmath::vec4(1.0, 1.0, 1.0, 1.0),
+                    cgmath::vec4(1.0, 1.0, 1.0, -0.9),
+                ),
+            ];
+            for (from, to) in pairs {
+                let rotor = Rotor::from_two_directions(from, to);
+                assert_abs_diff_eq!(rotor.magnitude(), 1.0, epsilon = EPSILON);
+                // a simple rotation has no e1e2e3e4 part
+                assert_abs_diff_eq!(rotor.e1e2e3e4, 0.0, epsilon = EPSILON);
+                assert_abs_diff_eq!(
+                    rotor.transform_direction(from.normalize()),
+                    to.normalize(),
+                    epsilon = EPSILON
+                );
+            }
+        }
+
+        #[test]
+        fn from_two_directions_edge_cases() {
+            let directions = [
+                cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+                cgmath::vec4(0.0, 0.0, 0.0, 2.0),
+                cgmath::vec4(0.3, -0.4, 1.2, 0.7),
+            ];
+            for direction in directions {
+                assert_abs_diff_eq!(
+                    Rotor::from_two_directions(direction, direction),
+                    Rotor::identity(),
+                    epsilon = EPSILON
+                );
+
+                // any plane containing both works for opposite directions, so only check the result
+                let rotor = Rotor::from_two_directions(direction, -direction);
+                assert_abs_diff_eq!(rotor.magnitude(), 1.0, epsilon = EPSILON);
+                assert_abs_diff_eq!(rotor.e1e2e3e4, 0.0, epsilon = EPSILON);
+                assert_abs_diff_eq!(
+                    rotor.transform_direction(direction),
+                    -direction,
+                    epsilon = EPSILON
+                );
+            }
+        }
+
+        #[test]
+        fn average_of_two_rotations_is_halfway() {
+            for angle in [0.5, 2.0, 3.0] {
+                let (from, to) = (Rotor::rotate_xy(0.0), Rotor::rotate_xy(angle));
+                let halfway = Rotor::rotate_xy(angle / 2.0);
+                assert_abs_diff_eq!(
+                    Rotor::weighted_average(&[(from, 1.0), (to, 1.0)]),
+                    halfway,
+                    epsilon = EPSILON
+                );
+                assert_abs_diff_eq!(from.nlerp(to, 0.5), halfway, epsilon = EPSILON);
+            }
+        }
+
+        /// A transform that moves and rotates in every plane, so no component is left out.
+        fn example_transform() -> Transform {
+            Transform::translation(cgmath::vec4(1.0, -2.0, 3.0, -4.0))
+                .then(Transform::rotate_xy(0.3))
+                .then(Transform::rotate_xz(-0.7))
+                .then(Transform::rotate_xw(1.1))
+                .then(Transform::rotate_yz(0.2))
+                .then(Transform::rotate_yw(-1.4))
+                .then(Transform::rotate_zw(0.9))
+        }
+
+        #[test]
+        fn exp_undoes_log() {
+            let offset = cgmath::vec4(1.0, -2.0, 3.0, -4.0);
+            let transforms = [
+                Transform::identity(),
+                Transform::translation(offset),
+                Transform::rotate_xy(0.3),
+                // a screw motion, translating along the plane orthogonal to the rotation
+                Transform::rotate_xy(1.1)
+                    .then(Transform::translation(cgmath::vec4(0.0, 0.0, 2.0, 0.5))),
+                Transform::translation(offset).then(Transform::rotate_xw(2.0)),
+                Transform::rotate_yz(0.4)
+                    .then(Transform::rotate_xw(-1.3))
+                    .then(Transform::translation(offset)),
+                example_transform(),
+            ];
+            for transform in transforms {
+                assert_abs_diff_eq!(
+                    Transform::exp(transform.log()),
+                    transform,
+                    epsilon = EPSILON
+                );
+            }
+        }
+
+        #[test]
+        fn transform_ray_matches_point_and_direction() {
+            let transform = example_transform();
+            let origin = cgmath::vec4(0.5, 1.5, -2.5, 3.5);
+            let direction = cgmath::vec4(0.1, -0.2, 0.3, 0.9);
+            let (ray_origin, ray_direction) = transform.transform_ray(origin, direction);
+            assert_abs_diff_eq!(
+                ray_origin,
+                transform.transform_point(origin),
+                epsilon = EPSILON
+            );
+            assert_abs_diff_eq!(
+                ray_direction,
+                transform.transform_direction(direction),
+                epsilon = EPSILON
+            );
+        }
+
+        #[test]
+        fn transform_points_matches_one_at_a_time() {
+            let transform = example_transform();
+            let points: Vec<_> = (0..64)
+                .map(|i| {
+                    let i = i as $t / 16.0;
+                    cgmath::vec4(i, -0.5 * i, (i * 0.3).sin(), (i * 0.7).cos())
+                })
+                .collect();
+
+            let mut out = vec![cgmath::vec4(0.0, 0.0, 0.0, 0.0); points.len()];
+            transform.transform_points(&points, &mut out);
+            for (&point, &out) in points.iter().zip(&out) {
+                assert_abs_diff_eq!(out, transform.transform_point(point), epsilon = EPSILON);
+            }
+
+            transform.transform_directions(&points, &mut out);
+            for (&direction, &out) in points.iter().zip(&out) {
+                assert_abs_diff_eq!(
+                    out,
+                    transform.transform_direction(direction),
+                    epsilon = EPSILON
+                );
+            }
+        }
+
+        #[test]
+        fn matrices_match_point_and_direction() {
+            let transform = example_transform();
+            let matrix = transform.to_matrix5();
+            let apply = |v: [$t; 5]| -> [$t; 5] {
+                std::array::from_fn(|row| {
+                    (0..5).map(|column| matrix[column][row] * v[column]).sum()
+                })
+            };
+
+            let point = cgmath::vec4(0.5, 1.5, -2.5, 3.5);
+            let [x, y, z, w, one] = apply([point.x, point.y, point.z, point.w, 1.0]);
+            assert_abs_diff_eq!(
+                cgmath::vec4(x, y, z, w),
+                transform.transform_point(point),
+                epsilon = EPSILON
+            );
+            assert_abs_diff_eq!(one, 1.0);
+
+            // directions have a last component of 0, so the translation doesn't touch them
+            let direction = cgmath::vec4(0.1, -0.2, 0.3, 0.9);
+            let [x, y, z, w, zero] =
+                apply([direction.x, direction.y, direction.z, direction.w, 0.0]);
+            assert_abs_diff_eq!(
+                cgmath::vec4(x, y, z, w),
+                transform.transform_direction(direction),
+                epsilon = EPSILON
+            );
+            assert_abs_diff_eq!(zero, 0.0);
+            assert_abs_diff_eq!(
+                transform.rotor_part().to_matrix4() * direction,
+                transform.transform_direction(direction),
+                epsilon = EPSILON
+            );
+        }
+
+        #[test]
+        fn serde_round_trip() {
+            let rotor = Rotor::rotate_xy(0.3).then(Rotor::rotate_zw(-1.2));
+            let json = serde_json::to_string(&rotor).unwrap();
+            // serde_json's default float parser can be off by an ulp for f64
+            assert_ulps_eq!(
+                serde_json::from_str::<Rotor>(&json).unwrap(),
+                rotor,
+                max_ulps = 1
+            );
+
+            let transform = example_transform();
+            let json = serde_json::to_string(&transform).unwrap();
+            assert_ulps_eq!(
+                serde_json::from_str::<Transform>(&json).unwrap(),
+                transform,
+                max_ulps = 1
+            );
+        }
+    };
+}
+
+mod f32 {
+    tests!(f32);
+}
+
+mod f64 {
+    tests!(f64);
+}
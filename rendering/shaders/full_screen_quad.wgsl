@@ -3,6 +3,20 @@ var texture: texture_2d<f32>;
 @group(0) @binding(1)
 var texture_sampler: sampler;
 
+struct ToneMapping {
+    mode: u32,
+    exposure: f32,
+}
+
+const TONE_MAPPING_NONE: u32 = 0u;
+const TONE_MAPPING_ACES: u32 = 1u;
+
+@group(1) @binding(0)
+var<uniform> tone_mapping: ToneMapping;
+
+// whether the colors need to be sRGB encoded by hand, which is not needed when the target format does it
+override ENCODE_SRGB: bool = true;
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
@@ -18,7 +32,25 @@ fn vertex(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return out;
 }
 
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+fn aces(x: vec3<f32>) -> vec3<f32> {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn srgb_encode(x: vec3<f32>) -> vec3<f32> {
+    return select(1.055 * pow(x, vec3<f32>(1.0 / 2.4)) - 0.055, 12.92 * x, x <= vec3<f32>(0.0031308));
+}
+
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(texture, texture_sampler, in.uv);
+    let color = textureSample(texture, texture_sampler, in.uv);
+    if tone_mapping.mode == TONE_MAPPING_NONE {
+        return color;
+    }
+
+    var mapped = aces(max(color.rgb, vec3<f32>(0.0)) * tone_mapping.exposure);
+    if ENCODE_SRGB {
+        mapped = srgb_encode(mapped);
+    }
+    return vec4<f32>(mapped, color.a);
 }
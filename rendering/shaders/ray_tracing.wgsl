@@ -3,25 +3,155 @@ struct Camera {
     forward: vec4<f32>,
     up: vec4<f32>,
     right: vec4<f32>,
+    // vertical field of view in radians
+    fov: f32,
+    projection: u32,
+    orthographic_height: f32,
+    // the range of distances along camera rays that objects are drawn in
+    near: f32,
+    // the direction that the edge of a stereographic view goes towards, in world space
+    pole: vec4<f32>,
+    far: f32,
+    // the bit of the view being rendered, objects without it in their views are left out
+    view: u32,
+    // the radius of the lens rays start from and how far in front of the camera they come back into focus,
+    // an aperture of 0 is a pinhole
+    aperture: f32,
+    focus_distance: f32,
 }
 
-var<push_constant> camera: Camera;
+const PROJECTION_PERSPECTIVE: u32 = 0u;
+const PROJECTION_ORTHOGRAPHIC: u32 = 1u;
+const PROJECTION_STEREOGRAPHIC: u32 = 2u;
+
+struct PushConstants {
+    camera: Camera,
+    // how many frames have already been summed into the accumulation buffer, 0 starts over
+    frame_index: u32,
+    accumulate: u32,
+    render_mode: u32,
+    // the w coordinate of the hyperplane the scene is cut by in slice mode
+    slice_w: f32,
+}
+
+const RENDER_MODE_PROJECT: u32 = 0u;
+const RENDER_MODE_SLICE: u32 = 1u;
+
+var<push_constant> push_constants: PushConstants;
 
 @group(0) @binding(0)
 var output_texture: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(1)
+var<storage, read_write> accumulation: array<vec4<f32>>;
 
 struct SceneInfo {
     hyperspheres_count: u32,
     hyperplanes_count: u32,
+    tesseracts_count: u32,
+    lights_count: u32,
+    headlamp_color: vec3<f32>,
+    headlamp_intensity: f32,
+    // how far shadow rays start off the surface along the normal, to avoid self shadowing
+    shadow_bias: f32,
+    max_bounces: u32,
+    // the rays for each pixel are shot in a samples_per_pixel x samples_per_pixel grid
+    samples_per_pixel: u32,
+    // when zero the hyperspheres are all tested one by one instead of walking the hierarchy
+    hypersphere_bvh_nodes_count: u32,
+    // the sky fades from sky_horizon to sky_zenith as rays point further along sky_direction
+    sky_direction: vec4<f32>,
+    sky_zenith: vec3<f32>,
+    // when non-zero the grid and axes from overlay are drawn over the scene
+    show_grid: u32,
+    sky_horizon: vec3<f32>,
+    tori_count: u32,
+    // how many steps a ray can march towards a torus before it is treated as a miss
+    torus_max_steps: u32,
+    // the object outlined by highlight_selection, the kind is NO_OBJECT when nothing is selected
+    selected_object_kind: u32,
+    selected_object_index: u32,
+    // how many shadow rays are spread over the surface of each emissive hypersphere, 1 traces a single ray to its center
+    shadow_samples: u32,
+    // the direction towards the sun, it doesn't need to be normalized
+    sun_direction: vec4<f32>,
+    sun_color: vec3<f32>,
+    sun_intensity: f32,
+    // surfaces further than fog_start fade exponentially towards fog_color, a density of 0 turns it off
+    fog_color: vec3<f32>,
+    fog_start: f32,
+    fog_density: f32,
+    spherinders_count: u32,
+    csg_pairs_count: u32,
+    // in projection mode surfaces are dimmed and desaturated the further they are from the camera in w, 0 turns it off
+    w_falloff: f32,
+    // when non-zero the sky is looked up in environment_texture instead of using the gradient
+    has_environment: u32,
 }
 
 @group(1) @binding(0)
 var<uniform> info: SceneInfo;
+@group(1) @binding(1)
+var environment_texture: texture_2d<f32>;
+
+struct Material {
+    color: vec3<f32>,
+    reflectivity: f32,
+    emission: vec3<f32>,
+    // below 1 part of the light continues through the surface, bent by the index of refraction
+    opacity: f32,
+    ior: f32,
+    pattern: u32,
+    pattern_scale: f32,
+    // the brightness and Blinn-Phong exponent of the highlights, which aren't tinted by the color
+    specular: f32,
+    shininess: f32,
+}
+
+const PATTERN_SOLID: u32 = 0u;
+const PATTERN_CHECKER: u32 = 1u;
+const PATTERN_GRID: u32 = 2u;
+// how much darker the dark cells of a checker pattern and the lines of a grid pattern are
+const PATTERN_SHADE: f32 = 0.5;
+// the width of the grid pattern's lines as a fraction of a cell
+const PATTERN_LINE_WIDTH: f32 = 0.05;
+
+// the material with its pattern applied at a point in the object's own space, so the pattern moves with it
+fn material_at(material: Material, relative_point: vec4<f32>) -> Material {
+    var result = material;
+    let cell_position = relative_point / max(material.pattern_scale, 1e-6);
+    switch material.pattern {
+        case PATTERN_CHECKER: {
+            let cell = floor(cell_position);
+            if (i32(cell.x + cell.y + cell.z + cell.w) & 1) != 0 {
+                result.color *= PATTERN_SHADE;
+            }
+        }
+        case PATTERN_GRID: {
+            let line_distance = abs(cell_position - round(cell_position));
+            if any(line_distance < vec4<f32>(PATTERN_LINE_WIDTH * 0.5)) {
+                result.color *= PATTERN_SHADE;
+            }
+        }
+        default: {}
+    }
+    return result;
+}
+
+// the point given to material_at for a point on a flat face, the coordinate along the face's normal
+// is moved to the middle of a cell so that it doesn't affect the pattern
+fn face_pattern_point(relative_point: vec4<f32>, axis: u32, material: Material) -> vec4<f32> {
+    var point = relative_point;
+    point[axis] = 0.5 * material.pattern_scale;
+    return point;
+}
 
 struct Hypersphere {
     transform: Transform,
-    color: vec3<f32>,
+    // applied in object space before the transform
+    scale: vec4<f32>,
+    material: Material,
     radius: f32,
+    views: u32,
 }
 
 @group(2) @binding(0)
@@ -29,15 +159,90 @@ var<storage, read> hyperspheres: array<Hypersphere>;
 
 struct Hyperplane {
     transform: Transform,
-    color: vec3<f32>,
+    material: Material,
     width: f32,
     height: f32,
     depth: f32,
+    // when non-zero the width, height and depth are ignored and the hyperplane is unbounded
+    infinite: u32,
+    // when non-zero rays coming from behind the hyperplane, the side opposite its local +y axis, pass through it
+    cull_back: u32,
+    views: u32,
 }
 
 @group(2) @binding(1)
 var<storage, read> hyperplanes: array<Hyperplane>;
 
+struct Tesseract {
+    transform: Transform,
+    size: vec4<f32>,
+    material: Material,
+    views: u32,
+}
+
+@group(2) @binding(2)
+var<storage, read> tesseracts: array<Tesseract>;
+
+// the nodes are stored depth first, so the children of a node follow it directly and skip is the first node after them
+struct BvhNode {
+    min: vec4<f32>,
+    max: vec4<f32>,
+    // the hypersphere in this leaf, or NO_OBJECT if the node has children
+    hypersphere: u32,
+    skip: u32,
+}
+
+@group(2) @binding(3)
+var<storage, read> hypersphere_bvh: array<BvhNode>;
+
+struct Torus {
+    transform: Transform,
+    material: Material,
+    major_radius: f32,
+    minor_radius: f32,
+    views: u32,
+}
+
+@group(2) @binding(4)
+var<storage, read> tori: array<Torus>;
+
+// a ball in the xzw hyperplane extruded along the local y axis, from - length / 2 to length / 2
+struct Spherinder {
+    transform: Transform,
+    material: Material,
+    radius: f32,
+    length: f32,
+    views: u32,
+}
+
+@group(2) @binding(5)
+var<storage, read> spherinders: array<Spherinder>;
+
+// only the transforms, scales and radii of a and b are used, the combined shape is shaded with material
+struct CsgPair {
+    a: Hypersphere,
+    b: Hypersphere,
+    material: Material,
+    op: u32,
+    views: u32,
+}
+
+const CSG_UNION: u32 = 0u;
+const CSG_INTERSECTION: u32 = 1u;
+const CSG_DIFFERENCE: u32 = 2u;
+
+@group(2) @binding(6)
+var<storage, read> csg_pairs: array<CsgPair>;
+
+struct Light {
+    position: vec4<f32>,
+    color: vec3<f32>,
+    intensity: f32,
+}
+
+@group(3) @binding(0)
+var<storage, read> lights: array<Light>;
+
 struct Ray {
     origin: vec4<f32>,
     direction: vec4<f32>,
@@ -48,19 +253,80 @@ struct Hit {
     distance: f32,
     position: vec4<f32>,
     normal: vec4<f32>,
-    color: vec3<f32>,
+    // the material of the hit object with its pattern already applied
+    material: Material,
+    // whether the ray hit the outside of the object, rather than leaving it from the inside
+    front_face: bool,
+    // which of the object arrays the hit object is in, and its index in that array
+    object_kind: u32,
+    object_index: u32,
+}
+
+const OBJECT_HYPERSPHERE: u32 = 0u;
+const OBJECT_HYPERPLANE: u32 = 1u;
+const OBJECT_TESSERACT: u32 = 2u;
+const OBJECT_TORUS: u32 = 3u;
+const OBJECT_SPHERINDER: u32 = 4u;
+const OBJECT_CSG_PAIR: u32 = 5u;
+const NO_OBJECT: u32 = 0xffffffffu;
+
+// the bit of the view objects are intersected for, the entry points that trace rays from the camera set it from the push constants,
+// and raycast leaves it matching every view
+var<private> view: u32 = 0xffffffffu;
+
+fn safe_normalize(v: vec4<f32>) -> vec4<f32> {
+    let length_squared = dot(v, v);
+    if length_squared > 0.0 {
+        return v * inverseSqrt(length_squared);
+    }
+    return vec4<f32>(0.0);
+}
+
+// transforms an object space normal into world space, the translation part of the transform is ignored
+fn transform_normal(transform: Transform, normal: vec4<f32>) -> vec4<f32> {
+    return safe_normalize(transform_direction(transform, normal));
+}
+
+// in slice mode every ray stays inside the w = slice_w hyperplane, so the objects are rendered as their 3d cross sections
+fn slice_point(point: vec4<f32>) -> vec4<f32> {
+    if push_constants.render_mode == RENDER_MODE_SLICE {
+        return vec4<f32>(point.xyz, push_constants.slice_w);
+    }
+    return point;
+}
+
+// removes the part of a direction that would leave the slice, this does not renormalize it
+fn slice_direction(direction: vec4<f32>) -> vec4<f32> {
+    if push_constants.render_mode == RENDER_MODE_SLICE {
+        return vec4<f32>(direction.xyz, 0.0);
+    }
+    return direction;
+}
+
+// flips the normal so that it points against the incoming ray
+fn face_forward(normal: vec4<f32>, direction: vec4<f32>) -> vec4<f32> {
+    if dot(normal, direction) > 0.0 {
+        return - normal;
+    }
+    return normal;
 }
 
 fn intersect_hypersphere(ray: Ray, hypersphere: Hypersphere) -> Hit {
     var hit: Hit;
     hit.hit = false;
+    if (hypersphere.views & view) == 0u {
+        return hit;
+    }
 
-    let position = transform_position(hypersphere.transform);
+    // the ray is moved into the space where the hypersphere is unscaled and centered on the origin,
+    // the mapping is affine so distances along the ray stay the same
+    let reverse_transform = transform_reverse(hypersphere.transform);
+    let origin = transform_point(reverse_transform, ray.origin) / hypersphere.scale;
+    let direction = transform_direction(reverse_transform, ray.direction) / hypersphere.scale;
 
-    let oc = position - ray.origin;
-    // TODO: can this be replaced with 1?
-    let a = dot(ray.direction, ray.direction);
-    let h = dot(ray.direction, oc);
+    let oc = - origin;
+    let a = dot(direction, direction);
+    let h = dot(direction, oc);
     let c = dot(oc, oc) - hypersphere.radius * hypersphere.radius;
     let discriminant = h * h - a * c;
 
@@ -68,17 +334,19 @@ fn intersect_hypersphere(ray: Ray, hypersphere: Hypersphere) -> Hit {
         let d1 = (h - sqrt(discriminant)) / a;
         let d2 = (h + sqrt(discriminant)) / a;
         hit.distance = d1;
+        hit.front_face = true;
         if d1 < 0.0 {
             hit.distance = d2;
+            hit.front_face = false;
         }
         if hit.distance > 0.0 {
             hit.hit = true;
             hit.position = ray.origin + ray.direction * hit.distance;
-            hit.normal = (hit.position - position) / hypersphere.radius;
-            if hit.distance == d2 {
-                hit.normal = - hit.normal;
-            }
-            hit.color = hypersphere.color;
+            // normals have to be transformed by the inverse transpose of the scale, not the scale itself,
+            // to stay perpendicular to the stretched surface
+            let normal = (origin + direction * hit.distance) / hypersphere.scale;
+            hit.normal = face_forward(transform_normal(hypersphere.transform, normal), ray.direction);
+            hit.material = material_at(hypersphere.material, origin + direction * hit.distance);
         }
     }
 
@@ -88,6 +356,9 @@ fn intersect_hypersphere(ray: Ray, hypersphere: Hypersphere) -> Hit {
 fn intersect_hyperplane(ray: Ray, hyperplane: Hyperplane) -> Hit {
     var hit: Hit;
     hit.hit = false;
+    if (hyperplane.views & view) == 0u {
+        return hit;
+    }
 
     let reverse_transform = transform_reverse(hyperplane.transform);
 
@@ -98,35 +369,376 @@ fn intersect_hyperplane(ray: Ray, hyperplane: Hyperplane) -> Hit {
     if sign(transformed_ray.origin.y) == sign(transformed_ray.direction.y) {
         return hit;
     }
+    if hyperplane.cull_back != 0u && transformed_ray.origin.y < 0.0 {
+        return hit;
+    }
 
     hit.distance = abs(transformed_ray.origin.y / transformed_ray.direction.y);
 
     let relative_point = transformed_ray.origin + transformed_ray.direction * hit.distance;
-    if !(abs(relative_point.x) <= hyperplane.height * 0.5) {
+    if hyperplane.infinite == 0u {
+        if !(abs(relative_point.x) <= hyperplane.height * 0.5) {
+            return hit;
+        }
+        if !(abs(relative_point.z) <= hyperplane.width * 0.5) {
+            return hit;
+        }
+        if !(abs(relative_point.w) <= hyperplane.depth * 0.5) {
+            return hit;
+        }
+    }
+
+    hit.hit = true;
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.normal = face_forward(transform_normal(hyperplane.transform, vec4<f32>(0.0, 1.0, 0.0, 0.0)), ray.direction);
+    hit.material = material_at(hyperplane.material, face_pattern_point(relative_point, 1u, hyperplane.material));
+    hit.front_face = true;
+    return hit;
+}
+
+fn intersect_tesseract(ray: Ray, tesseract: Tesseract) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    if (tesseract.views & view) == 0u {
         return hit;
     }
-    if !(abs(relative_point.z) <= hyperplane.width * 0.5) {
+
+    let reverse_transform = transform_reverse(tesseract.transform);
+    let origin = transform_point(reverse_transform, ray.origin);
+    let direction = transform_direction(reverse_transform, ray.direction);
+    let half_size = abs(tesseract.size) * 0.5;
+
+    // slab test, keeping track of which axis the ray enters and exits through
+    var near = - 3.4e38;
+    var far = 3.4e38;
+    var near_axis = 0u;
+    var far_axis = 0u;
+    for (var i = 0u; i < 4u; i++) {
+        if abs(direction[i]) < 1e-8 {
+            if abs(origin[i]) > half_size[i] {
+                return hit;
+            }
+            continue;
+        }
+
+        let t1 = (- half_size[i] - origin[i]) / direction[i];
+        let t2 = (half_size[i] - origin[i]) / direction[i];
+        if min(t1, t2) > near {
+            near = min(t1, t2);
+            near_axis = i;
+        }
+        if max(t1, t2) < far {
+            far = max(t1, t2);
+            far_axis = i;
+        }
+    }
+
+    if near > far || far <= 0.0 {
         return hit;
     }
-    if !(abs(relative_point.w) <= hyperplane.depth * 0.5) {
+
+    var axis = near_axis;
+    hit.distance = near;
+    if near <= 0.0 {
+        axis = far_axis;
+        hit.distance = far;
+    }
+
+    var normal = vec4<f32>(0.0);
+    normal[axis] = 1.0;
+
+    hit.hit = true;
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.normal = face_forward(transform_normal(tesseract.transform, normal), ray.direction);
+    hit.material = material_at(tesseract.material, face_pattern_point(origin + direction * hit.distance, axis, tesseract.material));
+    hit.front_face = near > 0.0;
+    return hit;
+}
+
+// signed distance to the torus in object space, the clifford torus is where the xy and zw circles both have the major radius
+fn torus_distance(point: vec4<f32>, torus: Torus) -> f32 {
+    let offset = vec2<f32>(length(point.xy), length(point.zw)) - torus.major_radius;
+    return length(offset) - torus.minor_radius;
+}
+
+// the gradient of torus_distance, which points away from the surface
+fn torus_normal(point: vec4<f32>, torus: Torus) -> vec4<f32> {
+    let xy = length(point.xy);
+    let zw = length(point.zw);
+    var normal = vec4<f32>(0.0);
+    if xy > 0.0 {
+        normal = vec4<f32>(point.xy * (xy - torus.major_radius) / xy, normal.zw);
+    }
+    if zw > 0.0 {
+        normal = vec4<f32>(normal.xy, point.zw * (zw - torus.major_radius) / zw);
+    }
+    return safe_normalize(normal);
+}
+
+const TORUS_HIT_DISTANCE: f32 = 1e-4;
+
+fn intersect_torus(ray: Ray, torus: Torus) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    if (torus.views & view) == 0u {
+        return hit;
+    }
+
+    let reverse_transform = transform_reverse(torus.transform);
+    let origin = transform_point(reverse_transform, ray.origin);
+    let direction_length = length(ray.direction);
+    if direction_length == 0.0 {
+        return hit;
+    }
+    let direction = transform_direction(reverse_transform, ray.direction) / direction_length;
+
+    // there is no closed form for the intersection, so the ray is sphere traced,
+    // only inside the hypersphere that bounds the torus so that rays that miss it stop early
+    let bounding_radius = sqrt(2.0) * abs(torus.major_radius) + abs(torus.minor_radius);
+    let h = - dot(direction, origin);
+    let discriminant = h * h - dot(origin, origin) + bounding_radius * bounding_radius;
+    if discriminant < 0.0 {
+        return hit;
+    }
+    let exit = h + sqrt(discriminant);
+    if exit <= 0.0 {
         return hit;
     }
 
+    // rays that start inside the torus march towards where the distance becomes positive
+    let side = sign(torus_distance(origin, torus));
+    var distance = max(h - sqrt(discriminant), 0.0);
+    for (var i = 0u; i < info.torus_max_steps; i++) {
+        let march = side * torus_distance(origin + direction * distance, torus);
+        if march < TORUS_HIT_DISTANCE {
+            hit.hit = true;
+            break;
+        }
+        distance += march;
+        if distance > exit {
+            break;
+        }
+    }
+    if !hit.hit {
+        return hit;
+    }
+
+    hit.distance = distance / direction_length;
+    hit.position = ray.origin + ray.direction * hit.distance;
+    let normal = torus_normal(origin + direction * distance, torus);
+    hit.normal = face_forward(transform_normal(torus.transform, normal), ray.direction);
+    hit.material = material_at(torus.material, origin + direction * distance);
+    hit.front_face = side >= 0.0;
+    return hit;
+}
+
+fn intersect_spherinder(ray: Ray, spherinder: Spherinder) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    if (spherinder.views & view) == 0u {
+        return hit;
+    }
+
+    let reverse_transform = transform_reverse(spherinder.transform);
+    let origin = transform_point(reverse_transform, ray.origin);
+    let direction = transform_direction(reverse_transform, ray.direction);
+
+    // the ray is inside the spherinder where it is both inside the round side, which is a hypersphere with the
+    // y axis removed, and between the two caps, keeping track of which of them it enters and exits through
+    var near = - 3.4e38;
+    var far = 3.4e38;
+    var near_cap = false;
+    var far_cap = false;
+
+    let a = dot(direction.xzw, direction.xzw);
+    let c = dot(origin.xzw, origin.xzw) - spherinder.radius * spherinder.radius;
+    if a < 1e-12 {
+        if c > 0.0 {
+            return hit;
+        }
+    }
+    else {
+        let h = - dot(direction.xzw, origin.xzw);
+        let discriminant = h * h - a * c;
+        if discriminant < 0.0 {
+            return hit;
+        }
+        near = (h - sqrt(discriminant)) / a;
+        far = (h + sqrt(discriminant)) / a;
+    }
+
+    let half_length = abs(spherinder.length) * 0.5;
+    if abs(direction.y) < 1e-8 {
+        if abs(origin.y) > half_length {
+            return hit;
+        }
+    }
+    else {
+        let t1 = (- half_length - origin.y) / direction.y;
+        let t2 = (half_length - origin.y) / direction.y;
+        if min(t1, t2) > near {
+            near = min(t1, t2);
+            near_cap = true;
+        }
+        if max(t1, t2) < far {
+            far = max(t1, t2);
+            far_cap = true;
+        }
+    }
+
+    if near > far || far <= 0.0 {
+        return hit;
+    }
+
+    var cap = near_cap;
+    hit.distance = near;
+    if near <= 0.0 {
+        cap = far_cap;
+        hit.distance = far;
+    }
+
+    let point = origin + direction * hit.distance;
+    var normal = vec4<f32>(point.x, 0.0, point.z, point.w);
+    var pattern_point = point;
+    if cap {
+        normal = vec4<f32>(0.0, sign(point.y), 0.0, 0.0);
+        pattern_point = face_pattern_point(point, 1u, spherinder.material);
+    }
+
     hit.hit = true;
     hit.position = ray.origin + ray.direction * hit.distance;
-    hit.normal = transform_y(hyperplane.transform) * sign(transformed_ray.origin.y);
-    hit.color = hyperplane.color;
+    hit.normal = face_forward(transform_normal(spherinder.transform, normal), ray.direction);
+    hit.material = material_at(spherinder.material, pattern_point);
+    hit.front_face = near > 0.0;
+    return hit;
+}
+
+// the distances along a ray where it enters and leaves an object, near is greater than far when it misses
+struct Interval {
+    near: f32,
+    far: f32,
+}
+
+fn hypersphere_interval(ray: Ray, hypersphere: Hypersphere) -> Interval {
+    var interval = Interval(3.4e38, - 3.4e38);
+
+    let reverse_transform = transform_reverse(hypersphere.transform);
+    let origin = transform_point(reverse_transform, ray.origin) / hypersphere.scale;
+    let direction = transform_direction(reverse_transform, ray.direction) / hypersphere.scale;
+
+    let a = dot(direction, direction);
+    let h = - dot(direction, origin);
+    let c = dot(origin, origin) - hypersphere.radius * hypersphere.radius;
+    let discriminant = h * h - a * c;
+    if discriminant >= 0.0 {
+        interval.near = (h - sqrt(discriminant)) / a;
+        interval.far = (h + sqrt(discriminant)) / a;
+    }
+    return interval;
+}
+
+fn intersect_csg_pair(ray: Ray, pair: CsgPair) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+    if (pair.views & view) == 0u {
+        return hit;
+    }
+
+    // each of the up to four places where the ray crosses the surface of a or b is on the combined surface depending on whether
+    // it is inside of the other hypersphere, for a difference the surface of b is carved into a so it only counts inside of a
+    var intervals = array<Interval, 2>(hypersphere_interval(ray, pair.a), hypersphere_interval(ray, pair.b));
+    var closest = 3.4e38;
+    var closest_object = 0u;
+    for (var i = 0u; i < 4u; i++) {
+        let object = i / 2u;
+        let interval = intervals[object];
+        let other = intervals[1u - object];
+        let entering = i % 2u == 0u;
+        let distance = select(interval.far, interval.near, entering);
+        if interval.near > interval.far || distance <= 0.0 || distance >= closest {
+            continue;
+        }
+
+        let inside_other = other.near < distance && distance < other.far;
+        let carved = pair.op == CSG_DIFFERENCE && object == 1u;
+        if inside_other != (pair.op == CSG_INTERSECTION || carved) {
+            continue;
+        }
+        closest = distance;
+        closest_object = object;
+        hit.front_face = entering != carved;
+    }
+    if closest == 3.4e38 {
+        return hit;
+    }
+
+    var hypersphere = pair.a;
+    if closest_object == 1u {
+        hypersphere = pair.b;
+    }
+    hit.hit = true;
+    hit.distance = closest;
+    hit.position = ray.origin + ray.direction * closest;
+    let point = transform_point(transform_reverse(hypersphere.transform), hit.position) / hypersphere.scale;
+    hit.normal = face_forward(transform_normal(hypersphere.transform, point / hypersphere.scale), ray.direction);
+    // the pattern is laid out in the space of a on both surfaces so that it lines up where they meet
+    let pattern_point = transform_point(transform_reverse(pair.a.transform), hit.position) / pair.a.scale;
+    hit.material = material_at(pair.material, pattern_point);
     return hit;
 }
 
+// whether the ray passes through the box closer than max_distance
+fn intersect_bounds(ray: Ray, min_corner: vec4<f32>, max_corner: vec4<f32>, max_distance: f32) -> bool {
+    var near = 0.0;
+    var far = max_distance;
+    for (var i = 0u; i < 4u; i++) {
+        if abs(ray.direction[i]) < 1e-8 {
+            if ray.origin[i] < min_corner[i] || ray.origin[i] > max_corner[i] {
+                return false;
+            }
+            continue;
+        }
+
+        let t1 = (min_corner[i] - ray.origin[i]) / ray.direction[i];
+        let t2 = (max_corner[i] - ray.origin[i]) / ray.direction[i];
+        near = max(near, min(t1, t2));
+        far = min(far, max(t1, t2));
+    }
+    return near <= far;
+}
+
 fn intersect_scene(ray: Ray) -> Hit {
     var closest_hit: Hit;
     closest_hit.hit = false;
 
-    for (var i = 0u; i < info.hyperspheres_count; i++) {
-        let hit = intersect_hypersphere(ray, hyperspheres[i]);
-        if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
-            closest_hit = hit;
+    if info.hypersphere_bvh_nodes_count == 0u {
+        for (var i = 0u; i < info.hyperspheres_count; i++) {
+            let hit = intersect_hypersphere(ray, hyperspheres[i]);
+            if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+                closest_hit = hit;
+                closest_hit.object_kind = OBJECT_HYPERSPHERE;
+                closest_hit.object_index = i;
+            }
+        }
+    } else {
+        var node_index = 0u;
+        while node_index < info.hypersphere_bvh_nodes_count {
+            let node = hypersphere_bvh[node_index];
+            let max_distance = select(3.4e38, closest_hit.distance, closest_hit.hit);
+            if !intersect_bounds(ray, node.min, node.max, max_distance) {
+                node_index = node.skip;
+                continue;
+            }
+
+            if node.hypersphere != NO_OBJECT {
+                let hit = intersect_hypersphere(ray, hyperspheres[node.hypersphere]);
+                if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+                    closest_hit = hit;
+                    closest_hit.object_kind = OBJECT_HYPERSPHERE;
+                    closest_hit.object_index = node.hypersphere;
+                }
+            }
+            node_index++;
         }
     }
 
@@ -134,40 +746,487 @@ fn intersect_scene(ray: Ray) -> Hit {
         let hit = intersect_hyperplane(ray, hyperplanes[i]);
         if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
             closest_hit = hit;
+            closest_hit.object_kind = OBJECT_HYPERPLANE;
+            closest_hit.object_index = i;
+        }
+    }
+
+    for (var i = 0u; i < info.tesseracts_count; i++) {
+        let hit = intersect_tesseract(ray, tesseracts[i]);
+        if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+            closest_hit = hit;
+            closest_hit.object_kind = OBJECT_TESSERACT;
+            closest_hit.object_index = i;
+        }
+    }
+
+    for (var i = 0u; i < info.tori_count; i++) {
+        let hit = intersect_torus(ray, tori[i]);
+        if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+            closest_hit = hit;
+            closest_hit.object_kind = OBJECT_TORUS;
+            closest_hit.object_index = i;
+        }
+    }
+
+    for (var i = 0u; i < info.spherinders_count; i++) {
+        let hit = intersect_spherinder(ray, spherinders[i]);
+        if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+            closest_hit = hit;
+            closest_hit.object_kind = OBJECT_SPHERINDER;
+            closest_hit.object_index = i;
+        }
+    }
+
+    for (var i = 0u; i < info.csg_pairs_count; i++) {
+        let hit = intersect_csg_pair(ray, csg_pairs[i]);
+        if hit.hit && (!closest_hit.hit || hit.distance < closest_hit.distance) {
+            closest_hit = hit;
+            closest_hit.object_kind = OBJECT_CSG_PAIR;
+            closest_hit.object_index = i;
         }
     }
 
     return closest_hit;
 }
 
-const SUN_DIRECTION: vec4<f32> = vec4<f32>(- 0.1, 1.0, 0.3, 0.1);
+// like intersect_scene, but only finds hits between the camera's near and far distances, for rays from camera_ray
+fn intersect_view(ray: Ray) -> Hit {
+    let near = max(push_constants.camera.near, 0.0);
+    var clipped_ray = ray;
+    clipped_ray.origin += ray.direction * near;
+    var hit = intersect_scene(clipped_ray);
+    hit.distance += near;
+    if hit.distance > push_constants.camera.far {
+        hit.hit = false;
+    }
+    return hit;
+}
+
+// the light every surface gets even when it faces away from the sun or is in its shadow
+const AMBIENT_LIGHT: f32 = 0.2;
+
+// a texel of the environment texture, wrapping around horizontally and clamped at the top and bottom
+fn environment_texel(texel: vec2<i32>, size: vec2<i32>) -> vec3<f32> {
+    let x = ((texel.x % size.x) + size.x) % size.x;
+    let y = clamp(texel.y, 0, size.y - 1);
+    return textureLoad(environment_texture, vec2<i32>(x, y), 0).rgb;
+}
+
+// looks the direction up in the environment texture like an equirectangular image of the 3d sky, see
+// EnvironmentMap in lib.rs for how the 3-sphere of directions is flattened
+fn environment_color(direction: vec4<f32>) -> vec3<f32> {
+    let size = vec2<i32>(textureDimensions(environment_texture));
+    let d = safe_normalize(direction);
+    let uv = vec2<f32>(atan2(d.z, d.x) / TAU + 0.5, acos(clamp(d.y, -1.0, 1.0)) / (TAU * 0.5));
+
+    // filtered by hand, since the texture's format can't be filtered by a sampler
+    let position = uv * vec2<f32>(size) - 0.5;
+    let texel = vec2<i32>(floor(position));
+    let t = fract(position);
+    let top = mix(environment_texel(texel, size), environment_texel(texel + vec2<i32>(1, 0), size), t.x);
+    let bottom = mix(environment_texel(texel + vec2<i32>(0, 1), size), environment_texel(texel + vec2<i32>(1, 1), size), t.x);
+    return mix(top, bottom, t.y);
+}
 
 fn sky_color(ray: Ray) -> vec3<f32> {
-    let up = vec3<f32>(0.4, 0.5, 0.8);
-    let down = vec3<f32>(0.2, 0.2, 0.3);
-    if dot(ray.direction, normalize(SUN_DIRECTION)) > 0.99 {
-        return vec3<f32>(1.0);
+    if info.sun_intensity > 0.0 && dot(ray.direction, safe_normalize(info.sun_direction)) > 0.99 {
+        return info.sun_color * info.sun_intensity;
     }
-    return mix(down, up, ray.direction.y * 0.5 + 0.5);
+    if info.has_environment != 0u {
+        return environment_color(ray.direction);
+    }
+    let height = dot(ray.direction, safe_normalize(info.sky_direction));
+    return mix(info.sky_horizon, info.sky_zenith, clamp(height, 0.0, 1.0));
 }
 
-fn trace_ray(ray: Ray) -> vec3<f32> {
-    let hit = intersect_scene(ray);
-    if hit.hit {
-        var sun_ray: Ray;
-        sun_ray.origin = hit.position + hit.normal * 0.001;
-        sun_ray.direction = normalize(SUN_DIRECTION);
+// the Blinn-Phong highlight for light arriving from light_direction and leaving along view_direction, the
+// half-vector works the same in 4d as it does in 3d
+fn specular(hit: Hit, view_direction: vec4<f32>, light_direction: vec4<f32>) -> f32 {
+    if hit.material.specular <= 0.0 || dot(hit.normal, light_direction) <= 0.0 {
+        return 0.0;
+    }
+    let half_vector = safe_normalize(light_direction - view_direction);
+    return hit.material.specular * pow(max(dot(hit.normal, half_vector), 0.0), max(hit.material.shininess, 1.0));
+}
+
+// the light leaving the hit surface back along view_direction, the diffuse part is tinted by the color and the
+// highlights aren't
+fn shade(hit: Hit, view_direction: vec4<f32>) -> vec3<f32> {
+    var sun_ray: Ray;
+    sun_ray.origin = hit.position + hit.normal * info.shadow_bias;
+    sun_ray.direction = safe_normalize(slice_direction(info.sun_direction));
+
+    let sun_hit = intersect_scene(sun_ray);
+
+    let sun_light = info.sun_color * info.sun_intensity * f32(!sun_hit.hit);
+    let sun = max(vec3<f32>(AMBIENT_LIGHT), sun_light * max(0.0, dot(hit.normal, sun_ray.direction)));
+    var highlights = sun_light * specular(hit, view_direction, sun_ray.direction);
+    // the headlamp shines along the camera's view direction, so it never casts visible shadows
+    let headlamp = info.headlamp_color * info.headlamp_intensity * max(0.0, dot(hit.normal, - push_constants.camera.forward));
+
+    var lighting = vec3<f32>(0.0);
+    for (var i = 0u; i < info.lights_count; i++) {
+        let light = lights[i];
+        let to_light = slice_direction(light.position - hit.position);
+        let distance = length(to_light);
+
+        var shadow_ray: Ray;
+        shadow_ray.origin = hit.position + hit.normal * info.shadow_bias;
+        shadow_ray.direction = to_light / distance;
+        let shadow_hit = intersect_scene(shadow_ray);
+        if shadow_hit.hit && shadow_hit.distance < distance - info.shadow_bias {
+            continue;
+        }
+
+        // in 4d the light spreads out over a 3-sphere, so it falls off with the cube of the distance
+        let attenuation = light.intensity / max(distance * distance * distance, 1e-4);
+        lighting += light.color * attenuation * max(0.0, dot(hit.normal, to_light / distance));
+        highlights += light.color * attenuation * specular(hit, view_direction, to_light / distance);
+    }
+
+    // emissive hyperspheres light their surroundings as if all of their light came from their center,
+    // the light reaching a point scales with the solid angle of the hypersphere, which is (r / d)^3 in 4d
+    for (var i = 0u; i < info.hyperspheres_count; i++) {
+        let hypersphere = hyperspheres[i];
+        // a hypersphere hidden from this view can't be hit by the shadow rays either, so it mustn't light it
+        if (hypersphere.views & view) == 0u || all(hypersphere.material.emission <= vec3<f32>(0.0)) || (hit.object_kind == OBJECT_HYPERSPHERE && hit.object_index == i) {
+            continue;
+        }
+
+        let to_light = slice_direction(transform_position(hypersphere.transform) - hit.position);
+        let distance = length(to_light);
+        let scale = abs(hypersphere.scale);
+        let radius = hypersphere.radius * max(max(scale.x, scale.y), max(scale.z, scale.w));
+        if distance <= radius {
+            continue;
+        }
+
+        // the fraction of the shadow rays that reach the hypersphere, aiming them at random points on its surface
+        // rather than its center gives a penumbra where it is partly hidden
+        let shadow_samples = max(info.shadow_samples, 1u);
+        var visibility = 0.0;
+        for (var sample = 0u; sample < shadow_samples; sample++) {
+            var target_offset = vec4<f32>(0.0);
+            if shadow_samples > 1u {
+                target_offset = random_direction() * radius;
+            }
+
+            var shadow_ray: Ray;
+            shadow_ray.origin = hit.position + hit.normal * info.shadow_bias;
+            shadow_ray.direction = safe_normalize(slice_direction(to_light + target_offset));
+            let shadow_hit = intersect_scene(shadow_ray);
+            if !shadow_hit.hit || (shadow_hit.object_kind == OBJECT_HYPERSPHERE && shadow_hit.object_index == i) {
+                visibility += 1.0;
+            }
+        }
+        if visibility <= 0.0 {
+            continue;
+        }
+
+        let ratio = radius / distance;
+        lighting += hypersphere.material.emission * ratio * ratio * ratio * (visibility / f32(shadow_samples)) * max(0.0, dot(hit.normal, to_light / distance));
+    }
+
+    return hit.material.color * (sun + headlamp + lighting) + highlights;
+}
+
+// how dark a surface infinitely far from the camera in w gets
+const W_DEPTH_MIN_BRIGHTNESS: f32 = 0.25;
+
+// fades the color of a surface towards a darker gray as it gets further from the camera in w, which gives a cue about the
+// w offsets that a projection otherwise hides
+fn w_depth_shade(material: Material, w_distance: f32) -> Material {
+    let shade = exp(- max(info.w_falloff, 0.0) * w_distance);
+    let brightness = mix(W_DEPTH_MIN_BRIGHTNESS, 1.0, shade);
+    var shaded = material;
+    let gray = vec3<f32>(dot(material.color, vec3<f32>(0.2126, 0.7152, 0.0722)));
+    shaded.color = mix(gray, material.color, shade) * brightness;
+    shaded.emission = material.emission * brightness;
+    return shaded;
+}
+
+// every bounce, whether it reflects off of a surface or passes through a transparent one, traces another ray
+// through the whole scene along with its shadow rays, so such a pixel costs up to max_bounces + 1 times as much as a diffuse one
+const MAX_BOUNCES: u32 = 16u;
+
+fn trace_ray(initial_ray: Ray) -> vec3<f32> {
+    var ray = initial_ray;
+    var color = vec3<f32>(0.0);
+    var throughput = vec3<f32>(1.0);
+    let max_bounces = min(info.max_bounces, MAX_BOUNCES);
+    for (var bounce = 0u; bounce <= max_bounces; bounce++) {
+        var hit: Hit;
+        if bounce == 0u {
+            hit = intersect_view(ray);
+        }
+        else {
+            hit = intersect_scene(ray);
+        }
+        if !hit.hit {
+            color += throughput * sky_color(ray);
+            break;
+        }
+
+        hit.normal = safe_normalize(slice_direction(hit.normal));
+
+        // the fog only covers surfaces, the sky is left as it is so that the gradient still shows
+        let fog = 1.0 - exp(- max(info.fog_density, 0.0) * max(hit.distance - info.fog_start, 0.0));
+        color += throughput * fog * info.fog_color;
+        throughput *= 1.0 - fog;
+
+        if push_constants.render_mode == RENDER_MODE_PROJECT {
+            hit.material = w_depth_shade(hit.material, abs(hit.position.w - push_constants.camera.position.w));
+        }
+
+        // the last bounce is shaded as fully diffuse and opaque so that capped reflections don't go black
+        var reflectivity = clamp(hit.material.reflectivity, 0.0, 1.0);
+        var opacity = clamp(hit.material.opacity, 0.0, 1.0);
+        if bounce == max_bounces {
+            reflectivity = 0.0;
+            opacity = 1.0;
+        }
+
+        color += throughput * opacity * (hit.material.emission + (1.0 - reflectivity) * shade(hit, ray.direction));
+
+        // only one ray can be followed, so a transparent surface continues through itself and its reflections are dropped
+        if opacity < 1.0 {
+            var eta = hit.material.ior;
+            if hit.front_face {
+                eta = 1.0 / hit.material.ior;
+            }
+            // refract gives back zero on total internal reflection
+            let refracted = refract(ray.direction, hit.normal, eta);
+            throughput *= 1.0 - opacity;
+            if all(refracted == vec4<f32>(0.0)) {
+                ray.origin = hit.position + hit.normal * info.shadow_bias;
+                ray.direction = reflect(ray.direction, hit.normal);
+            } else {
+                ray.origin = hit.position - hit.normal * info.shadow_bias;
+                ray.direction = safe_normalize(slice_direction(refracted));
+            }
+            continue;
+        }
+
+        if reflectivity <= 0.0 {
+            break;
+        }
+
+        throughput *= reflectivity * hit.material.color;
+        ray.origin = hit.position + hit.normal * info.shadow_bias;
+        ray.direction = reflect(ray.direction, hit.normal);
+    }
+    return color;
+}
+
+// pcg hash, from https://www.reedbeta.com/blog/hash-functions-for-gpu-rendering/
+fn hash(input: u32) -> u32 {
+    let state = input * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// maps a hash to a float in [0, 1)
+fn random_float(value: u32) -> f32 {
+    return f32(value >> 8u) / 16777216.0;
+}
+
+const TAU: f32 = 6.283185307;
+
+// the state of the random numbers used while tracing a sample, seeded in ray_trace
+var<private> random_state: u32;
 
-        let sun_hit = intersect_scene(sun_ray);
+fn next_random() -> f32 {
+    random_state = hash(random_state);
+    return random_float(random_state);
+}
+
+// a uniformly distributed direction, from normalizing 4 normally distributed numbers made with the box-muller transform
+fn random_direction() -> vec4<f32> {
+    let radii = sqrt(-2.0 * log(max(vec2<f32>(next_random(), next_random()), vec2<f32>(1e-7))));
+    let angles = vec2<f32>(next_random(), next_random()) * TAU;
+    return safe_normalize(vec4<f32>(radii.x * cos(angles.x), radii.x * sin(angles.x), radii.y * cos(angles.y), radii.y * sin(angles.y)));
+}
 
-        return hit.color * max(0.2, f32(!sun_hit.hit) * dot(hit.normal, sun_ray.direction));
+// builds the ray for a point in the pixel at coords, offset is in the range [0, 1) from the corner of the pixel
+fn camera_ray(coords: vec2<u32>, size: vec2<u32>, offset: vec2<f32>) -> Ray {
+    let camera = push_constants.camera;
+    let aspect = f32(size.x) / f32(size.y);
+    let uv = ((vec2<f32>(coords) + offset) / vec2<f32>(size)) * 2.0 - 1.0;
+
+    let screen_offset = camera.up * uv.y + camera.right * uv.x * aspect;
+    var ray: Ray;
+    if camera.projection == PROJECTION_ORTHOGRAPHIC {
+        ray.origin = camera.position + screen_offset * camera.orthographic_height * 0.5;
+        ray.direction = camera.forward;
+    }
+    else if camera.projection == PROJECTION_STEREOGRAPHIC {
+        // the inverse stereographic projection from the pole of the point on the image plane, moved into the
+        // hyperplane through the camera perpendicular to the pole, a point at distance tan(angle / 2) ends up
+        // angle away from the direction opposite the pole
+        let pole = safe_normalize(camera.pole);
+        var point = screen_offset * tan(camera.fov * 0.25);
+        point -= pole * dot(point, pole);
+        let squared_length = dot(point, point);
+        ray.origin = camera.position;
+        ray.direction = (2.0 * point + (squared_length - 1.0) * pole) / (squared_length + 1.0);
     }
     else {
-        return sky_color(ray);
+        ray.origin = camera.position;
+        ray.direction = normalize(camera.forward + screen_offset * tan(camera.fov * 0.5));
+    }
+    ray.origin = slice_point(ray.origin);
+    ray.direction = safe_normalize(slice_direction(ray.direction));
+    return ray;
+}
+
+// moves the start of a camera ray to a random point on the lens, which is a 3-ball perpendicular to the view
+// direction in 4d, and turns it so that it still passes through the point it did at the focus distance. the
+// focus is a hyperplane in front of the camera, except with a stereographic projection where rays can point
+// sideways or backwards and it is a hypersphere around the camera instead
+fn defocus(ray: Ray) -> Ray {
+    let camera = push_constants.camera;
+    if camera.aperture <= 0.0 {
+        return ray;
+    }
+
+    var focus_distance = max(camera.focus_distance, 0.0);
+    let along = dot(ray.direction, camera.forward);
+    if camera.projection != PROJECTION_STEREOGRAPHIC && along > 0.0 {
+        focus_distance /= along;
     }
+    let focus_point = ray.origin + ray.direction * focus_distance;
+
+    // a random direction with its part along the view direction removed is still evenly spread over the
+    // directions across it, and the cube root of the radius spreads the points evenly through the ball
+    let direction = random_direction();
+    let across = slice_direction(direction - camera.forward * dot(direction, camera.forward));
+    let lens_offset = safe_normalize(across) * camera.aperture * pow(next_random(), 1.0 / 3.0);
+
+    var result: Ray;
+    result.origin = ray.origin + lens_offset;
+    result.direction = safe_normalize(focus_point - result.origin);
+    return result;
+}
+
+const GRID_COLOR: vec3<f32> = vec3<f32>(0.8);
+const GRID_OPACITY: f32 = 0.4;
+const AXIS_COLORS: array<vec3<f32>, 4> = array<vec3<f32>, 4>(
+    vec3<f32>(1.0, 0.2, 0.2),
+    vec3<f32>(0.2, 1.0, 0.2),
+    vec3<f32>(0.2, 0.4, 1.0),
+    vec3<f32>(1.0, 0.9, 0.2),
+);
+
+// where the ray crosses the y = 0 hyperplane, or a negative distance if it never does
+fn intersect_grid(ray: Ray) -> f32 {
+    if ray.direction.y == 0.0 {
+        return - 1.0;
+    }
+    return - ray.origin.y / ray.direction.y;
 }
 
-@compute @workgroup_size(16, 16, 1)
+// draws a grid on the y = 0 hyperplane at every integer x, z and w, and the positive axes from the origin,
+// both are hidden behind the objects in the scene and stay one pixel wide however far away they are
+fn overlay(coords: vec2<u32>, size: vec2<u32>, color: vec3<f32>) -> vec3<f32> {
+    let ray = camera_ray(coords, size, vec2<f32>(0.5));
+    let hit = intersect_view(ray);
+    let max_distance = select(3.4e38, hit.distance, hit.hit);
+    var result = color;
+
+    // a grid line is drawn where the grid coordinates of the next pixel over fall into a different cell,
+    // this keeps the lines thin, and a coordinate that is the same across the whole view never draws a line
+    let distance = intersect_grid(ray);
+    if distance > 0.0 && distance < max_distance {
+        let cell = floor((ray.origin + ray.direction * distance).xzw);
+        var crosses = false;
+        for (var i = 0u; i < 2u; i++) {
+            var offset = vec2<f32>(0.5);
+            offset[i] = 1.5;
+            let neighbour_ray = camera_ray(coords, size, offset);
+            let neighbour_distance = intersect_grid(neighbour_ray);
+            if neighbour_distance > 0.0 {
+                let neighbour_cell = floor((neighbour_ray.origin + neighbour_ray.direction * neighbour_distance).xzw);
+                crosses = crosses || any(cell != neighbour_cell);
+            }
+        }
+        if crosses {
+            result = mix(result, GRID_COLOR, GRID_OPACITY);
+        }
+    }
+
+    // the size of a pixel one unit in front of a perspective camera, or anywhere in front of an orthographic one
+    let camera = push_constants.camera;
+    var pixel_size = 2.0 * tan(camera.fov * 0.5) / f32(size.y);
+    if camera.projection == PROJECTION_ORTHOGRAPHIC {
+        pixel_size = camera.orthographic_height / f32(size.y);
+    }
+
+    for (var axis = 0u; axis < 4u; axis++) {
+        var axis_direction = vec4<f32>(0.0);
+        axis_direction[axis] = 1.0;
+
+        // the closest points between the ray and the axis, both directions are normalized
+        let b = dot(ray.direction, axis_direction);
+        let denominator = 1.0 - b * b;
+        if denominator < 1e-6 {
+            continue;
+        }
+        let d = dot(ray.direction, ray.origin);
+        let e = dot(axis_direction, ray.origin);
+        let t = (b * e - d) / denominator;
+        let s = (e - b * d) / denominator;
+        if t <= 0.0 || s <= 0.0 || t >= max_distance {
+            continue;
+        }
+
+        var width = pixel_size;
+        if camera.projection != PROJECTION_ORTHOGRAPHIC {
+            width *= t;
+        }
+        if length(ray.origin + ray.direction * t - axis_direction * s) < width {
+            result = AXIS_COLORS[axis];
+        }
+    }
+
+    return result;
+}
+
+const SELECTION_COLOR: vec3<f32> = vec3<f32>(1.0, 0.6, 0.1);
+const SELECTION_TINT: f32 = 0.15;
+
+// whether the ray through the pixel at coords hits the selected object before anything else
+fn hits_selection(coords: vec2<u32>, size: vec2<u32>, offset: vec2<f32>) -> bool {
+    let hit = intersect_view(camera_ray(coords, size, offset));
+    return hit.hit && hit.object_kind == info.selected_object_kind && hit.object_index == info.selected_object_index;
+}
+
+// tints the selected object and outlines it where the pixels next to this one disagree about whether it is visible,
+// which draws its silhouette along with the edges where other objects cover it
+fn highlight_selection(coords: vec2<u32>, size: vec2<u32>, color: vec3<f32>) -> vec3<f32> {
+    let selected = hits_selection(coords, size, vec2<f32>(0.5));
+    var edge = false;
+    for (var i = 0u; i < 2u; i++) {
+        for (var side = 0u; side < 2u; side++) {
+            var offset = vec2<f32>(0.5);
+            offset[i] += select(- 1.0, 1.0, side == 1u);
+            edge = edge || hits_selection(coords, size, offset) != selected;
+        }
+    }
+
+    if edge {
+        return SELECTION_COLOR;
+    }
+    if selected {
+        return mix(color, SELECTION_COLOR, SELECTION_TINT);
+    }
+    return color;
+}
+
+// the width and height of the tile of pixels each workgroup traces, set by RenderState to suit the adapter
+override WORKGROUP_SIZE: u32 = 16u;
+
+@compute @workgroup_size(WORKGROUP_SIZE, WORKGROUP_SIZE, 1)
 fn ray_trace(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let size = textureDimensions(output_texture);
     let coords = global_id.xy;
@@ -175,16 +1234,96 @@ fn ray_trace(@builtin(global_invocation_id) global_id: vec3<u32>) {
     if coords.x >= size.x || coords.y >= size.y {
         return;
     }
+    view = push_constants.camera.view;
 
-    let aspect = f32(size.x) / f32(size.y);
-    let uv = ((vec2<f32>(coords) + 0.5) / vec2<f32>(size)) * 2.0 - 1.0;
+    let accumulate = push_constants.accumulate != 0u;
+    let samples_per_pixel = max(info.samples_per_pixel, 1u);
 
-    var ray: Ray;
-    ray.origin = camera.position;
-    ray.direction = normalize(camera.forward + camera.up * uv.y + camera.right * uv.x * aspect);
+    var color = vec3<f32>(0.0);
+    for (var sample_y = 0u; sample_y < samples_per_pixel; sample_y++) {
+        for (var sample_x = 0u; sample_x < samples_per_pixel; sample_x++) {
+            // jitter each sample within its cell of the grid, a single sample stays in the center of the pixel
+            // unless it is being accumulated, in which case every frame needs to sample somewhere new
+            var seed = hash(coords.x + hash(coords.y + hash(sample_x + sample_y * samples_per_pixel)));
+            if accumulate {
+                seed = hash(seed + push_constants.frame_index);
+            }
+            var jitter = vec2<f32>(0.5);
+            if samples_per_pixel > 1u || accumulate {
+                jitter = vec2<f32>(random_float(seed), random_float(hash(seed)));
+            }
+            random_state = hash(hash(seed));
+            let offset = (vec2<f32>(f32(sample_x), f32(sample_y)) + jitter) / f32(samples_per_pixel);
+            color += trace_ray(defocus(camera_ray(coords, size, offset)));
+        }
+    }
+    color /= f32(samples_per_pixel * samples_per_pixel);
+
+    if info.show_grid != 0u {
+        color = overlay(coords, size, color);
+    }
+
+    if info.selected_object_kind != NO_OBJECT {
+        color = highlight_selection(coords, size, color);
+    }
+
+    if accumulate {
+        let index = coords.y * size.x + coords.x;
+        var sum = color;
+        if push_constants.frame_index > 0u {
+            sum += accumulation[index].rgb;
+        }
+        accumulation[index] = vec4<f32>(sum, 1.0);
+        color = sum / f32(push_constants.frame_index + 1u);
+    }
+    // the colors are left unclamped so that the tone mapping in full_screen_quad.wgsl can compress the bright parts
+    textureStore(output_texture, coords, vec4<f32>(max(color, vec3<f32>(0.0)), 1.0));
+}
+
+struct Pick {
+    // the ray to trace for raycast
+    origin: vec4<f32>,
+    direction: vec4<f32>,
+    // the pixel to pick, in the same coordinates that ray_trace writes to
+    pixel: vec2<u32>,
+    // written back with the object that was hit, or NO_OBJECT if nothing was
+    object_kind: u32,
+    object_index: u32,
+    distance: f32,
+    position: vec4<f32>,
+    normal: vec4<f32>,
+}
 
-    let color = trace_ray(ray);
-    textureStore(output_texture, coords, vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
+@group(4) @binding(0)
+var<storage, read_write> pick_data: Pick;
+
+fn write_pick(hit: Hit) {
+    if hit.hit {
+        pick_data.object_kind = hit.object_kind;
+        pick_data.object_index = hit.object_index;
+        pick_data.distance = hit.distance;
+        pick_data.position = hit.position;
+        pick_data.normal = hit.normal;
+    }
+    else {
+        pick_data.object_kind = NO_OBJECT;
+        pick_data.object_index = NO_OBJECT;
+    }
+}
+
+@compute @workgroup_size(1, 1, 1)
+fn pick() {
+    let size = textureDimensions(output_texture);
+    view = push_constants.camera.view;
+    write_pick(intersect_view(camera_ray(pick_data.pixel, size, vec2<f32>(0.5))));
+}
+
+@compute @workgroup_size(1, 1, 1)
+fn raycast() {
+    var ray: Ray;
+    ray.origin = pick_data.origin;
+    ray.direction = pick_data.direction;
+    write_pick(intersect_scene(ray));
 }
 
 struct Transform {
@@ -318,20 +1457,3 @@ fn transform_position(transform: Transform) -> vec4<f32> {
     result.w = (2.0 * _0 * _4) + (2.0 * _1 * _7) + (2.0 * _10 * _3) + (2.0 * _11 * _15) + (2.0 * _12 * _5) + (2.0 * _13 * _6) + (2.0 * _14 * _8) + (2.0 * _2 * _9);
     return result;
 }
-
-fn transform_y(transform: Transform) -> vec4<f32> {
-    let _0 = transform.s;
-    let _1 = transform.e1e2;
-    let _2 = transform.e1e3;
-    let _3 = transform.e1e4;
-    let _4 = transform.e2e3;
-    let _5 = transform.e2e4;
-    let _6 = transform.e3e4;
-    let _7 = transform.e1e2e3e4;
-    var result: vec4<f32>;
-    result.x = (- 2.0 * _0 * _1) + (- 2.0 * _2 * _4) + (- 2.0 * _3 * _5) + (- 2.0 * _6 * _7);
-    result.y = (1.0) + (- 2.0 * _1 * _1) + (- 2.0 * _4 * _4) + (- 2.0 * _5 * _5) + (- 2.0 * _7 * _7);
-    result.z = (- 2.0 * _1 * _2) + (- 2.0 * _5 * _6) + (2.0 * _0 * _4) + (2.0 * _3 * _7);
-    result.w = (- 2.0 * _1 * _3) + (- 2.0 * _2 * _7) + (2.0 * _0 * _5) + (2.0 * _4 * _6);
-    return result;
-}
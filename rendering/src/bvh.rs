@@ -0,0 +1,103 @@
+use crate::objects::Hypersphere;
+use cgmath::{Array, Vector4};
+
+/// Scenes with fewer hyperspheres than this are faster to render by testing every hypersphere than
+/// by walking a hierarchy, so no hierarchy is built for them.
+pub(crate) const BVH_MIN_HYPERSPHERES: usize = 32;
+
+/// A node of the bounding volume hierarchy over the hyperspheres.
+///
+/// The nodes are stored depth first with every hypersphere in its own leaf, so the children of a
+/// node directly follow it and the shader can walk the tree without a stack: it moves to the next
+/// node when it enters a node's bounds, and jumps to `skip` when it misses them or finishes a leaf.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct BvhNode {
+    pub min: Vector4<f32>,
+    pub max: Vector4<f32>,
+    /// The index of the hypersphere in this leaf, or `u32::MAX` if this node has children.
+    pub hypersphere: u32,
+    /// The index of the first node after this one and all of its children.
+    pub skip: u32,
+    pub _padding: [u32; 2],
+}
+
+unsafe impl bytemuck::Zeroable for BvhNode {}
+unsafe impl bytemuck::Pod for BvhNode {}
+
+struct Item {
+    index: u32,
+    center: Vector4<f32>,
+    min: Vector4<f32>,
+    max: Vector4<f32>,
+}
+
+pub(crate) fn build_bvh(hyperspheres: &[Hypersphere]) -> Vec<BvhNode> {
+    let mut items = hyperspheres
+        .iter()
+        .enumerate()
+        .map(|(index, hypersphere)| {
+            let center = hypersphere.transform.position();
+            // the scale may stretch the hypersphere in any direction once it is rotated, so this
+            // bounds it by its largest axis
+            let scale = hypersphere.scale.map(f32::abs);
+            let radius = Vector4::from_value(
+                hypersphere.radius.abs() * scale.x.max(scale.y).max(scale.z).max(scale.w),
+            );
+            Item {
+                index: index.try_into().unwrap(),
+                center,
+                min: center - radius,
+                max: center + radius,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut nodes = Vec::with_capacity((2 * items.len()).saturating_sub(1));
+    if !items.is_empty() {
+        build_node(&mut nodes, &mut items);
+    }
+    nodes
+}
+
+fn build_node(nodes: &mut Vec<BvhNode>, items: &mut [Item]) {
+    let (min, max) = bounds(items.iter().map(|item| (item.min, item.max)));
+
+    let index = nodes.len();
+    nodes.push(BvhNode {
+        min,
+        max,
+        hypersphere: match items {
+            [item] => item.index,
+            _ => u32::MAX,
+        },
+        skip: 0,
+        _padding: [0; 2],
+    });
+
+    if items.len() > 1 {
+        // split the hyperspheres in half along the axis their centers are most spread out on
+        let (center_min, center_max) = bounds(items.iter().map(|item| (item.center, item.center)));
+        let extents = center_max - center_min;
+        let axis = (0..4)
+            .max_by(|&a, &b| extents[a].total_cmp(&extents[b]))
+            .unwrap();
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |a, b| a.center[axis].total_cmp(&b.center[axis]));
+
+        let (left, right) = items.split_at_mut(middle);
+        build_node(nodes, left);
+        build_node(nodes, right);
+    }
+
+    nodes[index].skip = nodes.len().try_into().unwrap();
+}
+
+fn bounds(
+    mut boxes: impl Iterator<Item = (Vector4<f32>, Vector4<f32>)>,
+) -> (Vector4<f32>, Vector4<f32>) {
+    let first = boxes.next().unwrap();
+    boxes.fold(first, |(min, max), (box_min, box_max)| {
+        (min.zip(box_min, f32::min), max.zip(box_max, f32::max))
+    })
+}
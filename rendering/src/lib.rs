@@ -1,11 +1,18 @@
+mod bvh;
 pub mod objects;
+mod pass_timer;
 mod render_target;
 
-pub use render_target::RenderTarget;
+pub use render_target::{RenderTarget, pixels_to_rgba8};
 
-use crate::objects::{Hyperplane, Hypersphere};
+use crate::{
+    bvh::{BVH_MIN_HYPERSPHERES, BvhNode, build_bvh},
+    objects::{CsgPair, Hyperplane, Hypersphere, ObjectId, Spherinder, Tesseract, Torus},
+};
+use cgmath::InnerSpace;
 use eframe::{egui, wgpu};
 use math::Transform;
+use serde::{Deserialize, Serialize};
 use std::mem::offset_of;
 
 #[derive(Debug, Clone, Copy)]
@@ -15,76 +22,569 @@ struct Camera {
     pub forward: cgmath::Vector4<f32>,
     pub up: cgmath::Vector4<f32>,
     pub right: cgmath::Vector4<f32>,
+    pub fov: f32,
+    pub projection: u32,
+    pub orthographic_height: f32,
+    pub near: f32,
+    pub pole: cgmath::Vector4<f32>,
+    pub far: f32,
+    pub view: u32,
+    pub aperture: f32,
+    pub focus_distance: f32,
 }
 
 unsafe impl bytemuck::Zeroable for Camera {}
 unsafe impl bytemuck::Pod for Camera {}
 
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct PushConstants {
+    camera: Camera,
+    frame_index: u32,
+    accumulate: u32,
+    render_mode: u32,
+    slice_w: f32,
+}
+
+unsafe impl bytemuck::Zeroable for PushConstants {}
+unsafe impl bytemuck::Pod for PushConstants {}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 struct SceneInfo {
     hyperspheres_count: u32,
     hyperplanes_count: u32,
+    tesseracts_count: u32,
+    lights_count: u32,
+    headlamp_color: cgmath::Vector3<f32>,
+    headlamp_intensity: f32,
+    shadow_bias: f32,
+    max_bounces: u32,
+    samples_per_pixel: u32,
+    hypersphere_bvh_nodes_count: u32,
+    sky_direction: cgmath::Vector4<f32>,
+    sky_zenith: cgmath::Vector3<f32>,
+    show_grid: u32,
+    sky_horizon: cgmath::Vector3<f32>,
+    tori_count: u32,
+    torus_max_steps: u32,
+    selected_object_kind: u32,
+    selected_object_index: u32,
+    shadow_samples: u32,
+    sun_direction: cgmath::Vector4<f32>,
+    sun_color: cgmath::Vector3<f32>,
+    sun_intensity: f32,
+    fog_color: cgmath::Vector3<f32>,
+    fog_start: f32,
+    fog_density: f32,
+    spherinders_count: u32,
+    csg_pairs_count: u32,
+    w_falloff: f32,
+    has_environment: u32,
+    _padding: [u32; 3],
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Light {
+    pub position: cgmath::Vector4<f32>,
+    pub color: cgmath::Vector3<f32>,
+    pub intensity: f32,
+}
+
+unsafe impl bytemuck::Zeroable for Light {}
+unsafe impl bytemuck::Pod for Light {}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct ToneMappingInfo {
+    mode: u32,
+    exposure: f32,
+}
+
+unsafe impl bytemuck::Zeroable for ToneMappingInfo {}
+unsafe impl bytemuck::Pod for ToneMappingInfo {}
+
+/// The buffer shared by the `pick` and `raycast` entry points, which trace a single ray and write
+/// back what it hit.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct Pick {
+    origin: cgmath::Vector4<f32>,
+    direction: cgmath::Vector4<f32>,
+    pixel: [u32; 2],
+    object_kind: u32,
+    object_index: u32,
+    distance: f32,
+    _padding: [f32; 3],
+    position: cgmath::Vector4<f32>,
+    normal: cgmath::Vector4<f32>,
+}
+
+unsafe impl bytemuck::Zeroable for Pick {}
+unsafe impl bytemuck::Pod for Pick {}
+
+impl Pick {
+    fn object(&self) -> Option<ObjectId> {
+        match self.object_kind {
+            0 => Some(ObjectId::Hypersphere(self.object_index)),
+            1 => Some(ObjectId::Hyperplane(self.object_index)),
+            2 => Some(ObjectId::Tesseract(self.object_index)),
+            3 => Some(ObjectId::Torus(self.object_index)),
+            4 => Some(ObjectId::Spherinder(self.object_index)),
+            5 => Some(ObjectId::CsgPair(self.object_index)),
+            _ => None,
+        }
+    }
+}
+
+/// Where a ray traced by [`RenderState::raycast`] first hit the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// How far along the ray the hit is, in world units.
+    pub distance: f32,
+    pub position: cgmath::Vector4<f32>,
+    /// The normal of the surface that was hit, facing back towards the ray.
+    pub normal: cgmath::Vector4<f32>,
+    pub object: ObjectId,
 }
 
+/// GPU state shared by every viewport.
+///
+/// The pipelines and bind group layouts live as long as the `RenderState`. The object buffers
+/// belong to the current scene: the `update_*` methods replace them when they need to grow or when
+/// most of them has gone unused (the old buffer is dropped with its bind group), and
+/// [`RenderState::release_scene_resources`] shrinks them back down when the scene is discarded.
 pub struct RenderState {
     scene_info_buffer: wgpu::Buffer,
+    /// The image the sky is looked up in, a single texel while there is none.
+    environment_texture: wgpu::Texture,
+    scene_info_bind_group_layout: wgpu::BindGroupLayout,
     scene_info_bind_group: wgpu::BindGroup,
 
     hyperspheres_buffer: wgpu::Buffer,
     hyperplanes_buffer: wgpu::Buffer,
+    tesseracts_buffer: wgpu::Buffer,
+    tori_buffer: wgpu::Buffer,
+    spherinders_buffer: wgpu::Buffer,
+    csg_pairs_buffer: wgpu::Buffer,
+    hypersphere_bvh_buffer: wgpu::Buffer,
     objects_bind_group_layout: wgpu::BindGroupLayout,
     objects_bind_group: wgpu::BindGroup,
 
+    lights_buffer: wgpu::Buffer,
+    lights_bind_group_layout: wgpu::BindGroupLayout,
+    lights_bind_group: wgpu::BindGroup,
+
+    pick_buffer: wgpu::Buffer,
+    pick_readback_buffer: wgpu::Buffer,
+    pick_bind_group: wgpu::BindGroup,
+    pick_compute_pipeline: wgpu::ComputePipeline,
+    /// Bound in place of a render target, which a raycast doesn't need.
+    empty_bind_group: wgpu::BindGroup,
+    raycast_compute_pipeline: wgpu::ComputePipeline,
+
+    tone_mapping_buffer: wgpu::Buffer,
+    tone_mapping_bind_group: wgpu::BindGroup,
+
+    /// The width and height of the tile of pixels traced by each ray tracing workgroup.
+    workgroup_size: u32,
+    ray_tracing_shader: wgpu::ShaderModule,
+    ray_tracing_compute_pipeline_layout: wgpu::PipelineLayout,
     ray_tracing_compute_pipeline: wgpu::ComputePipeline,
     full_screen_quad_render_pipeline: wgpu::RenderPipeline,
 }
 
 pub fn register_rendering_state(cc: &eframe::CreationContext<'_>) {
     let eframe::egui_wgpu::RenderState {
+        adapter,
         device,
         renderer,
         target_format,
         ..
     } = cc.wgpu_render_state.as_ref().unwrap();
 
-    let scene_info_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Scene Info Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-    let scene_info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Scene Info Buffer"),
-        size: size_of::<SceneInfo>().try_into().unwrap(),
-        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    renderer
+        .write()
+        .callback_resources
+        .insert(RenderState::new(adapter, device, *target_format));
+}
+
+/// Picks the tile size for the ray tracing shader: 16x16 where the device allows it, but 8x8 on
+/// integrated GPUs, which tend to run the smaller tiles faster, and 32x32 on software renderers,
+/// which llvmpipe ran about 4 times faster than 16x16. The headless `--benchmark` option of the app
+/// times every size on a device, to check this choice against.
+fn workgroup_size(adapter: &wgpu::Adapter, device: &wgpu::Device) -> u32 {
+    let preferred = match adapter.get_info().device_type {
+        wgpu::DeviceType::IntegratedGpu => 8,
+        wgpu::DeviceType::Cpu => 32,
+        _ => 16,
+    };
+    [preferred, 16, 8]
+        .into_iter()
+        .find(|&size| supports_workgroup_size(device, size))
+        .unwrap_or(8)
+}
+
+/// Whether `device` can run the ray tracing shader with `size` by `size` tiles.
+pub fn supports_workgroup_size(device: &wgpu::Device, size: u32) -> bool {
+    let limits = device.limits();
+    size > 0
+        && size * size <= limits.max_compute_invocations_per_workgroup
+        && size <= limits.max_compute_workgroup_size_x
+        && size <= limits.max_compute_workgroup_size_y
+}
+
+fn ray_tracing_compute_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    workgroup_size: u32,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Ray Tracing Compute Pipeline"),
+        layout: Some(layout),
+        module: shader,
+        entry_point: Some("ray_trace"),
+        compilation_options: wgpu::PipelineCompilationOptions {
+            constants: &[("WORKGROUP_SIZE", workgroup_size.into())],
+            ..Default::default()
+        },
+        cache: Default::default(),
+    })
+}
+
+/// Removes the [`RenderState`] registered by [`register_rendering_state`], dropping all of its GPU
+/// resources once the returned value is dropped.
+pub fn unregister_rendering_state(
+    render_state: &eframe::egui_wgpu::RenderState,
+) -> Option<RenderState> {
+    render_state.renderer.write().callback_resources.remove()
+}
+
+fn hyperspheres_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Hyperspheres Buffer"),
+        size: (length.max(1) * size_of::<Hypersphere>())
+            .try_into()
+            .unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn hyperplanes_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Hyperplanes Buffer"),
+        size: (length.max(1) * size_of::<Hyperplane>())
+            .try_into()
+            .unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn tesseracts_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Tesseracts Buffer"),
+        size: (length.max(1) * size_of::<Tesseract>()).try_into().unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn tori_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Tori Buffer"),
+        size: (length.max(1) * size_of::<Torus>()).try_into().unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false,
-    });
-    let scene_info_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    })
+}
+
+fn spherinders_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Spherinders Buffer"),
+        size: (length.max(1) * size_of::<Spherinder>())
+            .try_into()
+            .unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn csg_pairs_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("CSG Pairs Buffer"),
+        size: (length.max(1) * size_of::<CsgPair>()).try_into().unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn hypersphere_bvh_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Hypersphere BVH Buffer"),
+        size: (length.max(1) * size_of::<BvhNode>()).try_into().unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Whether a buffer of elements that are `size` bytes needs to be recreated to hold `len` of them,
+/// see [`outside_capacity`].
+fn should_reallocate(buffer: &wgpu::Buffer, len: usize, size: usize) -> bool {
+    outside_capacity(len, (buffer.size() / size as u64) as usize)
+}
+
+/// Whether `len` elements no longer belong in a buffer with room for `capacity` of them. Buffers
+/// grow as soon as the elements don't fit, but only shrink once less than a quarter of them is in
+/// use, so a scene that keeps changing size by a few objects doesn't reallocate every frame.
+fn outside_capacity(len: usize, capacity: usize) -> bool {
+    len > capacity || (capacity > 1 && len < capacity / 4)
+}
+
+/// The texture is `rgba32float` to keep the full range of HDR images, which can't be filtered by a
+/// sampler without an optional feature, so the shader filters it itself.
+fn environment_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Environment Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+fn scene_info_bind_group(
+    device: &wgpu::Device,
+    scene_info_bind_group_layout: &wgpu::BindGroupLayout,
+    scene_info_buffer: &wgpu::Buffer,
+    environment_texture: &wgpu::Texture,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Scene Info Bind Group"),
-        layout: &scene_info_bind_group_layout,
+        layout: scene_info_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: scene_info_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(
+                    &environment_texture.create_view(&Default::default()),
+                ),
+            },
+        ],
+    })
+}
+
+fn lights_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Lights Buffer"),
+        size: (length.max(1) * size_of::<Light>()).try_into().unwrap(),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn lights_bind_group(
+    device: &wgpu::Device,
+    lights_bind_group_layout: &wgpu::BindGroupLayout,
+    lights_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Lights Bind Group"),
+        layout: lights_bind_group_layout,
         entries: &[wgpu::BindGroupEntry {
             binding: 0,
-            resource: scene_info_buffer.as_entire_binding(),
+            resource: lights_buffer.as_entire_binding(),
         }],
-    });
+    })
+}
 
-    let hyperspheres_buffer = hyperspheres_buffer(device, 0);
-    let hyperplanes_buffer = hyperplanes_buffer(device, 0);
+/// `buffers` are the objects buffers in the order of their bindings, see
+/// [`RenderState::objects_buffers`].
+fn objects_bind_group(
+    device: &wgpu::Device,
+    objects_bind_group_layout: &wgpu::BindGroupLayout,
+    buffers: [&wgpu::Buffer; 7],
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Objects Bind Group"),
+        layout: objects_bind_group_layout,
+        entries: &buffers
+            .into_iter()
+            .zip(0..)
+            .map(|(buffer, binding)| wgpu::BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+impl RenderState {
+    /// Creates the pipelines and an empty scene. `target_format` is the format of the textures
+    /// that the views are painted into.
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+    ) -> Self {
+        let workgroup_size = workgroup_size(adapter, device);
 
-    let objects_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Objects Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
+        let scene_info_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Scene Info Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let scene_info_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scene Info Buffer"),
+            size: size_of::<SceneInfo>().try_into().unwrap(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let environment_texture = environment_texture(device, 1, 1);
+        let scene_info_bind_group = scene_info_bind_group(
+            device,
+            &scene_info_bind_group_layout,
+            &scene_info_buffer,
+            &environment_texture,
+        );
+
+        let hyperspheres_buffer = hyperspheres_buffer(device, 0);
+        let hyperplanes_buffer = hyperplanes_buffer(device, 0);
+        let tesseracts_buffer = tesseracts_buffer(device, 0);
+        let tori_buffer = tori_buffer(device, 0);
+        let spherinders_buffer = spherinders_buffer(device, 0);
+        let csg_pairs_buffer = csg_pairs_buffer(device, 0);
+        let hypersphere_bvh_buffer = hypersphere_bvh_buffer(device, 0);
+
+        let objects_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Objects Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        let objects_bind_group = objects_bind_group(
+            device,
+            &objects_bind_group_layout,
+            [
+                &hyperspheres_buffer,
+                &hyperplanes_buffer,
+                &tesseracts_buffer,
+                &hypersphere_bvh_buffer,
+                &tori_buffer,
+                &spherinders_buffer,
+                &csg_pairs_buffer,
+            ],
+        );
+
+        let lights_buffer = lights_buffer(device, 0);
+        let lights_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Lights Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
@@ -93,190 +593,801 @@ pub fn register_rendering_state(cc: &eframe::CreationContext<'_>) {
                         min_binding_size: None,
                     },
                     count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
+                }],
+            });
+        let lights_bind_group =
+            lights_bind_group(device, &lights_bind_group_layout, &lights_buffer);
+
+        let pick_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Buffer"),
+            size: size_of::<Pick>().try_into().unwrap(),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let pick_readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: pick_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let pick_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Pick Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
                     visibility: wgpu::ShaderStages::COMPUTE,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
                     count: None,
-                },
-            ],
+                }],
+            });
+        let pick_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Pick Bind Group"),
+            layout: &pick_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: pick_buffer.as_entire_binding(),
+            }],
         });
-    let objects_bind_group = objects_bind_group(
-        device,
-        &objects_bind_group_layout,
-        &hyperspheres_buffer,
-        &hyperplanes_buffer,
-    );
-
-    let ray_tracing_shader =
-        device.create_shader_module(wgpu::include_wgsl!("../shaders/ray_tracing.wgsl"));
-    let ray_tracing_compute_pipeline_layout =
-        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Ray Tracing Compute Pipeline Layout"),
-            bind_group_layouts: &[
-                &render_target::write_bind_group_layout(device),
-                &scene_info_bind_group_layout,
-                &objects_bind_group_layout,
-            ],
-            push_constant_ranges: &[wgpu::PushConstantRange {
-                stages: wgpu::ShaderStages::COMPUTE,
-                range: 0..size_of::<Camera>() as _,
+
+        let write_bind_group_layout = render_target::write_bind_group_layout(device);
+        let ray_tracing_shader =
+            device.create_shader_module(wgpu::include_wgsl!("../shaders/ray_tracing.wgsl"));
+        let ray_tracing_compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Ray Tracing Compute Pipeline Layout"),
+                bind_group_layouts: &[
+                    &write_bind_group_layout,
+                    &scene_info_bind_group_layout,
+                    &objects_bind_group_layout,
+                    &lights_bind_group_layout,
+                ],
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..size_of::<PushConstants>() as _,
+                }],
+            });
+        let ray_tracing_compute_pipeline = ray_tracing_compute_pipeline(
+            device,
+            &ray_tracing_compute_pipeline_layout,
+            &ray_tracing_shader,
+            workgroup_size,
+        );
+
+        let pick_compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Pick Compute Pipeline Layout"),
+                bind_group_layouts: &[
+                    &write_bind_group_layout,
+                    &scene_info_bind_group_layout,
+                    &objects_bind_group_layout,
+                    &lights_bind_group_layout,
+                    &pick_bind_group_layout,
+                ],
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..size_of::<PushConstants>() as _,
+                }],
+            });
+        let pick_compute_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Pick Compute Pipeline"),
+                layout: Some(&pick_compute_pipeline_layout),
+                module: &ray_tracing_shader,
+                entry_point: Some("pick"),
+                compilation_options: Default::default(),
+                cache: Default::default(),
+            });
+
+        let empty_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Empty Bind Group Layout"),
+                entries: &[],
+            });
+        let empty_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Empty Bind Group"),
+            layout: &empty_bind_group_layout,
+            entries: &[],
+        });
+        let raycast_compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Raycast Compute Pipeline Layout"),
+                bind_group_layouts: &[
+                    &empty_bind_group_layout,
+                    &scene_info_bind_group_layout,
+                    &objects_bind_group_layout,
+                    &lights_bind_group_layout,
+                    &pick_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let raycast_compute_pipeline =
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("Raycast Compute Pipeline"),
+                layout: Some(&raycast_compute_pipeline_layout),
+                module: &ray_tracing_shader,
+                entry_point: Some("raycast"),
+                compilation_options: Default::default(),
+                cache: Default::default(),
+            });
+
+        let tone_mapping_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Tone Mapping Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        let tone_mapping_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Tone Mapping Buffer"),
+            size: size_of::<ToneMappingInfo>().try_into().unwrap(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let tone_mapping_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Tone Mapping Bind Group"),
+            layout: &tone_mapping_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: tone_mapping_buffer.as_entire_binding(),
             }],
         });
-    let ray_tracing_compute_pipeline =
-        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Ray Tracing Compute Pipeline"),
-            layout: Some(&ray_tracing_compute_pipeline_layout),
-            module: &ray_tracing_shader,
-            entry_point: Some("ray_trace"),
-            compilation_options: Default::default(),
-            cache: Default::default(),
+
+        let full_screen_quad_shader =
+            device.create_shader_module(wgpu::include_wgsl!("../shaders/full_screen_quad.wgsl"));
+        let full_screen_quad_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Full Screen Quad Render Pipeline Layout"),
+                bind_group_layouts: &[
+                    &render_target::sample_bind_group_layout(device),
+                    &tone_mapping_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let full_screen_quad_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Full Screen Quad Render Pipeline"),
+                layout: Some(&full_screen_quad_render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &full_screen_quad_shader,
+                    entry_point: Some("vertex"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &full_screen_quad_shader,
+                    entry_point: Some("fragment"),
+                    compilation_options: wgpu::PipelineCompilationOptions {
+                        constants: &[(
+                            "ENCODE_SRGB",
+                            if target_format.is_srgb() { 0.0 } else { 1.0 },
+                        )],
+                        ..Default::default()
+                    },
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: target_format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::all(),
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            });
+
+        Self {
+            scene_info_buffer,
+            environment_texture,
+            scene_info_bind_group_layout,
+            scene_info_bind_group,
+
+            hyperspheres_buffer,
+            hyperplanes_buffer,
+            tesseracts_buffer,
+            tori_buffer,
+            spherinders_buffer,
+            csg_pairs_buffer,
+            hypersphere_bvh_buffer,
+            objects_bind_group_layout,
+            objects_bind_group,
+
+            lights_buffer,
+            lights_bind_group_layout,
+            lights_bind_group,
+
+            pick_buffer,
+            pick_readback_buffer,
+            pick_bind_group,
+            pick_compute_pipeline,
+            empty_bind_group,
+            raycast_compute_pipeline,
+
+            tone_mapping_buffer,
+            tone_mapping_bind_group,
+
+            workgroup_size,
+            ray_tracing_shader,
+            ray_tracing_compute_pipeline_layout,
+            ray_tracing_compute_pipeline,
+            full_screen_quad_render_pipeline,
+        }
+    }
+
+    /// The width and height of the tiles the views are ray traced in.
+    pub fn workgroup_size(&self) -> u32 {
+        self.workgroup_size
+    }
+
+    /// Recreates the ray tracing pipeline to trace `workgroup_size` by `workgroup_size` tiles
+    /// instead of the size picked for the device, which has to be
+    /// [`supports_workgroup_size`].
+    pub fn set_workgroup_size(&mut self, device: &wgpu::Device, workgroup_size: u32) {
+        assert!(supports_workgroup_size(device, workgroup_size));
+        self.workgroup_size = workgroup_size;
+        self.ray_tracing_compute_pipeline = ray_tracing_compute_pipeline(
+            device,
+            &self.ray_tracing_compute_pipeline_layout,
+            &self.ray_tracing_shader,
+            workgroup_size,
+        );
+    }
+
+    /// Ray traces a view into its render target without going through egui, so that it can be
+    /// rendered without a window.
+    pub fn render(&self, device: &wgpu::Device, queue: &wgpu::Queue, render_data: &RenderData) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Ray Tracing Encoder"),
+        });
+        self.ray_trace(&mut encoder, queue, render_data);
+        queue.submit([encoder.finish()]);
+    }
+
+    fn ray_trace(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+        render_data: &RenderData,
+    ) {
+        let timer = render_data.render_target.timer.as_ref();
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Ray Tracing Compute Pass"),
+            timestamp_writes: timer.map(|timer| timer.timestamp_writes()),
+        });
+
+        compute_pass.set_pipeline(&self.ray_tracing_compute_pipeline);
+        compute_pass.set_bind_group(0, &render_data.render_target.write_bind_group, &[]);
+        compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[]);
+        compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+        compute_pass.set_bind_group(3, &self.lights_bind_group, &[]);
+
+        compute_pass.set_push_constants(0, bytemuck::bytes_of(&render_data.push_constants()));
+
+        let (width, height) = render_data.render_target.size();
+        compute_pass.dispatch_workgroups(
+            width.div_ceil(self.workgroup_size),
+            height.div_ceil(self.workgroup_size),
+            1,
+        );
+        drop(compute_pass);
+
+        if let Some(timer) = timer {
+            timer.resolve(encoder, queue);
+        }
+    }
+
+    /// Frees the GPU memory of a scene that is being discarded, before the next one is uploaded.
+    ///
+    /// The object, BVH and light buffers are replaced with empty ones, together with the bind
+    /// groups that held on to the old ones, and every object and light count is set to 0. The
+    /// pipelines, bind group layouts, the scene info, pick and tone mapping buffers and the
+    /// environment texture are kept. The environment only changes through
+    /// [`RenderState::set_environment`].
+    pub fn release_scene_resources(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.hyperspheres_buffer = hyperspheres_buffer(device, 0);
+        self.hyperplanes_buffer = hyperplanes_buffer(device, 0);
+        self.tesseracts_buffer = tesseracts_buffer(device, 0);
+        self.tori_buffer = tori_buffer(device, 0);
+        self.spherinders_buffer = spherinders_buffer(device, 0);
+        self.csg_pairs_buffer = csg_pairs_buffer(device, 0);
+        self.hypersphere_bvh_buffer = hypersphere_bvh_buffer(device, 0);
+        self.objects_bind_group = objects_bind_group(
+            device,
+            &self.objects_bind_group_layout,
+            self.objects_buffers(),
+        );
+        self.lights_buffer = lights_buffer(device, 0);
+        self.lights_bind_group =
+            lights_bind_group(device, &self.lights_bind_group_layout, &self.lights_buffer);
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, hyperspheres_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, hypersphere_bvh_nodes_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, hyperplanes_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, tesseracts_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, tori_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, spherinders_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, csg_pairs_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, lights_count) as _,
+            &u32::to_ne_bytes(0),
+        );
+    }
+
+    /// The buffers in the objects bind group, in the order of their bindings.
+    fn objects_buffers(&self) -> [&wgpu::Buffer; 7] {
+        [
+            &self.hyperspheres_buffer,
+            &self.hyperplanes_buffer,
+            &self.tesseracts_buffer,
+            &self.hypersphere_bvh_buffer,
+            &self.tori_buffer,
+            &self.spherinders_buffer,
+            &self.csg_pairs_buffer,
+        ]
+    }
+
+    pub fn gpu_buffer_bytes(&self) -> u64 {
+        self.scene_info_buffer.size()
+            + self.hyperspheres_buffer.size()
+            + self.hyperplanes_buffer.size()
+            + self.tesseracts_buffer.size()
+            + self.tori_buffer.size()
+            + self.spherinders_buffer.size()
+            + self.csg_pairs_buffer.size()
+            + self.hypersphere_bvh_buffer.size()
+            + self.lights_buffer.size()
+    }
+
+    pub fn update_headlamp(
+        &mut self,
+        queue: &wgpu::Queue,
+        color: cgmath::Vector3<f32>,
+        intensity: f32,
+    ) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, headlamp_color) as _,
+            bytemuck::cast_slice(&[color.x, color.y, color.z, intensity]),
+        );
+    }
+
+    pub fn update_shadow_bias(&mut self, queue: &wgpu::Queue, shadow_bias: f32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, shadow_bias) as _,
+            &f32::to_ne_bytes(shadow_bias),
+        );
+    }
+
+    /// Sets how many shadow rays are traced towards each emissive hypersphere, spread over its
+    /// surface to give soft shadows. 1 traces a single ray to its center, for hard shadows.
+    ///
+    /// The samples are noisy, accumulating samples over frames smooths them out.
+    pub fn update_shadow_samples(&mut self, queue: &wgpu::Queue, shadow_samples: u32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, shadow_samples) as _,
+            &u32::to_ne_bytes(shadow_samples.max(1)),
+        );
+    }
+
+    /// Sets how many times a ray may reflect off of reflective objects.
+    ///
+    /// Every bounce traces another ray through the whole scene, along with its shadow rays, so the
+    /// cost of rendering a reflective pixel grows linearly with this. The shader caps it at 16.
+    pub fn update_max_bounces(&mut self, queue: &wgpu::Queue, max_bounces: u32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, max_bounces) as _,
+            &u32::to_ne_bytes(max_bounces),
+        );
+    }
+
+    /// Sets the size of the grid of rays shot for each pixel. This traces
+    /// `samples_per_pixel * samples_per_pixel` rays per pixel, so the cost of a frame grows
+    /// quadratically with it.
+    pub fn update_samples_per_pixel(&mut self, queue: &wgpu::Queue, samples_per_pixel: u32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, samples_per_pixel) as _,
+            &u32::to_ne_bytes(samples_per_pixel.max(1)),
+        );
+    }
+
+    /// Sets the colors of the sky seen by rays that miss every object. The sky is `horizon` colored
+    /// perpendicular to `direction` and fades to `zenith` when looking straight along it.
+    pub fn update_sky(
+        &mut self,
+        queue: &wgpu::Queue,
+        zenith: cgmath::Vector3<f32>,
+        horizon: cgmath::Vector3<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, sky_direction) as _,
+            bytemuck::cast_slice(&[direction.x, direction.y, direction.z, direction.w]),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, sky_zenith) as _,
+            bytemuck::cast_slice(&[zenith.x, zenith.y, zenith.z]),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, sky_horizon) as _,
+            bytemuck::cast_slice(&[horizon.x, horizon.y, horizon.z]),
+        );
+    }
+
+    /// Sets the image of the sky that rays that miss every object see, in place of the gradient
+    /// from [`RenderState::update_sky`], or goes back to the gradient for `None`.
+    pub fn set_environment(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        environment: Option<EnvironmentMap<'_>>,
+    ) {
+        let (width, height) = environment.as_ref().map_or((1, 1), |environment| {
+            (environment.width.max(1), environment.height.max(1))
         });
+        if self.environment_texture.width() != width || self.environment_texture.height() != height
+        {
+            self.environment_texture = environment_texture(device, width, height);
+            self.scene_info_bind_group = scene_info_bind_group(
+                device,
+                &self.scene_info_bind_group_layout,
+                &self.scene_info_buffer,
+                &self.environment_texture,
+            );
+        }
+        if let Some(environment) = &environment {
+            assert_eq!(
+                environment.pixels.len(),
+                width as usize * height as usize,
+                "the environment map should have width * height pixels"
+            );
+            queue.write_texture(
+                self.environment_texture.as_image_copy(),
+                bytemuck::cast_slice(environment.pixels),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * size_of::<[f32; 4]>() as u32),
+                    rows_per_image: None,
+                },
+                self.environment_texture.size(),
+            );
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, has_environment) as _,
+            &u32::to_ne_bytes(environment.is_some().into()),
+        );
+    }
+
+    /// Sets how many steps the ray march towards a torus can take before giving up on hitting it.
+    pub fn update_torus_max_steps(&mut self, queue: &wgpu::Queue, torus_max_steps: u32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, torus_max_steps) as _,
+            &u32::to_ne_bytes(torus_max_steps),
+        );
+    }
+
+    /// Sets the object that is outlined in every view, `None` outlines nothing.
+    pub fn update_selection(&mut self, queue: &wgpu::Queue, selection: Option<ObjectId>) {
+        let (kind, index) = match selection {
+            Some(ObjectId::Hypersphere(index)) => (0, index),
+            Some(ObjectId::Hyperplane(index)) => (1, index),
+            Some(ObjectId::Tesseract(index)) => (2, index),
+            Some(ObjectId::Torus(index)) => (3, index),
+            Some(ObjectId::Spherinder(index)) => (4, index),
+            Some(ObjectId::CsgPair(index)) => (5, index),
+            None => (u32::MAX, u32::MAX),
+        };
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, selected_object_kind) as _,
+            bytemuck::cast_slice(&[kind, index]),
+        );
+    }
+
+    /// Sets the directional light that shines on the whole scene from `direction`, which doesn't
+    /// need to be normalized.
+    pub fn update_sun(
+        &mut self,
+        queue: &wgpu::Queue,
+        direction: cgmath::Vector4<f32>,
+        color: cgmath::Vector3<f32>,
+        intensity: f32,
+    ) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, sun_direction) as _,
+            bytemuck::cast_slice(&[direction.x, direction.y, direction.z, direction.w]),
+        );
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, sun_color) as _,
+            bytemuck::cast_slice(&[color.x, color.y, color.z, intensity]),
+        );
+    }
 
-    let full_screen_quad_shader =
-        device.create_shader_module(wgpu::include_wgsl!("../shaders/full_screen_quad.wgsl"));
-    let full_screen_quad_render_pipeline_layout =
-        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Full Screen Quad Render Pipeline Layout"),
-            bind_group_layouts: &[&render_target::sample_bind_group_layout(device)],
-            push_constant_ranges: &[],
-        });
-    let full_screen_quad_render_pipeline =
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Full Screen Quad Render Pipeline"),
-            layout: Some(&full_screen_quad_render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &full_screen_quad_shader,
-                entry_point: Some("vertex"),
-                compilation_options: Default::default(),
-                buffers: &[],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &full_screen_quad_shader,
-                entry_point: Some("fragment"),
-                compilation_options: Default::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: *target_format,
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::all(),
-                })],
-            }),
-            multiview: None,
-            cache: None,
-        });
+    /// Sets the fog that surfaces fade into past `start`, a `density` of 0 turns it off.
+    pub fn update_fog(
+        &mut self,
+        queue: &wgpu::Queue,
+        color: cgmath::Vector3<f32>,
+        start: f32,
+        density: f32,
+    ) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, fog_color) as _,
+            bytemuck::cast_slice(&[color.x, color.y, color.z, start, density]),
+        );
+    }
 
-    renderer.write().callback_resources.insert(RenderState {
-        scene_info_buffer,
-        scene_info_bind_group,
+    /// Sets how quickly surfaces are dimmed and desaturated the further they are from the camera in
+    /// w, only in [`RenderMode::Project`]. A `w_falloff` of 0 turns it off.
+    pub fn update_w_falloff(&mut self, queue: &wgpu::Queue, w_falloff: f32) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, w_falloff) as _,
+            &f32::to_ne_bytes(w_falloff),
+        );
+    }
 
-        hyperspheres_buffer,
-        hyperplanes_buffer,
-        objects_bind_group_layout,
-        objects_bind_group,
+    /// Sets whether a grid on the y = 0 hyperplane and the positive x, y, z and w axes are drawn
+    /// over every view.
+    pub fn update_show_grid(&mut self, queue: &wgpu::Queue, show_grid: bool) {
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, show_grid) as _,
+            &u32::to_ne_bytes(show_grid.into()),
+        );
+    }
 
-        ray_tracing_compute_pipeline,
-        full_screen_quad_render_pipeline,
-    });
-}
+    /// Sets how the ray traced colors of every view are mapped to the colors shown on screen.
+    pub fn update_tone_mapping(&mut self, queue: &wgpu::Queue, tone_mapping: ToneMapping) {
+        let tone_mapping = match tone_mapping {
+            ToneMapping::None => ToneMappingInfo {
+                mode: 0,
+                exposure: 1.0,
+            },
+            ToneMapping::Aces { exposure } => ToneMappingInfo { mode: 1, exposure },
+        };
+        queue.write_buffer(
+            &self.tone_mapping_buffer,
+            0,
+            bytemuck::bytes_of(&tone_mapping),
+        );
+    }
 
-fn hyperspheres_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
-    device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Hyperspheres Buffer"),
-        size: (length.max(1) * size_of::<Hypersphere>())
-            .try_into()
-            .unwrap(),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    })
-}
+    /// Traces a single ray through `pixel` of the view described by `render_data`, and returns the
+    /// object it hits first. The pixel is measured from the top left of the view.
+    ///
+    /// This blocks until the GPU has finished all submitted work.
+    pub fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_data: &RenderData,
+        (x, y): (u32, u32),
+    ) -> Option<ObjectId> {
+        let (width, height) = render_data.render_target.size();
+        if x >= width || y >= height {
+            return None;
+        }
+        let pick = Pick {
+            // the ray tracer counts rows up from the bottom of the view
+            pixel: [x, height - 1 - y],
+            ..bytemuck::Zeroable::zeroed()
+        };
+        self.trace_pick(
+            device,
+            queue,
+            pick,
+            &self.pick_compute_pipeline,
+            &render_data.render_target.write_bind_group,
+            Some(render_data.push_constants()),
+        )
+        .object()
+    }
 
-fn hyperplanes_buffer(device: &wgpu::Device, length: usize) -> wgpu::Buffer {
-    device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Hyperplanes Buffer"),
-        size: (length.max(1) * size_of::<Hyperplane>())
-            .try_into()
-            .unwrap(),
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-        mapped_at_creation: false,
-    })
-}
+    /// Traces a ray through the scene last uploaded with the `update_*` methods, using the same
+    /// intersection code as the renderer, and returns the first surface it hits. The direction
+    /// doesn't need to be normalized.
+    ///
+    /// This blocks until the GPU has finished all submitted work.
+    pub fn raycast(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        origin: cgmath::Vector4<f32>,
+        direction: cgmath::Vector4<f32>,
+    ) -> Option<RayHit> {
+        if direction.magnitude2() == 0.0 {
+            return None;
+        }
+        let pick = Pick {
+            origin,
+            direction: direction.normalize(),
+            ..bytemuck::Zeroable::zeroed()
+        };
+        let pick = self.trace_pick(
+            device,
+            queue,
+            pick,
+            &self.raycast_compute_pipeline,
+            &self.empty_bind_group,
+            None,
+        );
+        Some(RayHit {
+            distance: pick.distance,
+            position: pick.position,
+            normal: pick.normal,
+            object: pick.object()?,
+        })
+    }
 
-fn objects_bind_group(
-    device: &wgpu::Device,
-    objects_bind_group_layout: &wgpu::BindGroupLayout,
-    hyperspheres_buffer: &wgpu::Buffer,
-    hyperplanes_buffer: &wgpu::Buffer,
-) -> wgpu::BindGroup {
-    device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Objects Bind Group"),
-        layout: objects_bind_group_layout,
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: hyperspheres_buffer.as_entire_binding(),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: hyperplanes_buffer.as_entire_binding(),
-            },
-        ],
-    })
-}
+    /// Runs one of the entry points that trace a single ray, and reads back the result.
+    fn trace_pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        pick: Pick,
+        pipeline: &wgpu::ComputePipeline,
+        target_bind_group: &wgpu::BindGroup,
+        push_constants: Option<PushConstants>,
+    ) -> Pick {
+        queue.write_buffer(&self.pick_buffer, 0, bytemuck::bytes_of(&pick));
 
-impl RenderState {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Pick Compute Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, target_bind_group, &[]);
+            compute_pass.set_bind_group(1, &self.scene_info_bind_group, &[]);
+            compute_pass.set_bind_group(2, &self.objects_bind_group, &[]);
+            compute_pass.set_bind_group(3, &self.lights_bind_group, &[]);
+            compute_pass.set_bind_group(4, &self.pick_bind_group, &[]);
+            if let Some(push_constants) = push_constants {
+                compute_pass.set_push_constants(0, bytemuck::bytes_of(&push_constants));
+            }
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(
+            &self.pick_buffer,
+            0,
+            &self.pick_readback_buffer,
+            0,
+            self.pick_buffer.size(),
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = self.pick_readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::PollType::Wait).unwrap();
+        let pick = bytemuck::pod_read_unaligned::<Pick>(&slice.get_mapped_range());
+        self.pick_readback_buffer.unmap();
+        pick
+    }
+
+    /// Uploads the hyperspheres, and rebuilds the bounding volume hierarchy the shader uses to
+    /// skip most of them once there are enough of them for it to be worth it.
     pub fn update_hyperspheres(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         hyperspheres: impl ExactSizeIterator<Item = Hypersphere>,
     ) {
-        let len = hyperspheres.len();
-        let size = size_of::<Hypersphere>();
-        if len * size > self.hyperspheres_buffer.size() as _ {
+        let hyperspheres = hyperspheres.collect::<Vec<_>>();
+        let bvh = if hyperspheres.len() >= BVH_MIN_HYPERSPHERES {
+            build_bvh(&hyperspheres)
+        } else {
+            vec![]
+        };
+
+        let mut reallocated = false;
+        if should_reallocate(
+            &self.hyperspheres_buffer,
+            hyperspheres.len(),
+            size_of::<Hypersphere>(),
+        ) {
             self.hyperspheres_buffer = hyperspheres_buffer(device, hyperspheres.len());
+            reallocated = true;
+        }
+        if should_reallocate(
+            &self.hypersphere_bvh_buffer,
+            bvh.len(),
+            size_of::<BvhNode>(),
+        ) {
+            self.hypersphere_bvh_buffer = hypersphere_bvh_buffer(device, bvh.len());
+            reallocated = true;
+        }
+        if reallocated {
             self.objects_bind_group = objects_bind_group(
                 device,
                 &self.objects_bind_group_layout,
-                &self.hyperspheres_buffer,
-                &self.hyperplanes_buffer,
+                self.objects_buffers(),
             );
         }
+
         queue.write_buffer(
             &self.scene_info_buffer,
             offset_of!(SceneInfo, hyperspheres_count) as _,
             &u32::to_ne_bytes(hyperspheres.len().try_into().unwrap()),
         );
-        let mut hyperspheres_buffer = queue
-            .write_buffer_with(
-                &self.hyperspheres_buffer,
-                0,
-                u64::try_from(len * size).unwrap().try_into().unwrap(),
-            )
-            .unwrap();
-        for (i, hypersphere) in hyperspheres.enumerate() {
-            hyperspheres_buffer[i * size..][..size]
-                .copy_from_slice(bytemuck::bytes_of(&hypersphere));
-        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, hypersphere_bvh_nodes_count) as _,
+            &u32::to_ne_bytes(bvh.len().try_into().unwrap()),
+        );
+        queue.write_buffer(
+            &self.hyperspheres_buffer,
+            0,
+            bytemuck::cast_slice(&hyperspheres),
+        );
+        queue.write_buffer(&self.hypersphere_bvh_buffer, 0, bytemuck::cast_slice(&bvh));
     }
 
     pub fn update_hyperplanees(
@@ -287,13 +1398,12 @@ impl RenderState {
     ) {
         let len = hyperplanes.len();
         let size = size_of::<Hyperplane>();
-        if len * size > self.hyperplanes_buffer.size() as _ {
-            self.hyperplanes_buffer = hyperplanes_buffer(device, hyperplanes.len());
+        if should_reallocate(&self.hyperplanes_buffer, len, size) {
+            self.hyperplanes_buffer = hyperplanes_buffer(device, len);
             self.objects_bind_group = objects_bind_group(
                 device,
                 &self.objects_bind_group_layout,
-                &self.hyperspheres_buffer,
-                &self.hyperplanes_buffer,
+                self.objects_buffers(),
             );
         }
         queue.write_buffer(
@@ -301,36 +1411,416 @@ impl RenderState {
             offset_of!(SceneInfo, hyperplanes_count) as _,
             &u32::to_ne_bytes(len.try_into().unwrap()),
         );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
         let mut hyperplanes_buffer = queue
-            .write_buffer_with(
-                &self.hyperplanes_buffer,
-                0,
-                u64::try_from(len * size).unwrap().try_into().unwrap(),
-            )
+            .write_buffer_with(&self.hyperplanes_buffer, 0, bytes)
             .unwrap();
         for (i, hyperplane) in hyperplanes.enumerate() {
             hyperplanes_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&hyperplane));
         }
     }
+
+    pub fn update_tesseracts(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        tesseracts: impl ExactSizeIterator<Item = Tesseract>,
+    ) {
+        let len = tesseracts.len();
+        let size = size_of::<Tesseract>();
+        if should_reallocate(&self.tesseracts_buffer, len, size) {
+            self.tesseracts_buffer = tesseracts_buffer(device, len);
+            self.objects_bind_group = objects_bind_group(
+                device,
+                &self.objects_bind_group_layout,
+                self.objects_buffers(),
+            );
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, tesseracts_count) as _,
+            &u32::to_ne_bytes(len.try_into().unwrap()),
+        );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
+        let mut tesseracts_buffer = queue
+            .write_buffer_with(&self.tesseracts_buffer, 0, bytes)
+            .unwrap();
+        for (i, tesseract) in tesseracts.enumerate() {
+            tesseracts_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&tesseract));
+        }
+    }
+
+    pub fn update_tori(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        tori: impl ExactSizeIterator<Item = Torus>,
+    ) {
+        let len = tori.len();
+        let size = size_of::<Torus>();
+        if should_reallocate(&self.tori_buffer, len, size) {
+            self.tori_buffer = tori_buffer(device, len);
+            self.objects_bind_group = objects_bind_group(
+                device,
+                &self.objects_bind_group_layout,
+                self.objects_buffers(),
+            );
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, tori_count) as _,
+            &u32::to_ne_bytes(len.try_into().unwrap()),
+        );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
+        let mut tori_buffer = queue
+            .write_buffer_with(&self.tori_buffer, 0, bytes)
+            .unwrap();
+        for (i, torus) in tori.enumerate() {
+            tori_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&torus));
+        }
+    }
+
+    pub fn update_spherinders(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        spherinders: impl ExactSizeIterator<Item = Spherinder>,
+    ) {
+        let len = spherinders.len();
+        let size = size_of::<Spherinder>();
+        if should_reallocate(&self.spherinders_buffer, len, size) {
+            self.spherinders_buffer = spherinders_buffer(device, len);
+            self.objects_bind_group = objects_bind_group(
+                device,
+                &self.objects_bind_group_layout,
+                self.objects_buffers(),
+            );
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, spherinders_count) as _,
+            &u32::to_ne_bytes(len.try_into().unwrap()),
+        );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
+        let mut spherinders_buffer = queue
+            .write_buffer_with(&self.spherinders_buffer, 0, bytes)
+            .unwrap();
+        for (i, spherinder) in spherinders.enumerate() {
+            spherinders_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&spherinder));
+        }
+    }
+
+    pub fn update_csg_pairs(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        csg_pairs: impl ExactSizeIterator<Item = CsgPair>,
+    ) {
+        let len = csg_pairs.len();
+        let size = size_of::<CsgPair>();
+        if should_reallocate(&self.csg_pairs_buffer, len, size) {
+            self.csg_pairs_buffer = csg_pairs_buffer(device, len);
+            self.objects_bind_group = objects_bind_group(
+                device,
+                &self.objects_bind_group_layout,
+                self.objects_buffers(),
+            );
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, csg_pairs_count) as _,
+            &u32::to_ne_bytes(len.try_into().unwrap()),
+        );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
+        let mut csg_pairs_buffer = queue
+            .write_buffer_with(&self.csg_pairs_buffer, 0, bytes)
+            .unwrap();
+        for (i, csg_pair) in csg_pairs.enumerate() {
+            csg_pairs_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&csg_pair));
+        }
+    }
+
+    pub fn update_lights(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lights: impl ExactSizeIterator<Item = Light>,
+    ) {
+        let len = lights.len();
+        let size = size_of::<Light>();
+        if should_reallocate(&self.lights_buffer, len, size) {
+            self.lights_buffer = lights_buffer(device, len);
+            self.lights_bind_group =
+                lights_bind_group(device, &self.lights_bind_group_layout, &self.lights_buffer);
+        }
+        queue.write_buffer(
+            &self.scene_info_buffer,
+            offset_of!(SceneInfo, lights_count) as _,
+            &u32::to_ne_bytes(len.try_into().unwrap()),
+        );
+        let Some(bytes) = wgpu::BufferSize::new(u64::try_from(len * size).unwrap()) else {
+            return;
+        };
+        let mut lights_buffer = queue
+            .write_buffer_with(&self.lights_buffer, 0, bytes)
+            .unwrap();
+        for (i, light) in lights.enumerate() {
+            lights_buffer[i * size..][..size].copy_from_slice(bytemuck::bytes_of(&light));
+        }
+    }
+}
+
+/// An image of the sky in every direction, for [`RenderState::set_environment`].
+///
+/// The 4d sky is a 3-sphere of directions, which a 2d image can't cover one to one. The image is
+/// laid out like an equirectangular image of the 3d sky: across it is the angle around +y from +x
+/// towards +z, and down it is the angle from +y. Directions that only differ in how much of them
+/// is along w share a pixel, so directions with no w see the image like a 3d sky would.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvironmentMap<'a> {
+    pub width: u32,
+    pub height: u32,
+    /// Linear RGBA, row by row from the top.
+    pub pixels: &'a [[f32; 4]],
+}
+
+/// One of the camera's local axes, x is forward, y is up, z is right and w is ana.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CameraAxis {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+impl CameraAxis {
+    pub const ALL: [Self; 4] = [Self::X, Self::Y, Self::Z, Self::W];
+
+    pub fn direction(self, camera_transform: Transform) -> cgmath::Vector4<f32> {
+        match self {
+            CameraAxis::X => camera_transform.x(),
+            CameraAxis::Y => camera_transform.y(),
+            CameraAxis::Z => camera_transform.z(),
+            CameraAxis::W => camera_transform.w(),
+        }
+    }
 }
 
-pub enum ViewAxes {
-    XYZ,
-    XWZ,
-    XYW,
+impl std::fmt::Display for CameraAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CameraAxis::X => "X",
+            CameraAxis::Y => "Y",
+            CameraAxis::Z => "Z",
+            CameraAxis::W => "W",
+        })
+    }
+}
+
+/// Which of the camera's axes a view looks along and which it shows going up and to the right,
+/// choosing the 3d slice of the camera's frame that the view shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewAxes {
+    pub forward: CameraAxis,
+    pub up: CameraAxis,
+    pub right: CameraAxis,
+}
+
+impl ViewAxes {
+    pub const XYZ: Self = Self {
+        forward: CameraAxis::X,
+        up: CameraAxis::Y,
+        right: CameraAxis::Z,
+    };
+    pub const XWZ: Self = Self {
+        forward: CameraAxis::X,
+        up: CameraAxis::W,
+        right: CameraAxis::Z,
+    };
+    pub const XYW: Self = Self {
+        forward: CameraAxis::X,
+        up: CameraAxis::Y,
+        right: CameraAxis::W,
+    };
+}
+
+/// A set of the app's three views, named after the axes they show by default, for choosing which
+/// views an object is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewMask(pub u32);
+
+impl ViewMask {
+    pub const XYZ: Self = Self(1 << 0);
+    pub const XWZ: Self = Self(1 << 1);
+    pub const XYW: Self = Self(1 << 2);
+    pub const ALL: Self = Self(Self::XYZ.0 | Self::XWZ.0 | Self::XYW.0);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+}
+
+impl Default for ViewMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::fmt::Display for ViewAxes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.forward, self.up, self.right)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ToneMapping {
+    /// Shows the ray traced colors as they are, for looking at the raw values.
+    None,
+    /// Scales the colors by `exposure`, then compresses them with the ACES filmic curve and sRGB
+    /// encodes them.
+    Aces { exposure: f32 },
+}
+
+impl ToneMapping {
+    /// Maps one channel of a ray traced color the same way `full_screen_quad.wgsl` does, to a
+    /// value between 0 and 1.
+    pub(crate) fn map_channel(self, channel: f32) -> f32 {
+        match self {
+            ToneMapping::None => channel.clamp(0.0, 1.0),
+            ToneMapping::Aces { exposure } => {
+                let x = channel.max(0.0) * exposure;
+                let x = ((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0);
+                if x <= 0.0031308 {
+                    12.92 * x
+                } else {
+                    1.055 * x.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+    /// Spreads the view over every direction around the camera by inverse stereographic
+    /// projection, the image plane is wrapped onto the sphere of directions from `pole`, which
+    /// ends up infinitely far from the center of the view.
+    ///
+    /// `pole` is in the camera's local space and doesn't need to be normalized, it is -x, behind the
+    /// camera, for a fisheye view. Moving it through w turns the middle of the view towards ana or
+    /// kata.
+    Stereographic {
+        pole: cgmath::Vector4<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RenderMode {
+    /// Renders the whole 4d scene projected onto the view.
+    Project,
+    /// Renders the 3d cross section of the scene where it intersects the hyperplane at `w`.
+    Slice { w: f32 },
 }
 
 pub struct RenderData {
     pub render_target: RenderTarget,
     pub camera_transform: Transform,
     pub view_axes: ViewAxes,
+    pub render_mode: RenderMode,
+    pub projection: Projection,
+    /// The vertical field of view in radians, only used by [`Projection::Perspective`].
+    pub fov: f32,
+    /// The height of the view in world units, only used by [`Projection::Orthographic`].
+    pub orthographic_height: f32,
+    /// Objects closer to the camera than `near` are cut away, and ones further than `far` are
+    /// replaced by the sky.
+    pub near: f32,
+    pub far: f32,
+    /// Which view is being rendered, objects whose `views` don't include it are left out.
+    pub view: ViewMask,
+    /// The radius of the lens that rays start from, 0 is a pinhole with everything in focus.
+    /// The blur is noisy in a single frame and smooths out as frames are accumulated.
+    pub aperture: f32,
+    /// How far in front of the camera things are in focus when `aperture` isn't 0.
+    pub focus_distance: f32,
+    /// When set, this frame's samples are averaged with the previous `frame_index` frames in the
+    /// render target's accumulation buffer. A `frame_index` of 0 starts the average over.
+    pub accumulate: bool,
+    pub frame_index: u32,
+}
+
+impl RenderData {
+    fn push_constants(&self) -> PushConstants {
+        let camera = {
+            let ViewAxes { forward, up, right } = self.view_axes;
+            Camera {
+                position: self.camera_transform.position(),
+                forward: forward.direction(self.camera_transform),
+                up: up.direction(self.camera_transform),
+                right: right.direction(self.camera_transform),
+                fov: self.fov,
+                projection: match self.projection {
+                    Projection::Perspective => 0,
+                    Projection::Orthographic => 1,
+                    Projection::Stereographic { .. } => 2,
+                },
+                orthographic_height: self.orthographic_height,
+                near: self.near,
+                pole: match self.projection {
+                    Projection::Stereographic { pole } => {
+                        self.camera_transform.transform_direction(pole)
+                    }
+                    Projection::Perspective | Projection::Orthographic => {
+                        cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0)
+                    }
+                },
+                far: self.far,
+                view: self.view.0,
+                aperture: self.aperture,
+                focus_distance: self.focus_distance,
+            }
+        };
+        PushConstants {
+            camera,
+            frame_index: self.frame_index,
+            accumulate: self.accumulate.into(),
+            render_mode: match self.render_mode {
+                RenderMode::Project => 0,
+                RenderMode::Slice { .. } => 1,
+            },
+            slice_w: match self.render_mode {
+                RenderMode::Project => 0.0,
+                RenderMode::Slice { w } => w,
+            },
+        }
+    }
 }
 
 impl eframe::egui_wgpu::CallbackTrait for RenderData {
     fn prepare(
         &self,
         device: &wgpu::Device,
-        _queue: &wgpu::Queue,
+        queue: &wgpu::Queue,
         _screen_descriptor: &eframe::egui_wgpu::ScreenDescriptor,
         _egui_encoder: &mut wgpu::CommandEncoder,
         callback_resources: &mut eframe::egui_wgpu::CallbackResources,
@@ -340,41 +1830,7 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Ray Tracing Encoder"),
         });
-
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Ray Tracing Compute Pass"),
-                timestamp_writes: None,
-            });
-
-            compute_pass.set_pipeline(&state.ray_tracing_compute_pipeline);
-            compute_pass.set_bind_group(0, &self.render_target.write_bind_group, &[]);
-            compute_pass.set_bind_group(1, &state.scene_info_bind_group, &[]);
-            compute_pass.set_bind_group(2, &state.objects_bind_group, &[]);
-
-            let camera = {
-                let x = self.camera_transform.x();
-                let y = self.camera_transform.y();
-                let z = self.camera_transform.z();
-                let w = self.camera_transform.w();
-                let (forward, up, right) = match self.view_axes {
-                    ViewAxes::XYZ => (x, y, z),
-                    ViewAxes::XWZ => (x, w, z),
-                    ViewAxes::XYW => (x, y, w),
-                };
-                Camera {
-                    position: self.camera_transform.position(),
-                    forward,
-                    up,
-                    right,
-                }
-            };
-            compute_pass.set_push_constants(0, bytemuck::bytes_of(&camera));
-
-            let (width, height) = self.render_target.size();
-            compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
-        }
-
+        state.ray_trace(&mut encoder, queue, self);
         vec![encoder.finish()]
     }
 
@@ -388,6 +1844,82 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
 
         render_pass.set_pipeline(&state.full_screen_quad_render_pipeline);
         render_pass.set_bind_group(0, &self.render_target.sample_bind_group, &[]);
+        render_pass.set_bind_group(1, &state.tone_mapping_bind_group, &[]);
         render_pass.draw(0..4, 0..1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of buffers that are still alive on the GPU, or `None` when the backend can't
+    /// report it.
+    fn live_buffers(instance: &wgpu::Instance) -> Option<usize> {
+        Some(instance.generate_report()?.hub.buffers.num_allocated)
+    }
+
+    #[test]
+    fn dropping_render_states_frees_their_buffers() {
+        let instance = wgpu::Instance::default();
+        let Ok(adapter) =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        else {
+            eprintln!("skipped, there is no GPU adapter");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::PUSH_CONSTANTS,
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .unwrap();
+
+        let mut render_state = RenderState::new(&adapter, &device, wgpu::TextureFormat::Rgba8Unorm);
+        let empty_bytes = render_state.gpu_buffer_bytes();
+        drop(render_state);
+        device.poll(wgpu::PollType::Wait).unwrap();
+        let Some(baseline) = live_buffers(&instance) else {
+            eprintln!("skipped, the backend doesn't report its resources");
+            return;
+        };
+
+        for _ in 0..50 {
+            render_state = RenderState::new(&adapter, &device, wgpu::TextureFormat::Rgba8Unorm);
+            render_state.update_hyperspheres(
+                &device,
+                &queue,
+                std::iter::repeat_n(bytemuck::Zeroable::zeroed(), 1000),
+            );
+            assert!(render_state.gpu_buffer_bytes() > empty_bytes);
+            render_state.release_scene_resources(&device, &queue);
+            assert_eq!(render_state.gpu_buffer_bytes(), empty_bytes);
+            drop(render_state);
+        }
+        device.poll(wgpu::PollType::Wait).unwrap();
+        assert_eq!(live_buffers(&instance), Some(baseline));
+    }
+
+    #[test]
+    fn buffer_capacity_tracks_the_length() {
+        // buffers are recreated with room for exactly `len` elements, and never less than 1
+        let mut capacity = 1;
+        for len in [0, 1, 10, 500, 499, 400, 126, 125, 124, 20, 3, 0, 2000, 1, 0] {
+            if outside_capacity(len, capacity) {
+                capacity = len.max(1);
+            }
+            assert!(len <= capacity, "{len} elements don't fit in {capacity}");
+            assert!(
+                capacity == 1 || len >= capacity / 4,
+                "{len} elements leave most of {capacity} unused"
+            );
+        }
+
+        // within the band nothing is reallocated
+        assert!(!outside_capacity(500, 500));
+        assert!(!outside_capacity(125, 500));
+        assert!(outside_capacity(124, 500));
+        assert!(outside_capacity(501, 500));
+        assert!(!outside_capacity(0, 1));
+    }
+}
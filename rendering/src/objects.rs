@@ -1,11 +1,92 @@
 use math::Transform;
+use serde::{Deserialize, Serialize};
+
+/// How the surface of an object looks, shared by every kind of object so that they are all
+/// shaded the same way.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Material {
+    pub color: cgmath::Vector3<f32>,
+    pub reflectivity: f32,
+    pub emission: cgmath::Vector3<f32>,
+    /// How much of the light hitting the object is stopped by it, the rest passes through.
+    pub opacity: f32,
+    /// The index of refraction, bending the rays that pass through the object.
+    pub ior: f32,
+    /// A [`Pattern`] as a `u32`.
+    pub pattern: u32,
+    /// The size of one cell of the pattern.
+    pub pattern_scale: f32,
+    /// How bright the highlights from the lights are, they aren't tinted by the color.
+    pub specular: f32,
+    /// The Blinn-Phong exponent, higher values give smaller and sharper highlights.
+    pub shininess: f32,
+    pub _padding: [f32; 3],
+}
+
+unsafe impl bytemuck::Zeroable for Material {}
+unsafe impl bytemuck::Pod for Material {}
+
+impl Default for Material {
+    /// An opaque, non-reflective, matte white.
+    fn default() -> Self {
+        Self {
+            color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            reflectivity: 0.0,
+            emission: cgmath::Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            opacity: 1.0,
+            ior: 1.5,
+            pattern: Pattern::Solid.into(),
+            pattern_scale: 1.0,
+            specular: 0.0,
+            shininess: 32.0,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// How the color of a surface varies across it, the patterns are laid out in the object's own
+/// space along all four axes so that they move with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Pattern {
+    #[default]
+    Solid,
+    /// Alternates between the color and a darker shade of it in every cell.
+    Checker,
+    /// Draws darker lines along the edges of every cell.
+    Grid,
+}
+
+impl From<Pattern> for u32 {
+    fn from(pattern: Pattern) -> Self {
+        match pattern {
+            Pattern::Solid => 0,
+            Pattern::Checker => 1,
+            Pattern::Grid => 2,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct Hypersphere {
     pub transform: Transform,
-    pub color: cgmath::Vector3<f32>,
+    /// Stretches the hypersphere along each of its local axes before it is transformed, which
+    /// turns it into an ellipsoid.
+    pub scale: cgmath::Vector4<f32>,
+    pub material: Material,
     pub radius: f32,
+    /// The bits of the [`crate::ViewMask`] of views the object is drawn in.
+    pub views: u32,
+    pub _padding: [f32; 2],
 }
 
 unsafe impl bytemuck::Zeroable for Hypersphere {}
@@ -15,12 +96,115 @@ unsafe impl bytemuck::Pod for Hypersphere {}
 #[repr(C)]
 pub struct Hyperplane {
     pub transform: Transform,
-    pub color: cgmath::Vector3<f32>,
+    pub material: Material,
     pub width: f32,
     pub height: f32,
     pub depth: f32,
+    /// When non-zero the hyperplane extends forever instead of being bounded by its width, height
+    /// and depth.
+    pub infinite: u32,
+    /// When non-zero the hyperplane is only visible from its front, the side its local +y axis
+    /// points towards. Otherwise both sides are shaded with the normal facing the viewer.
+    pub cull_back: u32,
+    pub views: u32,
     pub _padding: [f32; 2],
 }
 
 unsafe impl bytemuck::Zeroable for Hyperplane {}
 unsafe impl bytemuck::Pod for Hyperplane {}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Tesseract {
+    pub transform: Transform,
+    pub size: cgmath::Vector4<f32>,
+    pub material: Material,
+    pub views: u32,
+    pub _padding: [f32; 3],
+}
+
+unsafe impl bytemuck::Zeroable for Tesseract {}
+unsafe impl bytemuck::Pod for Tesseract {}
+
+/// A thickened Clifford torus: the points `minor_radius` away from the surface where the distance
+/// from the origin in the xy plane and in the zw plane are both `major_radius`.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Torus {
+    pub transform: Transform,
+    pub material: Material,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub views: u32,
+    pub _padding: f32,
+}
+
+unsafe impl bytemuck::Zeroable for Torus {}
+unsafe impl bytemuck::Pod for Torus {}
+
+/// A 3-ball of `radius` in the xzw hyperplane extruded along the local y axis, reaching
+/// `length / 2` either side of the origin, so that an untransformed spherinder stands upright
+/// like a pillar. Its two caps are flat balls.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Spherinder {
+    pub transform: Transform,
+    pub material: Material,
+    pub radius: f32,
+    pub length: f32,
+    pub views: u32,
+    pub _padding: f32,
+}
+
+unsafe impl bytemuck::Zeroable for Spherinder {}
+unsafe impl bytemuck::Pod for Spherinder {}
+
+/// Two hyperspheres combined into one shape by a boolean [`CsgOp`]. Only the transforms, scales
+/// and radii of `a` and `b` are used, the combined shape is drawn with `material`.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CsgPair {
+    pub a: Hypersphere,
+    pub b: Hypersphere,
+    pub material: Material,
+    /// A [`CsgOp`] as a `u32`.
+    pub op: u32,
+    pub views: u32,
+    pub _padding: [f32; 2],
+}
+
+unsafe impl bytemuck::Zeroable for CsgPair {}
+unsafe impl bytemuck::Pod for CsgPair {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CsgOp {
+    /// The space inside of either hypersphere.
+    #[default]
+    Union,
+    /// The space inside of both hyperspheres.
+    Intersection,
+    /// The space inside of `a` with `b` carved out of it.
+    Difference,
+}
+
+impl From<CsgOp> for u32 {
+    fn from(op: CsgOp) -> Self {
+        match op {
+            CsgOp::Union => 0,
+            CsgOp::Intersection => 1,
+            CsgOp::Difference => 2,
+        }
+    }
+}
+
+/// An object in the arrays last uploaded with the `RenderState::update_*` methods, identified by
+/// its index in the iterator it was uploaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectId {
+    Hypersphere(u32),
+    Hyperplane(u32),
+    Tesseract(u32),
+    Torus(u32),
+    Spherinder(u32),
+    CsgPair(u32),
+}
@@ -0,0 +1,121 @@
+use eframe::wgpu;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Measures how long a compute pass takes on the GPU with timestamp queries.
+///
+/// Reading the timestamps back has to wait for the GPU, so each measurement is copied to a
+/// readback buffer, mapped once the frame that copied it has been submitted, and only then does the
+/// next measurement get copied. Passes in between are timed but not read.
+#[derive(Debug)]
+pub(crate) struct PassTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    readback: Mutex<Readback>,
+    duration: Mutex<Option<Duration>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Readback {
+    /// The readback buffer is free to copy the next measurement into.
+    Idle,
+    /// A measurement has been copied into the readback buffer by a command buffer that may not
+    /// have been submitted yet.
+    Copied,
+    Mapping,
+}
+
+impl PassTimer {
+    /// Returns `None` if the device doesn't support timestamp queries.
+    pub(crate) fn new(device: &wgpu::Device) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("PassTimer Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let size = 2 * size_of::<u64>() as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("PassTimer Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("PassTimer Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            readback: Mutex::new(Readback::Idle),
+            duration: Mutex::new(None),
+        })
+    }
+
+    /// The most recent measurement that has made it back from the GPU.
+    pub(crate) fn duration(&self) -> Option<Duration> {
+        *self.duration.lock().unwrap()
+    }
+
+    pub(crate) fn timestamp_writes(&self) -> wgpu::ComputePassTimestampWrites<'_> {
+        wgpu::ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        }
+    }
+
+    /// Records reading back the timestamps of the pass that was just encoded into `encoder`.
+    pub(crate) fn resolve(
+        self: &Arc<Self>,
+        encoder: &mut wgpu::CommandEncoder,
+        queue: &wgpu::Queue,
+    ) {
+        let mut readback = self.readback.lock().unwrap();
+        match *readback {
+            Readback::Idle => {
+                encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+                encoder.copy_buffer_to_buffer(
+                    &self.resolve_buffer,
+                    0,
+                    &self.readback_buffer,
+                    0,
+                    self.resolve_buffer.size(),
+                );
+                *readback = Readback::Copied;
+            }
+            // the copy was recorded for an earlier frame, so it has been submitted by now
+            Readback::Copied => {
+                *readback = Readback::Mapping;
+                let timer = Arc::clone(self);
+                let period = queue.get_timestamp_period();
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        if result.is_ok() {
+                            let [start, end] = bytemuck::pod_read_unaligned::<[u64; 2]>(
+                                &timer.readback_buffer.slice(..).get_mapped_range(),
+                            );
+                            timer.readback_buffer.unmap();
+                            let nanoseconds = end.saturating_sub(start) as f64 * f64::from(period);
+                            *timer.duration.lock().unwrap() =
+                                Some(Duration::from_nanos(nanoseconds as u64));
+                        }
+                        *timer.readback.lock().unwrap() = Readback::Idle;
+                    });
+            }
+            Readback::Mapping => {}
+        }
+    }
+}
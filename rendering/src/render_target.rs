@@ -1,4 +1,7 @@
+use crate::{ToneMapping, pass_timer::PassTimer};
 use eframe::wgpu;
+use math::Transform;
+use std::{sync::Arc, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct RenderTarget {
@@ -6,9 +9,19 @@ pub struct RenderTarget {
     sample_bind_group_layout: wgpu::BindGroupLayout,
 
     texture: wgpu::Texture,
+    /// The sum of every sample traced since the accumulation was last reset, one `vec4<f32>` per
+    /// pixel.
+    accumulation_buffer: wgpu::Buffer,
 
     pub(crate) write_bind_group: wgpu::BindGroup,
     pub(crate) sample_bind_group: wgpu::BindGroup,
+
+    frame_index: u32,
+    last_camera_transform: Option<Transform>,
+    /// How the texture is sampled when it is drawn at a different size than it was rendered at.
+    filter: wgpu::FilterMode,
+    /// Times the ray tracing pass, `None` if the device can't.
+    pub(crate) timer: Option<Arc<PassTimer>>,
 }
 
 impl RenderTarget {
@@ -28,18 +41,33 @@ impl RenderTarget {
             },
         );
 
+        let accumulation_buffer = accumulation_buffer(device, width, height);
+
         let texture_view = texture.create_view(&Default::default());
-        let write_bind_group = write_bind_group(device, &write_bind_group_layout, &texture_view);
-        let sample_bind_group = sample_bind_group(device, &sample_bind_group_layout, &texture_view);
+        let write_bind_group = write_bind_group(
+            device,
+            &write_bind_group_layout,
+            &texture_view,
+            &accumulation_buffer,
+        );
+        let filter = wgpu::FilterMode::Nearest;
+        let sample_bind_group =
+            sample_bind_group(device, &sample_bind_group_layout, &texture_view, filter);
 
         Self {
             write_bind_group_layout,
             sample_bind_group_layout,
 
             texture,
+            accumulation_buffer,
 
             write_bind_group,
             sample_bind_group,
+
+            frame_index: 0,
+            last_camera_transform: None,
+            filter,
+            timer: PassTimer::new(device).map(Arc::new),
         }
     }
 
@@ -59,14 +87,143 @@ impl RenderTarget {
         };
         if new_size != self.texture.size() {
             self.texture = texture(device, new_size);
+            self.accumulation_buffer = accumulation_buffer(device, width, height);
+
+            let texture_view = self.texture.create_view(&Default::default());
+            self.write_bind_group = write_bind_group(
+                device,
+                &self.write_bind_group_layout,
+                &texture_view,
+                &self.accumulation_buffer,
+            );
+            self.sample_bind_group = sample_bind_group(
+                device,
+                &self.sample_bind_group_layout,
+                &texture_view,
+                self.filter,
+            );
+            self.reset_accumulation();
+        }
+    }
 
+    /// Sets how the texture is sampled when it is stretched over a view of a different size,
+    /// `Nearest` keeps the pixels crisp and `Linear` blends between them.
+    ///
+    /// The texture is only filterable on devices with [`wgpu::Features::FLOAT32_FILTERABLE`], on
+    /// others it is always sampled with `Nearest`.
+    pub fn set_filter(&mut self, device: &wgpu::Device, filter: wgpu::FilterMode) {
+        let filter = if filterable(device) {
+            filter
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+        if filter != self.filter {
+            self.filter = filter;
             let texture_view = self.texture.create_view(&Default::default());
-            self.write_bind_group =
-                write_bind_group(device, &self.write_bind_group_layout, &texture_view);
-            self.sample_bind_group =
-                sample_bind_group(device, &self.sample_bind_group_layout, &texture_view);
+            self.sample_bind_group = sample_bind_group(
+                device,
+                &self.sample_bind_group_layout,
+                &texture_view,
+                self.filter,
+            );
         }
     }
+
+    /// How long the GPU took to ray trace a recent frame, or `None` if the device doesn't support
+    /// timestamp queries or no frame has been measured yet.
+    pub fn gpu_time(&self) -> Option<Duration> {
+        self.timer.as_ref()?.duration()
+    }
+
+    /// Discards the accumulated samples, the next frame starts the average over.
+    pub fn reset_accumulation(&mut self) {
+        self.frame_index = 0;
+    }
+
+    /// Returns the index of the frame about to be rendered into the accumulation buffer, which is
+    /// how many frames have already been accumulated.
+    ///
+    /// The accumulation is reset first if the camera has moved since the previous frame.
+    pub fn next_frame_index(&mut self, camera_transform: Transform) -> u32 {
+        if self.last_camera_transform != Some(camera_transform) {
+            self.last_camera_transform = Some(camera_transform);
+            self.reset_accumulation();
+        }
+        let frame_index = self.frame_index;
+        self.frame_index = self.frame_index.saturating_add(1);
+        frame_index
+    }
+
+    /// Copies the last rendered frame back to the CPU, row by row from the top left of the view.
+    ///
+    /// This blocks until the GPU has finished all submitted work.
+    pub fn read_pixels(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<[f32; 4]> {
+        let size = self.texture.size();
+        let bytes_per_pixel = size_of::<[f32; 4]>() as u32;
+        let unpadded_bytes_per_row = size.width * bytes_per_pixel;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("RenderTarget Readback Buffer"),
+            size: u64::from(padded_bytes_per_row) * u64::from(size.height),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("RenderTarget Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+        device.poll(wgpu::PollType::Wait).unwrap();
+
+        let data = slice.get_mapped_range();
+        // the ray tracer writes the bottom row of the view first
+        let pixels = data
+            .chunks_exact(padded_bytes_per_row as usize)
+            .rev()
+            .flat_map(|row| {
+                bytemuck::pod_collect_to_vec::<u8, [f32; 4]>(
+                    &row[..unpadded_bytes_per_row as usize],
+                )
+            })
+            .collect();
+        drop(data);
+        buffer.unmap();
+        pixels
+    }
+}
+
+/// Converts pixels read back with [`RenderTarget::read_pixels`] to 8 bit RGBA, tone mapping them
+/// the same way they are displayed.
+pub fn pixels_to_rgba8(pixels: &[[f32; 4]], tone_mapping: ToneMapping) -> Vec<u8> {
+    pixels
+        .iter()
+        .flat_map(|&[r, g, b, a]| {
+            [
+                tone_mapping.map_channel(r),
+                tone_mapping.map_channel(g),
+                tone_mapping.map_channel(b),
+                a.clamp(0.0, 1.0),
+            ]
+        })
+        .map(|channel| (channel * 255.0).round() as u8)
+        .collect()
 }
 
 fn texture(device: &wgpu::Device, size: wgpu::Extent3d) -> wgpu::Texture {
@@ -77,24 +234,47 @@ fn texture(device: &wgpu::Device, size: wgpu::Extent3d) -> wgpu::Texture {
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Rgba32Float,
-        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        usage: wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     })
 }
 
+fn accumulation_buffer(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("RenderTarget Accumulation Buffer"),
+        size: u64::from(width) * u64::from(height) * size_of::<[f32; 4]>() as u64,
+        usage: wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    })
+}
+
 pub(crate) fn write_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Write RenderTarget Texture Bind Group Layout"),
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::COMPUTE,
-            ty: wgpu::BindingType::StorageTexture {
-                access: wgpu::StorageTextureAccess::WriteOnly,
-                format: wgpu::TextureFormat::Rgba32Float,
-                view_dimension: wgpu::TextureViewDimension::D2,
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rgba32Float,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
             },
-            count: None,
-        }],
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
     })
 }
 
@@ -102,18 +282,33 @@ fn write_bind_group(
     device: &wgpu::Device,
     write_bind_group_layout: &wgpu::BindGroupLayout,
     texture_view: &wgpu::TextureView,
+    accumulation_buffer: &wgpu::Buffer,
 ) -> wgpu::BindGroup {
     device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Write RenderTarget Texture Bind Group"),
         layout: write_bind_group_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::TextureView(texture_view),
-        }],
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: accumulation_buffer.as_entire_binding(),
+            },
+        ],
     })
 }
 
+/// Whether the device can filter the `Rgba32Float` texture that is rendered to.
+fn filterable(device: &wgpu::Device) -> bool {
+    device
+        .features()
+        .contains(wgpu::Features::FLOAT32_FILTERABLE)
+}
+
 pub(crate) fn sample_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let filterable = filterable(device);
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Sample RenderTarget Texture Bind Group Layout"),
         entries: &[
@@ -121,7 +316,7 @@ pub(crate) fn sample_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroup
                 binding: 0,
                 visibility: wgpu::ShaderStages::FRAGMENT,
                 ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    sample_type: wgpu::TextureSampleType::Float { filterable },
                     view_dimension: wgpu::TextureViewDimension::D2,
                     multisampled: false,
                 },
@@ -130,7 +325,11 @@ pub(crate) fn sample_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroup
             wgpu::BindGroupLayoutEntry {
                 binding: 1,
                 visibility: wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                ty: wgpu::BindingType::Sampler(if filterable {
+                    wgpu::SamplerBindingType::Filtering
+                } else {
+                    wgpu::SamplerBindingType::NonFiltering
+                }),
                 count: None,
             },
         ],
@@ -141,14 +340,15 @@ fn sample_bind_group(
     device: &wgpu::Device,
     sample_bind_group_layout: &wgpu::BindGroupLayout,
     texture_view: &wgpu::TextureView,
+    filter: wgpu::FilterMode,
 ) -> wgpu::BindGroup {
     let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
         label: Some("RenderTarget Texture Sampler"),
         address_mode_u: wgpu::AddressMode::ClampToEdge,
         address_mode_v: wgpu::AddressMode::ClampToEdge,
         address_mode_w: wgpu::AddressMode::ClampToEdge,
-        mag_filter: wgpu::FilterMode::Nearest,
-        min_filter: wgpu::FilterMode::Nearest,
+        mag_filter: filter,
+        min_filter: filter,
         ..Default::default()
     });
     device.create_bind_group(&wgpu::BindGroupDescriptor {